@@ -13,15 +13,18 @@ use crate::integrate::{self, DevnetOrchestrator};
 use crate::lsp::run_lsp;
 use crate::runner::run_scripts;
 use crate::runner::DeploymentCache;
+use crate::runner::{fuzz_contracts, FuzzSettings};
 use clarinet_deployments::setup_session_with_deployment;
 use clarinet_deployments::types::{DeploymentGenerationArtifacts, DeploymentSpecification};
 use clarinet_files::{FileLocation, ProjectManifest, ProjectManifestFile, RequirementConfig};
 use clarity_repl::analysis::call_checker::ContractAnalysis;
+use clarity_repl::analysis::graph::build_graph;
 use clarity_repl::clarity::vm::analysis::AnalysisDatabase;
 use clarity_repl::clarity::vm::costs::LimitedCostTracker;
 use clarity_repl::clarity::vm::diagnostic::{Diagnostic, Level};
 use clarity_repl::clarity::vm::types::QualifiedContractIdentifier;
 use clarity_repl::repl::diagnostic::{output_code, output_diagnostic};
+use clarity_repl::repl::formatter::{format_source, FormatterSettings};
 use clarity_repl::{analysis, repl, Terminal};
 use orchestra_types::Chain;
 use orchestra_types::StacksNetwork;
@@ -35,6 +38,10 @@ use clap::{IntoApp, Parser, Subcommand};
 use clap_generate::{Generator, Shell};
 use toml;
 
+use super::diagnostics::{
+    diagnostics_to_json, diagnostics_to_sarif, ContractDiagnostics, OutputFormat, CLARITY_ANALYSIS,
+};
+
 macro_rules! pluralize {
     ($value:expr, $word:expr) => {
         if $value > 1 {
@@ -78,9 +85,18 @@ enum Command {
     /// Execute test suite
     #[clap(name = "test", bin_name = "test")]
     Test(Test),
+    /// Call public functions with random arguments, looking for runtime errors
+    #[clap(name = "fuzz", bin_name = "fuzz")]
+    Fuzz(Fuzz),
     /// Check contracts syntax
     #[clap(name = "check", bin_name = "check")]
     Check(Check),
+    /// Export the graph of the dependencies between contracts, or of the calls between functions
+    #[clap(name = "graph", bin_name = "graph")]
+    Graph(Graph),
+    /// Format contracts source code
+    #[clap(name = "fmt", bin_name = "fmt")]
+    Fmt(Fmt),
     /// Execute Clarinet extension
     #[clap(name = "run", bin_name = "run")]
     Run(Run),
@@ -396,6 +412,78 @@ struct Test {
     pub allow_net: bool,
 }
 
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct Fuzz {
+    /// Contracts to fuzz (defaults to all the contracts of the project)
+    pub contracts: Vec<String>,
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// Number of sequences of calls executed against each contract
+    #[clap(long = "runs", default_value = "100")]
+    pub runs: u32,
+    /// Number of calls in each sequence
+    #[clap(long = "calls", default_value = "20")]
+    pub calls: u32,
+    /// Seed of the random generator, to reproduce a failure
+    #[clap(long = "seed")]
+    pub seed: Option<u64>,
+    /// If specified, use this deployment file
+    #[clap(long = "deployment-plan-path", short = 'p')]
+    pub deployment_plan_path: Option<String>,
+    /// Use on disk deployment plan (prevent updates computing)
+    #[clap(
+        long = "use-on-disk-deployment-plan",
+        short = 'd',
+        conflicts_with = "use-computed-deployment-plan"
+    )]
+    pub use_on_disk_deployment_plan: bool,
+    /// Use computed deployment plan (will overwrite on disk version if any update)
+    #[clap(
+        long = "use-computed-deployment-plan",
+        short = 'c',
+        conflicts_with = "use-on-disk-deployment-plan"
+    )]
+    pub use_computed_deployment_plan: bool,
+}
+
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct Graph {
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// Format of the graph
+    #[clap(long = "format", arg_enum, ignore_case = true, default_value = "dot")]
+    pub format: GraphFormat,
+    /// Graph the calls between functions, instead of the dependencies between contracts
+    #[clap(long = "functions")]
+    pub functions: bool,
+    /// If specified, use this deployment file
+    #[clap(long = "deployment-plan-path", short = 'p')]
+    pub deployment_plan_path: Option<String>,
+    /// Use on disk deployment plan (prevent updates computing)
+    #[clap(
+        long = "use-on-disk-deployment-plan",
+        short = 'd',
+        conflicts_with = "use-computed-deployment-plan"
+    )]
+    pub use_on_disk_deployment_plan: bool,
+    /// Use computed deployment plan (will overwrite on disk version if any update)
+    #[clap(
+        long = "use-computed-deployment-plan",
+        short = 'c',
+        conflicts_with = "use-on-disk-deployment-plan"
+    )]
+    pub use_computed_deployment_plan: bool,
+}
+
+#[derive(clap::ArgEnum, PartialEq, Clone, Copy, Debug)]
+enum GraphFormat {
+    Dot,
+    Mermaid,
+    Json,
+}
+
 #[derive(Parser, PartialEq, Clone, Debug)]
 struct Run {
     /// Script to run
@@ -432,6 +520,24 @@ struct Run {
     pub use_computed_deployment_plan: bool,
 }
 
+#[derive(Parser, PartialEq, Clone, Debug)]
+struct Fmt {
+    /// Path to Clarinet.toml
+    #[clap(long = "manifest-path", short = 'm')]
+    pub manifest_path: Option<String>,
+    /// If specified, format just this one file
+    pub file: Option<String>,
+    /// Check that contracts are formatted, without updating them
+    #[clap(long = "check")]
+    pub check: bool,
+    /// Number of spaces used for indentation
+    #[clap(long = "indentation", default_value = "2")]
+    pub indentation: usize,
+    /// Maximum length of a line
+    #[clap(long = "max-line-length", default_value = "80")]
+    pub max_line_length: usize,
+}
+
 #[derive(Parser, PartialEq, Clone, Debug)]
 struct Check {
     /// Path to Clarinet.toml
@@ -439,6 +545,14 @@ struct Check {
     pub manifest_path: Option<String>,
     /// If specified, perform a simple syntax-check on just this one file
     pub file: Option<String>,
+    /// Format of the diagnostics output
+    #[clap(
+        long = "output-format",
+        arg_enum,
+        ignore_case = true,
+        default_value = "text"
+    )]
+    pub output_format: OutputFormat,
     /// If specified, use this deployment file
     #[clap(long = "deployment-plan-path", short = 'p')]
     pub deployment_plan_path: Option<String>,
@@ -920,10 +1034,6 @@ pub fn main() {
         }
         Command::Check(cmd) if cmd.file.is_some() => {
             let file = cmd.file.unwrap();
-            let mut settings = repl::SessionSettings::default();
-            settings.repl_settings.analysis.enable_all_passes();
-
-            let mut session = repl::Session::new(settings.clone());
             let code = match fs::read_to_string(&file) {
                 Ok(code) => code,
                 _ => {
@@ -931,47 +1041,9 @@ pub fn main() {
                     std::process::exit(1);
                 }
             };
-            let contract_id = QualifiedContractIdentifier::transient();
-            let (ast, mut diagnostics, mut success) = session
-                .interpreter
-                .build_ast(contract_id.clone(), code.clone());
-            let (annotations, mut annotation_diagnostics) =
-                session.interpreter.collect_annotations(&ast, &code);
-            diagnostics.append(&mut annotation_diagnostics);
-
-            let mut contract_analysis = ContractAnalysis::new(
-                contract_id,
-                ast.expressions,
-                LimitedCostTracker::new_free(),
-                settings.repl_settings.clarity_version,
-            );
-            let mut analysis_db = AnalysisDatabase::new(&mut session.interpreter.datastore);
-            let mut analysis_diagnostics = match analysis::run_analysis(
-                &mut contract_analysis,
-                &mut analysis_db,
-                &annotations,
-                &settings.repl_settings.analysis,
-            ) {
-                Ok(diagnostics) => diagnostics,
-                Err(diagnostics) => {
-                    success = false;
-                    diagnostics
-                }
-            };
-            diagnostics.append(&mut analysis_diagnostics);
-
-            let lines = code.lines();
-            let formatted_lines: Vec<String> = lines.map(|l| l.to_string()).collect();
-            for d in diagnostics {
-                for line in output_diagnostic(&d, &file, &formatted_lines) {
-                    println!("{}", line);
-                }
-            }
-
-            if success {
-                println!("{} Syntax of contract successfully checked", green!("✔"),);
-                return;
-            } else {
+            let (report, success) = check_contract_file(&file, &code, cmd.output_format);
+            println!("{}", report);
+            if !success {
                 std::process::exit(1);
             }
         }
@@ -984,6 +1056,40 @@ pub fn main() {
                 cmd.use_computed_deployment_plan,
             );
 
+            let exit_code = match results.success {
+                true => 0,
+                false => 1,
+            };
+
+            if cmd.output_format != OutputFormat::Text {
+                let mut contracts = vec![];
+                for (contract_id, diags) in results.diags.iter() {
+                    // Requirements are not included in `deployment.contracts`, and are ignored
+                    if let Some((_, contract_location)) = deployment.contracts.get(contract_id) {
+                        let interpreter = &results.session.interpreter;
+                        contracts.push(ContractDiagnostics {
+                            path: contract_location
+                                .get_relative_location()
+                                .unwrap_or_else(|_| contract_location.to_string()),
+                            diagnostics: diags
+                                .iter()
+                                .enumerate()
+                                .map(|(i, diag)| {
+                                    let pass = interpreter
+                                        .get_diagnostic_passes(contract_id)
+                                        .and_then(|passes| passes.get(i).copied().flatten())
+                                        .unwrap_or(CLARITY_ANALYSIS);
+                                    (pass, diag)
+                                })
+                                .collect(),
+                        });
+                    }
+                }
+                contracts.sort_by(|a, b| a.path.cmp(&b.path));
+                print_diagnostics_report(cmd.output_format, &contracts, results.success);
+                std::process::exit(exit_code);
+            }
+
             let diags_digest = DiagnosticsDigest::new(&results.diags, &deployment);
             if diags_digest.has_feedbacks() {
                 println!("{}", diags_digest.message);
@@ -1009,10 +1115,6 @@ pub fn main() {
                     pluralize!(diags_digest.contracts_checked, "contract"),
                 );
             }
-            let exit_code = match results.success {
-                true => 0,
-                false => 1,
-            };
 
             if hints_enabled {
                 display_post_check_hint();
@@ -1025,6 +1127,90 @@ pub fn main() {
             }
             std::process::exit(exit_code);
         }
+        Command::Fmt(cmd) => {
+            let files = match cmd.file {
+                Some(file) => vec![file],
+                None => {
+                    let manifest = load_manifest_or_exit(cmd.manifest_path);
+                    let mut files = vec![];
+                    for (_, contract_config) in manifest.contracts.iter() {
+                        let mut contract_location =
+                            match manifest.location.get_project_root_location() {
+                                Ok(location) => location,
+                                Err(e) => {
+                                    println!("{}: {}", red!("error"), e);
+                                    process::exit(1);
+                                }
+                            };
+                        if let Err(e) = contract_location.append_path(&contract_config.path) {
+                            println!("{}: {}", red!("error"), e);
+                            process::exit(1);
+                        }
+                        files.push(contract_location.to_string());
+                    }
+                    files
+                }
+            };
+
+            let settings = FormatterSettings {
+                indentation: cmd.indentation,
+                max_line_length: cmd.max_line_length,
+            };
+            let mut success = true;
+            let mut unformatted = 0;
+            for file in files.iter() {
+                let code = match fs::read_to_string(file) {
+                    Ok(code) => code,
+                    _ => {
+                        println!("{}: unable to read file: '{}'", red!("error"), file);
+                        std::process::exit(1);
+                    }
+                };
+                let formatted = match format_source(&code, &settings) {
+                    Ok(formatted) => formatted,
+                    Err(diagnostics) => {
+                        let lines: Vec<String> = code.lines().map(|l| l.to_string()).collect();
+                        for d in diagnostics {
+                            for line in output_diagnostic(&d, file, &lines) {
+                                println!("{}", line);
+                            }
+                        }
+                        success = false;
+                        continue;
+                    }
+                };
+                if formatted == code {
+                    continue;
+                }
+                unformatted += 1;
+                if cmd.check {
+                    println!("{} {} is not formatted", red!("x"), file);
+                } else if let Err(e) = fs::write(file, formatted) {
+                    println!("{}: unable to write file '{}': {}", red!("error"), file, e);
+                    std::process::exit(1);
+                } else {
+                    println!("{} {}", green!("Formatted"), file);
+                }
+            }
+
+            if cmd.check && unformatted > 0 {
+                println!(
+                    "{} {} not formatted",
+                    red!("x"),
+                    pluralize!(unformatted, "contract")
+                );
+                success = false;
+            } else if success {
+                println!(
+                    "{} {} formatted",
+                    green!("✔"),
+                    pluralize!(files.len(), "contract")
+                );
+            }
+            if !success {
+                process::exit(1);
+            }
+        }
         Command::Test(cmd) => {
             let manifest = load_manifest_or_exit(cmd.manifest_path);
             let deployment_plan_path = cmd.deployment_plan_path.clone();
@@ -1068,6 +1254,54 @@ pub fn main() {
                 process::exit(1)
             }
         }
+        Command::Fuzz(cmd) => {
+            let manifest = load_manifest_or_exit(cmd.manifest_path);
+            let (deployment, _, artifacts) = load_deployment_and_artifacts_or_exit(
+                &manifest,
+                &cmd.deployment_plan_path,
+                cmd.use_on_disk_deployment_plan,
+                cmd.use_computed_deployment_plan,
+            );
+            if !artifacts.success {
+                let diags_digest = DiagnosticsDigest::new(&artifacts.diags, &deployment);
+                println!("{}", diags_digest.message);
+                process::exit(1);
+            }
+
+            let seed = cmd.seed.unwrap_or_else(rand::random);
+            println!("{}: fuzzing with seed {}", yellow!("note"), seed);
+            let settings = FuzzSettings {
+                contracts: cmd.contracts,
+                runs: cmd.runs,
+                calls: cmd.calls,
+                seed,
+            };
+            if !fuzz_contracts(&manifest, &deployment, artifacts, &settings) {
+                process::exit(1);
+            }
+        }
+        Command::Graph(cmd) => {
+            let manifest = load_manifest_or_exit(cmd.manifest_path);
+            let (deployment, _, artifacts) = load_deployment_and_artifacts_or_exit(
+                &manifest,
+                &cmd.deployment_plan_path,
+                cmd.use_on_disk_deployment_plan,
+                cmd.use_computed_deployment_plan,
+            );
+            if !artifacts.success {
+                let diags_digest = DiagnosticsDigest::new(&artifacts.diags, &deployment);
+                println!("{}", diags_digest.message);
+                process::exit(1);
+            }
+
+            let graph = build_graph(&artifacts.asts, cmd.functions);
+            let output = match cmd.format {
+                GraphFormat::Dot => graph.to_dot(),
+                GraphFormat::Mermaid => graph.to_mermaid(),
+                GraphFormat::Json => graph.to_json(),
+            };
+            println!("{}", output);
+        }
         Command::Run(cmd) => {
             let manifest = load_manifest_or_exit(cmd.manifest_path);
 
@@ -1316,7 +1550,8 @@ fn load_deployment_and_artifacts_or_exit(
             );
             match res {
                 Some(Ok(deployment)) => {
-                    println!(
+                    // Printed on stderr, to keep stdout parsable with `--output-format`
+                    eprintln!(
                         "{}: using deployments/default.simnet-plan.yaml",
                         yellow!("note")
                     );
@@ -1608,6 +1843,93 @@ fn execute_changes(changes: Vec<Changes>) -> bool {
     true
 }
 
+/// Analyse the contract `code`, read from `file`, with all the passes, and
+/// report its diagnostics in `output_format`. The check fails when one of the
+/// diagnostics is an error, including the warnings denied by the rules.
+fn check_contract_file(file: &str, code: &str, output_format: OutputFormat) -> (String, bool) {
+    let mut settings = repl::SessionSettings::default();
+    settings.repl_settings.analysis.enable_all_passes();
+
+    let mut session = repl::Session::new(settings.clone());
+    let contract_id = QualifiedContractIdentifier::transient();
+    let (ast, mut diagnostics, mut success) = session
+        .interpreter
+        .build_ast(contract_id.clone(), code.to_string());
+    let (annotations, mut annotation_diagnostics) = session
+        .interpreter
+        .collect_annotations(&ast, &code.to_string());
+    diagnostics.append(&mut annotation_diagnostics);
+
+    let mut contract_analysis = ContractAnalysis::new(
+        contract_id,
+        ast.expressions,
+        LimitedCostTracker::new_free(),
+        settings.repl_settings.clarity_version,
+    );
+    let mut analysis_db = AnalysisDatabase::new(&mut session.interpreter.datastore);
+    let analysis_diagnostics = match analysis::run_analysis(
+        &mut contract_analysis,
+        &mut analysis_db,
+        &annotations,
+        &settings.repl_settings.analysis,
+    ) {
+        Ok(diagnostics) => diagnostics,
+        Err(diagnostics) => {
+            success = false;
+            diagnostics
+        }
+    };
+    let mut passes = vec![CLARITY_ANALYSIS; diagnostics.len()];
+    for (pass, diagnostic) in analysis_diagnostics.into_iter() {
+        passes.push(pass);
+        diagnostics.push(diagnostic);
+    }
+    // Warnings denied by the rules or the annotations are reported as errors
+    if diagnostics
+        .iter()
+        .any(|diagnostic| matches!(diagnostic.level, Level::Error))
+    {
+        success = false;
+    }
+
+    if output_format != OutputFormat::Text {
+        let contracts = vec![ContractDiagnostics {
+            path: file.to_string(),
+            diagnostics: passes.into_iter().zip(diagnostics.iter()).collect(),
+        }];
+        let report = match output_format {
+            OutputFormat::Json => diagnostics_to_json(&contracts, success),
+            _ => diagnostics_to_sarif(&contracts),
+        };
+        return (report, success);
+    }
+
+    let formatted_lines: Vec<String> = code.lines().map(|l| l.to_string()).collect();
+    let mut report = vec![];
+    for d in diagnostics.iter() {
+        report.append(&mut output_diagnostic(d, file, &formatted_lines));
+    }
+    if success {
+        report.push(format!(
+            "{} Syntax of contract successfully checked",
+            green!("✔")
+        ));
+    }
+    (report.join("\n"), success)
+}
+
+fn print_diagnostics_report(
+    output_format: OutputFormat,
+    contracts: &[ContractDiagnostics],
+    success: bool,
+) {
+    match output_format {
+        OutputFormat::Json => println!("{}", diagnostics_to_json(contracts, success)),
+        OutputFormat::Sarif => println!("{}", diagnostics_to_sarif(contracts)),
+        OutputFormat::Text => unreachable!("text diagnostics are printed by the caller"),
+    }
+}
+
 #[allow(dead_code)]
 struct DiagnosticsDigest {
     message: String,
@@ -1827,3 +2149,52 @@ fn display_deploy_hint() {
     );
     display_hint_footer();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    const DENIED_CONTRACT: &str = ";; #[deny(unused)]
+(define-constant ERR_UNUSED (err u402))
+";
+
+    #[test]
+    fn check_contract_file_text() {
+        let (report, success) = check_contract_file(
+            "contract.clar",
+            "(define-read-only (get-one) u1)",
+            OutputFormat::Text,
+        );
+        assert!(success);
+        assert!(report.contains("Syntax of contract successfully checked"));
+
+        let (report, success) =
+            check_contract_file("contract.clar", DENIED_CONTRACT, OutputFormat::Text);
+        assert!(!success);
+        assert!(report.contains("constant 'ERR_UNUSED' is never used"));
+        assert!(!report.contains("successfully checked"));
+    }
+
+    #[test]
+    fn check_contract_file_json() {
+        let (report, success) =
+            check_contract_file("contract.clar", DENIED_CONTRACT, OutputFormat::Json);
+        assert!(!success);
+        let report: Value = serde_json::from_str(&report).unwrap();
+        assert_eq!(report["success"], false);
+        assert_eq!(report["diagnostics"][0]["level"], "error");
+        assert_eq!(report["diagnostics"][0]["pass"], "unused_checker");
+    }
+
+    #[test]
+    fn check_contract_file_sarif() {
+        let (report, success) =
+            check_contract_file("contract.clar", DENIED_CONTRACT, OutputFormat::Sarif);
+        assert!(!success);
+        let log: Value = serde_json::from_str(&report).unwrap();
+        let result = &log["runs"][0]["results"][0];
+        assert_eq!(result["ruleId"], "unused_checker");
+        assert_eq!(result["level"], "error");
+    }
+}
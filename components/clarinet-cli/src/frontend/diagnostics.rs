@@ -0,0 +1,331 @@
+use clarity_repl::clarity::vm::diagnostic::{Diagnostic, Level};
+use clarity_repl::clarity::vm::representations::Span;
+
+#[derive(clap::ArgEnum, PartialEq, Clone, Copy, Debug)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+/// Name given to the analysis of the diagnostics which are not reported by
+/// one of the analysis passes, but by the parser or the Clarity analysis.
+pub const CLARITY_ANALYSIS: &str = "clarity";
+
+/// Diagnostics reported for a contract, along with the path of its source.
+/// Each diagnostic is tagged with the name of the analysis which reported it.
+pub struct ContractDiagnostics<'a> {
+    pub path: String,
+    pub diagnostics: Vec<(&'static str, &'a Diagnostic)>,
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    success: bool,
+    diagnostics: Vec<JsonDiagnostic<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonDiagnostic<'a> {
+    path: &'a str,
+    level: &'static str,
+    pass: &'static str,
+    message: &'a str,
+    span: Option<JsonSpan>,
+    suggestion: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct JsonSpan {
+    start_line: u32,
+    start_column: u32,
+    end_line: u32,
+    end_column: u32,
+}
+
+/// Serializes the diagnostics as a JSON document, listing every diagnostic
+/// (notes included) with the path of the contract, its span, level and the
+/// name of the analysis pass which reported it.
+pub fn diagnostics_to_json(contracts: &[ContractDiagnostics], success: bool) -> String {
+    let mut diagnostics = vec![];
+    for contract in contracts.iter() {
+        for (pass, diagnostic) in contract.diagnostics.iter() {
+            diagnostics.push(JsonDiagnostic {
+                path: &contract.path,
+                level: level_name(&diagnostic.level),
+                pass: *pass,
+                message: &diagnostic.message,
+                span: diagnostic.spans.first().map(|span| JsonSpan {
+                    start_line: span.start_line,
+                    start_column: span.start_column,
+                    end_line: span.end_line,
+                    end_column: span.end_column,
+                }),
+                suggestion: diagnostic.suggestion.as_deref(),
+            });
+        }
+    }
+    let report = JsonReport {
+        success,
+        diagnostics,
+    };
+    serde_json::to_string_pretty(&report).expect("unable to serialize diagnostics")
+}
+
+#[derive(Serialize)]
+struct SarifLog {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    version: &'static str,
+    runs: Vec<SarifRun>,
+}
+
+#[derive(Serialize)]
+struct SarifRun {
+    tool: SarifTool,
+    results: Vec<SarifResult>,
+}
+
+#[derive(Serialize)]
+struct SarifTool {
+    driver: SarifDriver,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifDriver {
+    name: &'static str,
+    version: &'static str,
+    information_uri: &'static str,
+    rules: Vec<SarifRule>,
+}
+
+#[derive(Serialize)]
+struct SarifRule {
+    id: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifResult {
+    rule_id: &'static str,
+    level: &'static str,
+    message: SarifMessage,
+    locations: Vec<SarifLocation>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    related_locations: Vec<SarifLocation>,
+}
+
+#[derive(Serialize)]
+struct SarifMessage {
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifLocation {
+    physical_location: SarifPhysicalLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<SarifMessage>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifPhysicalLocation {
+    artifact_location: SarifArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<SarifRegion>,
+}
+
+#[derive(Serialize)]
+struct SarifArtifactLocation {
+    uri: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SarifRegion {
+    start_line: u32,
+    start_column: u32,
+    end_line: u32,
+    end_column: u32,
+}
+
+/// Serializes the diagnostics as a SARIF 2.1.0 log, to be consumed by code
+/// scanning tools. The notes are reported as the related locations of the
+/// diagnostic they follow.
+pub fn diagnostics_to_sarif(contracts: &[ContractDiagnostics]) -> String {
+    let mut results: Vec<SarifResult> = vec![];
+    let mut rules: Vec<&'static str> = vec![];
+    for contract in contracts.iter() {
+        let mut previous_is_result = false;
+        for (pass, diagnostic) in contract.diagnostics.iter() {
+            let location = sarif_location(&contract.path, diagnostic.spans.first());
+            if let Level::Note = diagnostic.level {
+                if let (true, Some(result)) = (previous_is_result, results.last_mut()) {
+                    result.related_locations.push(SarifLocation {
+                        message: Some(SarifMessage {
+                            text: diagnostic.message.clone(),
+                        }),
+                        ..location
+                    });
+                    continue;
+                }
+            }
+            if !rules.contains(pass) {
+                rules.push(*pass);
+            }
+            let mut text = diagnostic.message.clone();
+            if let Some(ref suggestion) = diagnostic.suggestion {
+                text = format!("{}\n{}", text, suggestion);
+            }
+            results.push(SarifResult {
+                rule_id: *pass,
+                level: level_name(&diagnostic.level),
+                message: SarifMessage { text },
+                locations: vec![location],
+                related_locations: vec![],
+            });
+            previous_is_result = true;
+        }
+    }
+
+    let log = SarifLog {
+        schema: "https://json.schemastore.org/sarif-2.1.0.json",
+        version: "2.1.0",
+        runs: vec![SarifRun {
+            tool: SarifTool {
+                driver: SarifDriver {
+                    name: "clarinet",
+                    version: option_env!("CARGO_PKG_VERSION").unwrap_or("unknown"),
+                    information_uri: "https://github.com/hirosystems/clarinet",
+                    rules: rules.into_iter().map(|id| SarifRule { id }).collect(),
+                },
+            },
+            results,
+        }],
+    };
+    serde_json::to_string_pretty(&log).expect("unable to serialize diagnostics")
+}
+
+fn sarif_location(path: &str, span: Option<&Span>) -> SarifLocation {
+    SarifLocation {
+        physical_location: SarifPhysicalLocation {
+            artifact_location: SarifArtifactLocation {
+                uri: path.to_string(),
+            },
+            // Clarity spans are inclusive, while the end column of SARIF
+            // regions is exclusive. Empty spans, starting on line 0, are omitted.
+            region: span
+                .filter(|span| span.start_line > 0)
+                .map(|span| SarifRegion {
+                    start_line: span.start_line,
+                    start_column: span.start_column,
+                    end_line: span.end_line,
+                    end_column: span.end_column + 1,
+                }),
+        },
+        message: None,
+    }
+}
+
+fn level_name(level: &Level) -> &'static str {
+    match level {
+        Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Note => "note",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn diagnostic(level: Level, message: &str, line: u32) -> Diagnostic {
+        Diagnostic {
+            level,
+            message: message.to_string(),
+            spans: vec![Span {
+                start_line: line,
+                start_column: 5,
+                end_line: line,
+                end_column: 10,
+            }],
+            suggestion: None,
+        }
+    }
+
+    fn diagnostics() -> Vec<Diagnostic> {
+        vec![
+            diagnostic(Level::Warning, "constant 'ERR' is never used", 2),
+            diagnostic(Level::Note, "defined here", 3),
+            diagnostic(Level::Error, "use of unresolved variable 'x'", 4),
+        ]
+    }
+
+    fn contracts(diagnostics: &[Diagnostic]) -> Vec<ContractDiagnostics> {
+        vec![ContractDiagnostics {
+            path: "contracts/test.clar".to_string(),
+            diagnostics: vec![
+                ("unused_checker", &diagnostics[0]),
+                ("unused_checker", &diagnostics[1]),
+                (CLARITY_ANALYSIS, &diagnostics[2]),
+            ],
+        }]
+    }
+
+    #[test]
+    fn serialize_json() {
+        let diagnostics = diagnostics();
+        let report: Value =
+            serde_json::from_str(&diagnostics_to_json(&contracts(&diagnostics), false)).unwrap();
+        assert_eq!(report["success"], false);
+
+        let diagnostics = report["diagnostics"].as_array().unwrap();
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0]["path"], "contracts/test.clar");
+        assert_eq!(diagnostics[0]["level"], "warning");
+        assert_eq!(diagnostics[0]["pass"], "unused_checker");
+        assert_eq!(diagnostics[0]["message"], "constant 'ERR' is never used");
+        assert_eq!(diagnostics[0]["span"]["start_line"], 2);
+        assert_eq!(diagnostics[0]["span"]["end_column"], 10);
+        assert_eq!(diagnostics[1]["level"], "note");
+        assert_eq!(diagnostics[1]["pass"], "unused_checker");
+        assert_eq!(diagnostics[2]["level"], "error");
+        assert_eq!(diagnostics[2]["pass"], "clarity");
+    }
+
+    #[test]
+    fn serialize_sarif() {
+        let diagnostics = diagnostics();
+        let log: Value =
+            serde_json::from_str(&diagnostics_to_sarif(&contracts(&diagnostics))).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+
+        let run = &log["runs"][0];
+        let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0]["id"], "unused_checker");
+        assert_eq!(rules[1]["id"], "clarity");
+
+        // the note is reported as a related location of the warning
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], "unused_checker");
+        assert_eq!(results[0]["level"], "warning");
+        assert_eq!(
+            results[0]["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "contracts/test.clar"
+        );
+        let region = &results[0]["locations"][0]["physicalLocation"]["region"];
+        assert_eq!(region["startLine"], 2);
+        assert_eq!(region["endColumn"], 11);
+        let related_locations = results[0]["relatedLocations"].as_array().unwrap();
+        assert_eq!(related_locations.len(), 1);
+        assert_eq!(related_locations[0]["message"]["text"], "defined here");
+        assert_eq!(results[1]["ruleId"], "clarity");
+        assert_eq!(results[1]["level"], "error");
+        assert!(results[1].get("relatedLocations").is_none());
+    }
+}
@@ -1,4 +1,5 @@
 pub mod cli;
 pub mod dap;
+mod diagnostics;
 #[cfg(feature = "telemetry")]
 mod telemetry;
@@ -129,6 +129,8 @@ Clarinet.test({{
         let contract_config = ContractConfig {
             path: format!("contracts/{}", contract_file_name),
             deployer: None,
+            clarity_version: None,
+            epoch: None,
         };
         let mut contracts_to_add = HashMap::new();
         contracts_to_add.insert(self.contract_name.clone(), contract_config);
@@ -7,7 +7,6 @@ use clarity_repl::clarity::vm::diagnostic::{
 use native_bridge::LspNativeBridge;
 
 use crossbeam_channel::unbounded;
-use std::sync::mpsc;
 use tokio;
 use tower_lsp::lsp_types::{
     Diagnostic, DiagnosticSeverity, Documentation, MarkupContent, MarkupKind, Position, Range,
@@ -35,18 +34,15 @@ async fn do_run_lsp() -> Result<(), String> {
 
     let (notification_tx, notification_rx) = unbounded();
     let (request_tx, request_rx) = unbounded();
-    let (response_tx, response_rx) = mpsc::channel();
     std::thread::spawn(move || {
         crate::utils::nestable_block_on(native_bridge::start_language_server(
             notification_rx,
             request_rx,
-            response_tx,
         ));
     });
 
-    let (service, messages) = LspService::new(|client| {
-        LspNativeBridge::new(client, request_tx, notification_tx, response_rx)
-    });
+    let (service, messages) =
+        LspService::new(|client| LspNativeBridge::new(client, request_tx, notification_tx));
     Server::new(stdin, stdout)
         .interleave(messages)
         .serve(service)
@@ -123,6 +119,281 @@ pub fn insert_text_format_lsp_type_to_tower_lsp_type(
     }
 }
 
+pub fn position_tower_lsp_type_to_lsp_type(
+    position: &tower_lsp::lsp_types::Position,
+) -> clarity_lsp::lsp_types::Position {
+    clarity_lsp::lsp_types::Position {
+        line: position.line,
+        character: position.character,
+    }
+}
+
+pub fn range_lsp_type_to_tower_lsp_type(
+    range: &clarity_lsp::lsp_types::Range,
+) -> tower_lsp::lsp_types::Range {
+    Range {
+        start: Position {
+            line: range.start.line,
+            character: range.start.character,
+        },
+        end: Position {
+            line: range.end.line,
+            character: range.end.character,
+        },
+    }
+}
+
+pub fn range_tower_lsp_type_to_lsp_type(
+    range: &tower_lsp::lsp_types::Range,
+) -> clarity_lsp::lsp_types::Range {
+    clarity_lsp::lsp_types::Range {
+        start: position_tower_lsp_type_to_lsp_type(&range.start),
+        end: position_tower_lsp_type_to_lsp_type(&range.end),
+    }
+}
+
+pub fn location_lsp_type_to_tower_lsp_type(
+    location: &clarity_lsp::lsp_types::Location,
+) -> tower_lsp::lsp_types::Location {
+    tower_lsp::lsp_types::Location {
+        uri: location.uri.clone(),
+        range: range_lsp_type_to_tower_lsp_type(&location.range),
+    }
+}
+
+pub fn text_edit_lsp_type_to_tower_lsp_type(
+    text_edit: &clarity_lsp::lsp_types::TextEdit,
+) -> tower_lsp::lsp_types::TextEdit {
+    tower_lsp::lsp_types::TextEdit {
+        range: range_lsp_type_to_tower_lsp_type(&text_edit.range),
+        new_text: text_edit.new_text.clone(),
+    }
+}
+
+pub fn workspace_edit_lsp_type_to_tower_lsp_type(
+    workspace_edit: &clarity_lsp::lsp_types::WorkspaceEdit,
+) -> tower_lsp::lsp_types::WorkspaceEdit {
+    tower_lsp::lsp_types::WorkspaceEdit {
+        changes: workspace_edit.changes.as_ref().map(|changes| {
+            changes
+                .iter()
+                .map(|(uri, text_edits)| {
+                    (
+                        uri.clone(),
+                        text_edits
+                            .iter()
+                            .map(text_edit_lsp_type_to_tower_lsp_type)
+                            .collect(),
+                    )
+                })
+                .collect()
+        }),
+        ..Default::default()
+    }
+}
+
+pub fn code_action_lsp_type_to_tower_lsp_type(
+    code_action: &clarity_lsp::lsp_types::CodeAction,
+) -> tower_lsp::lsp_types::CodeAction {
+    use clarity_lsp::lsp_types::DiagnosticSeverity as LspDiagnosticSeverity;
+
+    let diagnostic_to_tower_lsp_type =
+        |diagnostic: &clarity_lsp::lsp_types::Diagnostic| Diagnostic {
+            range: range_lsp_type_to_tower_lsp_type(&diagnostic.range),
+            severity: diagnostic.severity.map(|severity| match severity {
+                LspDiagnosticSeverity::ERROR => DiagnosticSeverity::Error,
+                LspDiagnosticSeverity::WARNING => DiagnosticSeverity::Warning,
+                LspDiagnosticSeverity::INFORMATION => DiagnosticSeverity::Information,
+                _ => DiagnosticSeverity::Hint,
+            }),
+            source: diagnostic.source.clone(),
+            message: diagnostic.message.clone(),
+            ..Default::default()
+        };
+
+    tower_lsp::lsp_types::CodeAction {
+        title: code_action.title.clone(),
+        kind: code_action
+            .kind
+            .as_ref()
+            .map(|kind| tower_lsp::lsp_types::CodeActionKind::from(kind.as_str().to_string())),
+        diagnostics: code_action.diagnostics.as_ref().map(|diagnostics| {
+            diagnostics
+                .iter()
+                .map(diagnostic_to_tower_lsp_type)
+                .collect()
+        }),
+        edit: code_action
+            .edit
+            .as_ref()
+            .map(workspace_edit_lsp_type_to_tower_lsp_type),
+        ..Default::default()
+    }
+}
+
+pub fn formatting_options_tower_lsp_type_to_lsp_type(
+    options: &tower_lsp::lsp_types::FormattingOptions,
+) -> clarity_lsp::lsp_types::FormattingOptions {
+    clarity_lsp::lsp_types::FormattingOptions {
+        tab_size: options.tab_size,
+        insert_spaces: options.insert_spaces,
+        ..Default::default()
+    }
+}
+
+pub fn symbol_kind_lsp_type_to_tower_lsp_type(
+    kind: &clarity_lsp::lsp_types::SymbolKind,
+) -> tower_lsp::lsp_types::SymbolKind {
+    match kind {
+        &clarity_lsp::lsp_types::SymbolKind::FUNCTION => tower_lsp::lsp_types::SymbolKind::Function,
+        &clarity_lsp::lsp_types::SymbolKind::VARIABLE => tower_lsp::lsp_types::SymbolKind::Variable,
+        &clarity_lsp::lsp_types::SymbolKind::CONSTANT => tower_lsp::lsp_types::SymbolKind::Constant,
+        &clarity_lsp::lsp_types::SymbolKind::STRUCT => tower_lsp::lsp_types::SymbolKind::Struct,
+        &clarity_lsp::lsp_types::SymbolKind::OBJECT => tower_lsp::lsp_types::SymbolKind::Object,
+        &clarity_lsp::lsp_types::SymbolKind::INTERFACE => {
+            tower_lsp::lsp_types::SymbolKind::Interface
+        }
+        _ => tower_lsp::lsp_types::SymbolKind::Unknown,
+    }
+}
+
+#[allow(deprecated)]
+pub fn document_symbol_lsp_type_to_tower_lsp_type(
+    symbol: &clarity_lsp::lsp_types::DocumentSymbol,
+) -> tower_lsp::lsp_types::DocumentSymbol {
+    tower_lsp::lsp_types::DocumentSymbol {
+        name: symbol.name.clone(),
+        detail: symbol.detail.clone(),
+        kind: symbol_kind_lsp_type_to_tower_lsp_type(&symbol.kind),
+        tags: None,
+        deprecated: None,
+        range: range_lsp_type_to_tower_lsp_type(&symbol.range),
+        selection_range: range_lsp_type_to_tower_lsp_type(&symbol.selection_range),
+        children: symbol.children.as_ref().map(|children| {
+            children
+                .iter()
+                .map(document_symbol_lsp_type_to_tower_lsp_type)
+                .collect()
+        }),
+    }
+}
+
+#[allow(deprecated)]
+pub fn symbol_information_lsp_type_to_tower_lsp_type(
+    symbol: &clarity_lsp::lsp_types::SymbolInformation,
+) -> tower_lsp::lsp_types::SymbolInformation {
+    tower_lsp::lsp_types::SymbolInformation {
+        name: symbol.name.clone(),
+        kind: symbol_kind_lsp_type_to_tower_lsp_type(&symbol.kind),
+        tags: None,
+        deprecated: None,
+        location: location_lsp_type_to_tower_lsp_type(&symbol.location),
+        container_name: symbol.container_name.clone(),
+    }
+}
+
+pub fn hover_lsp_type_to_tower_lsp_type(
+    hover: &clarity_lsp::lsp_types::Hover,
+) -> tower_lsp::lsp_types::Hover {
+    use clarity_lsp::lsp_types::{HoverContents, MarkedString};
+
+    let marked_string_to_tower_lsp_type = |marked_string: &MarkedString| match marked_string {
+        MarkedString::String(value) => tower_lsp::lsp_types::MarkedString::String(value.clone()),
+        MarkedString::LanguageString(language_string) => {
+            tower_lsp::lsp_types::MarkedString::LanguageString(
+                tower_lsp::lsp_types::LanguageString {
+                    language: language_string.language.clone(),
+                    value: language_string.value.clone(),
+                },
+            )
+        }
+    };
+
+    let contents = match &hover.contents {
+        HoverContents::Markup(markup) => {
+            tower_lsp::lsp_types::HoverContents::Markup(MarkupContent {
+                kind: match markup.kind {
+                    clarity_lsp::lsp_types::MarkupKind::Markdown => MarkupKind::Markdown,
+                    clarity_lsp::lsp_types::MarkupKind::PlainText => MarkupKind::PlainText,
+                },
+                value: markup.value.clone(),
+            })
+        }
+        HoverContents::Scalar(marked_string) => tower_lsp::lsp_types::HoverContents::Scalar(
+            marked_string_to_tower_lsp_type(marked_string),
+        ),
+        HoverContents::Array(marked_strings) => tower_lsp::lsp_types::HoverContents::Array(
+            marked_strings
+                .iter()
+                .map(marked_string_to_tower_lsp_type)
+                .collect(),
+        ),
+    };
+
+    tower_lsp::lsp_types::Hover {
+        contents,
+        range: hover.range.as_ref().map(range_lsp_type_to_tower_lsp_type),
+    }
+}
+
+pub fn signature_help_lsp_type_to_tower_lsp_type(
+    signature_help: &clarity_lsp::lsp_types::SignatureHelp,
+) -> tower_lsp::lsp_types::SignatureHelp {
+    use clarity_lsp::lsp_types::ParameterLabel;
+
+    let documentation_to_tower_lsp_type =
+        |documentation: &clarity_lsp::lsp_types::Documentation| match documentation {
+            clarity_lsp::lsp_types::Documentation::String(value) => {
+                Documentation::String(value.clone())
+            }
+            clarity_lsp::lsp_types::Documentation::MarkupContent(markup) => {
+                Documentation::MarkupContent(MarkupContent {
+                    kind: match markup.kind {
+                        clarity_lsp::lsp_types::MarkupKind::Markdown => MarkupKind::Markdown,
+                        clarity_lsp::lsp_types::MarkupKind::PlainText => MarkupKind::PlainText,
+                    },
+                    value: markup.value.clone(),
+                })
+            }
+        };
+
+    tower_lsp::lsp_types::SignatureHelp {
+        signatures: signature_help
+            .signatures
+            .iter()
+            .map(|signature| tower_lsp::lsp_types::SignatureInformation {
+                label: signature.label.clone(),
+                documentation: signature
+                    .documentation
+                    .as_ref()
+                    .map(documentation_to_tower_lsp_type),
+                parameters: signature.parameters.as_ref().map(|parameters| {
+                    parameters
+                        .iter()
+                        .map(|parameter| tower_lsp::lsp_types::ParameterInformation {
+                            label: match &parameter.label {
+                                ParameterLabel::Simple(label) => {
+                                    tower_lsp::lsp_types::ParameterLabel::Simple(label.clone())
+                                }
+                                ParameterLabel::LabelOffsets(offsets) => {
+                                    tower_lsp::lsp_types::ParameterLabel::LabelOffsets(*offsets)
+                                }
+                            },
+                            documentation: parameter
+                                .documentation
+                                .as_ref()
+                                .map(documentation_to_tower_lsp_type),
+                        })
+                        .collect()
+                }),
+                active_parameter: signature.active_parameter,
+            })
+            .collect(),
+        active_signature: signature_help.active_signature,
+        active_parameter: signature_help.active_parameter,
+    }
+}
+
 pub fn clarity_diagnostics_to_tower_lsp_type(
     diagnostics: &mut Vec<ClarityDiagnostic>,
 ) -> Vec<tower_lsp::lsp_types::Diagnostic> {
@@ -172,16 +443,14 @@ fn test_opening_counter_contract_should_return_fresh_analysis() {
     use clarinet_files::FileLocation;
     use clarity_lsp::backend::{LspNotification, LspResponse};
     use crossbeam_channel::unbounded;
-    use std::sync::mpsc::channel;
 
     let (notification_tx, notification_rx) = unbounded();
     let (_request_tx, request_rx) = unbounded();
-    let (response_tx, response_rx) = channel();
+    let (response_tx, response_rx) = unbounded();
     std::thread::spawn(move || {
         crate::utils::nestable_block_on(native_bridge::start_language_server(
             notification_rx,
             request_rx,
-            response_tx,
         ));
     });
 
@@ -194,7 +463,10 @@ fn test_opening_counter_contract_should_return_fresh_analysis() {
         FileLocation::from_path(counter_path)
     };
 
-    let _ = notification_tx.send(LspNotification::ContractOpened(contract_location.clone()));
+    let _ = notification_tx.send((
+        LspNotification::ContractOpened(contract_location.clone()),
+        response_tx.clone(),
+    ));
     let response = response_rx.recv().expect("Unable to get response");
 
     // the counter project should emit 2 warnings and 2 notes coming from counter.clar
@@ -203,7 +475,10 @@ fn test_opening_counter_contract_should_return_fresh_analysis() {
     assert_eq!(diags.len(), 4);
 
     // re-opening this contract should not trigger a full analysis
-    let _ = notification_tx.send(LspNotification::ContractOpened(contract_location));
+    let _ = notification_tx.send((
+        LspNotification::ContractOpened(contract_location),
+        response_tx.clone(),
+    ));
     let response = response_rx.recv().expect("Unable to get response");
     assert_eq!(response, LspResponse::default());
 }
@@ -213,16 +488,14 @@ fn test_opening_counter_manifest_should_return_fresh_analysis() {
     use clarinet_files::FileLocation;
     use clarity_lsp::backend::{LspNotification, LspResponse};
     use crossbeam_channel::unbounded;
-    use std::sync::mpsc::channel;
 
     let (notification_tx, notification_rx) = unbounded();
     let (_request_tx, request_rx) = unbounded();
-    let (response_tx, response_rx) = channel();
+    let (response_tx, response_rx) = unbounded();
     std::thread::spawn(move || {
         crate::utils::nestable_block_on(native_bridge::start_language_server(
             notification_rx,
             request_rx,
-            response_tx,
         ));
     });
 
@@ -234,7 +507,10 @@ fn test_opening_counter_manifest_should_return_fresh_analysis() {
         FileLocation::from_path(manifest_path)
     };
 
-    let _ = notification_tx.send(LspNotification::ManifestOpened(manifest_location.clone()));
+    let _ = notification_tx.send((
+        LspNotification::ManifestOpened(manifest_location.clone()),
+        response_tx.clone(),
+    ));
     let response = response_rx.recv().expect("Unable to get response");
 
     // the counter project should emit 2 warnings and 2 notes coming from counter.clar
@@ -243,7 +519,10 @@ fn test_opening_counter_manifest_should_return_fresh_analysis() {
     assert_eq!(diags.len(), 4);
 
     // re-opening this manifest should not trigger a full analysis
-    let _ = notification_tx.send(LspNotification::ManifestOpened(manifest_location));
+    let _ = notification_tx.send((
+        LspNotification::ManifestOpened(manifest_location),
+        response_tx.clone(),
+    ));
     let response = response_rx.recv().expect("Unable to get response");
     assert_eq!(response, LspResponse::default());
 }
@@ -253,16 +532,14 @@ fn test_opening_simple_nft_manifest_should_return_fresh_analysis() {
     use clarinet_files::FileLocation;
     use clarity_lsp::backend::LspNotification;
     use crossbeam_channel::unbounded;
-    use std::sync::mpsc::channel;
 
     let (notification_tx, notification_rx) = unbounded();
     let (_request_tx, request_rx) = unbounded();
-    let (response_tx, response_rx) = channel();
+    let (response_tx, response_rx) = unbounded();
     std::thread::spawn(move || {
         crate::utils::nestable_block_on(native_bridge::start_language_server(
             notification_rx,
             request_rx,
-            response_tx,
         ));
     });
 
@@ -271,9 +548,10 @@ fn test_opening_simple_nft_manifest_should_return_fresh_analysis() {
     manifest_location.push("simple-nft");
     manifest_location.push("Clarinet.toml");
 
-    let _ = notification_tx.send(LspNotification::ManifestOpened(FileLocation::from_path(
-        manifest_location,
-    )));
+    let _ = notification_tx.send((
+        LspNotification::ManifestOpened(FileLocation::from_path(manifest_location)),
+        response_tx.clone(),
+    ));
     let response = response_rx.recv().expect("Unable to get response");
 
     // the counter project should emit 2 warnings and 2 notes coming from counter.clar
@@ -282,3 +560,730 @@ fn test_opening_simple_nft_manifest_should_return_fresh_analysis() {
     let (_, diags_1) = &response.aggregated_diagnostics[1];
     assert_eq!(diags_0.len().max(diags_1.len()), 8);
 }
+
+#[test]
+fn test_failing_notification_should_still_get_a_response() {
+    use clarinet_files::FileLocation;
+    use clarity_lsp::backend::LspNotification;
+    use clarity_lsp::lsp_types::MessageType;
+    use crossbeam_channel::unbounded;
+
+    let (notification_tx, notification_rx) = unbounded();
+    let (_request_tx, request_rx) = unbounded();
+    let (response_tx, response_rx) = unbounded();
+    std::thread::spawn(move || {
+        crate::utils::nestable_block_on(native_bridge::start_language_server(
+            notification_rx,
+            request_rx,
+        ));
+    });
+
+    // a contract living outside of any project can't be analysed
+    let mut contract_path = std::env::temp_dir();
+    contract_path.push("orphan.clar");
+    let _ = notification_tx.send((
+        LspNotification::ContractOpened(FileLocation::from_path(contract_path)),
+        response_tx.clone(),
+    ));
+    let response = response_rx
+        .recv_timeout(std::time::Duration::from_secs(10))
+        .expect("Unable to get response");
+    let (level, _) = response.notification.expect("Unable to get notification");
+    assert_eq!(level, MessageType::ERROR);
+}
+
+#[test]
+fn test_counter_contract_definitions_and_references() {
+    use clarinet_files::FileLocation;
+    use clarity_lsp::backend::{LspNotification, LspRequest};
+    use clarity_lsp::lsp_types::Position;
+    use crossbeam_channel::unbounded;
+
+    let (notification_tx, notification_rx) = unbounded();
+    let (request_tx, request_rx) = unbounded();
+    let (response_tx, response_rx) = unbounded();
+    std::thread::spawn(move || {
+        crate::utils::nestable_block_on(native_bridge::start_language_server(
+            notification_rx,
+            request_rx,
+        ));
+    });
+
+    let contract_location = {
+        let mut counter_path = std::env::current_dir().expect("Unable to get current dir");
+        counter_path.push("examples");
+        counter_path.push("counter");
+        counter_path.push("contracts");
+        counter_path.push("counter.clar");
+        FileLocation::from_path(counter_path)
+    };
+
+    let _ = notification_tx.send((
+        LspNotification::ContractOpened(contract_location.clone()),
+        response_tx.clone(),
+    ));
+    let _ = response_rx.recv().expect("Unable to get response");
+
+    // `(var-get counter)` in `increment` should lead to `(define-data-var counter uint u1)`
+    let _ = request_tx.send((
+        LspRequest::GetDefinition(
+            contract_location.clone(),
+            Position {
+                line: 6,
+                character: 37,
+            },
+        ),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    assert_eq!(response.locations.len(), 1);
+    assert_eq!(response.locations[0].range.start.line, 3);
+    assert_eq!(response.locations[0].range.start.character, 17);
+
+    // the data-var is declared once, read 3 times and written twice
+    let _ = request_tx.send((
+        LspRequest::GetReferences(
+            contract_location.clone(),
+            Position {
+                line: 3,
+                character: 18,
+            },
+            true,
+        ),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    assert_eq!(response.locations.len(), 6);
+
+    let _ = request_tx.send((
+        LspRequest::GetReferences(
+            contract_location,
+            Position {
+                line: 3,
+                character: 18,
+            },
+            false,
+        ),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    assert_eq!(response.locations.len(), 5);
+}
+
+#[test]
+fn test_counter_contract_hover() {
+    use clarinet_files::FileLocation;
+    use clarity_lsp::backend::{LspNotification, LspRequest};
+    use clarity_lsp::lsp_types::{HoverContents, Position};
+    use crossbeam_channel::unbounded;
+
+    let (notification_tx, notification_rx) = unbounded();
+    let (request_tx, request_rx) = unbounded();
+    let (response_tx, response_rx) = unbounded();
+    std::thread::spawn(move || {
+        crate::utils::nestable_block_on(native_bridge::start_language_server(
+            notification_rx,
+            request_rx,
+        ));
+    });
+
+    let contract_location = {
+        let mut counter_path = std::env::current_dir().expect("Unable to get current dir");
+        counter_path.push("examples");
+        counter_path.push("counter");
+        counter_path.push("contracts");
+        counter_path.push("counter.clar");
+        FileLocation::from_path(counter_path)
+    };
+
+    let _ = notification_tx.send((
+        LspNotification::ContractOpened(contract_location.clone()),
+        response_tx.clone(),
+    ));
+    let _ = response_rx.recv().expect("Unable to get response");
+
+    // hovering `var-get` should display the native function documentation
+    let _ = request_tx.send((
+        LspRequest::GetHover(
+            contract_location.clone(),
+            Position {
+                line: 6,
+                character: 29,
+            },
+        ),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    match response.hover.expect("Unable to get hover").contents {
+        HoverContents::Markup(markup) => assert!(markup.value.contains("(var-get var-name)")),
+        _ => panic!("Unexpected hover contents"),
+    }
+
+    // hovering `counter` should display the type of the data-var
+    let _ = request_tx.send((
+        LspRequest::GetHover(
+            contract_location,
+            Position {
+                line: 6,
+                character: 37,
+            },
+        ),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    match response.hover.expect("Unable to get hover").contents {
+        HoverContents::Markup(markup) => {
+            assert!(markup.value.contains("(define-data-var counter uint)"))
+        }
+        _ => panic!("Unexpected hover contents"),
+    }
+}
+
+#[test]
+fn test_counter_contract_incremental_analysis() {
+    use clarinet_files::FileLocation;
+    use clarity_lsp::backend::LspNotification;
+    use clarity_repl::clarity::vm::diagnostic::Level;
+    use crossbeam_channel::unbounded;
+
+    let (notification_tx, notification_rx) = unbounded();
+    let (_request_tx, request_rx) = unbounded();
+    let (response_tx, response_rx) = unbounded();
+    std::thread::spawn(move || {
+        crate::utils::nestable_block_on(native_bridge::start_language_server(
+            notification_rx,
+            request_rx,
+        ));
+    });
+
+    let contract_location = {
+        let mut counter_path = std::env::current_dir().expect("Unable to get current dir");
+        counter_path.push("examples");
+        counter_path.push("counter");
+        counter_path.push("contracts");
+        counter_path.push("counter.clar");
+        FileLocation::from_path(counter_path)
+    };
+
+    let _ = notification_tx.send((
+        LspNotification::ContractOpened(contract_location.clone()),
+        response_tx.clone(),
+    ));
+    let _ = response_rx.recv().expect("Unable to get response");
+
+    // unsaved edits, with a typo and an unclosed list, should be reported
+    let source = "(define-data-var counter uint u1)\n(define-read-only (read-counter)\n    (ok (var-get countr))\n(define-public (increment";
+    let _ = notification_tx.send((
+        LspNotification::ContractEdited(contract_location.clone(), source.to_string()),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    let (_, diags) = response
+        .aggregated_diagnostics
+        .iter()
+        .find(|(location, _)| location.eq(&contract_location))
+        .expect("Unable to get diagnostics");
+    assert!(diags.iter().any(|diag| diag.level == Level::Error));
+
+    // fixing the contract should clear the errors
+    let source = "(define-data-var counter uint u1)\n(define-read-only (read-counter)\n    (ok (var-get counter)))\n";
+    let _ = notification_tx.send((
+        LspNotification::ContractEdited(contract_location.clone(), source.to_string()),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    let (_, diags) = response
+        .aggregated_diagnostics
+        .iter()
+        .find(|(location, _)| location.eq(&contract_location))
+        .expect("Unable to get diagnostics");
+    assert!(diags.iter().all(|diag| diag.level != Level::Error));
+}
+
+#[test]
+fn test_simple_nft_incremental_analysis_only_affects_dependents() {
+    use clarinet_files::FileLocation;
+    use clarity_lsp::state::{build_state, ProtocolState};
+
+    let project_path = {
+        let mut project_path = std::env::current_dir().expect("Unable to get current dir");
+        project_path.push("examples");
+        project_path.push("simple-nft");
+        project_path
+    };
+    let manifest_location = FileLocation::from_path(project_path.join("Clarinet.toml"));
+    let nft_location = FileLocation::from_path(project_path.join("contracts/simple-nft.clar"));
+    let trait_location = FileLocation::from_path(
+        project_path.join("contracts/external/sip-009-nft-trait-standard.clar"),
+    );
+
+    let mut protocol_state = ProtocolState::new();
+    crate::utils::nestable_block_on(build_state(&manifest_location, &mut protocol_state, None))
+        .expect("Unable to build state");
+
+    // the trait is a dependency of simple-nft, editing simple-nft should leave it untouched
+    let source = nft_location
+        .read_content_as_utf8()
+        .expect("Unable to read contract");
+    let analysed = protocol_state
+        .run_incremental_analysis(&nft_location, source)
+        .expect("Unable to run analysis");
+    assert_eq!(analysed, vec![nft_location.clone()]);
+
+    // editing the trait should re-analyse simple-nft, which implements it
+    let source = trait_location
+        .read_content_as_utf8()
+        .expect("Unable to read contract");
+    let analysed = protocol_state
+        .run_incremental_analysis(&trait_location, source)
+        .expect("Unable to run analysis");
+    assert_eq!(analysed, vec![trait_location, nft_location]);
+}
+
+#[test]
+fn test_incremental_analysis_applies_contract_epoch() {
+    use clarinet_files::FileLocation;
+    use clarity_lsp::state::{build_state, EditorState, ProtocolState};
+    use clarity_repl::clarity::vm::diagnostic::Level;
+
+    // `stx-account` is only available from epoch 2.1, with Clarity 2
+    let mut project_path = std::env::temp_dir();
+    project_path.push("clarinet-lsp-contract-epoch");
+    std::fs::create_dir_all(project_path.join("contracts")).unwrap();
+    std::fs::create_dir_all(project_path.join("settings")).unwrap();
+    let mut counter_path = std::env::current_dir().expect("Unable to get current dir");
+    counter_path.push("examples/counter/settings/Devnet.toml");
+    std::fs::copy(counter_path, project_path.join("settings/Devnet.toml")).unwrap();
+    std::fs::write(
+        project_path.join("Clarinet.toml"),
+        "[project]\nname = \"epochs\"\n\n[contracts.legacy]\npath = \"contracts/legacy.clar\"\nepoch = \"Epoch20\"\n\n[contracts.current]\npath = \"contracts/current.clar\"\nepoch = \"Epoch21\"\n",
+    )
+    .unwrap();
+    let source = "(define-read-only (get-locked)\n    (get locked (stx-account tx-sender)))\n";
+    std::fs::write(project_path.join("contracts/legacy.clar"), source).unwrap();
+    std::fs::write(project_path.join("contracts/current.clar"), source).unwrap();
+
+    let manifest_location = FileLocation::from_path(project_path.join("Clarinet.toml"));
+    let legacy_location = FileLocation::from_path(project_path.join("contracts/legacy.clar"));
+    let current_location = FileLocation::from_path(project_path.join("contracts/current.clar"));
+
+    let mut protocol_state = ProtocolState::new();
+    crate::utils::nestable_block_on(build_state(&manifest_location, &mut protocol_state, None))
+        .expect("Unable to build state");
+    for location in [&legacy_location, &current_location] {
+        protocol_state
+            .run_incremental_analysis(location, source.to_string())
+            .expect("Unable to run analysis");
+    }
+
+    let mut editor_state = EditorState::new();
+    editor_state.index_protocol(manifest_location, protocol_state);
+    let (aggregated_diagnostics, _) = editor_state.get_aggregated_diagnostics();
+    let has_errors = |contract_location: &FileLocation| {
+        let (_, diags) = aggregated_diagnostics
+            .iter()
+            .find(|(location, _)| location.eq(contract_location))
+            .expect("Unable to get diagnostics");
+        diags.iter().any(|diag| diag.level == Level::Error)
+    };
+    // The contract pinned to epoch 2.0 is analysed with Clarity 1
+    assert!(has_errors(&legacy_location));
+    assert!(!has_errors(&current_location));
+}
+
+#[test]
+fn test_formatting() {
+    use clarity_lsp::backend::LspRequest;
+    use clarity_lsp::lsp_types::FormattingOptions;
+    use crossbeam_channel::unbounded;
+
+    let (_notification_tx, notification_rx) = unbounded();
+    let (request_tx, request_rx) = unbounded();
+    let (response_tx, response_rx) = unbounded();
+    std::thread::spawn(move || {
+        crate::utils::nestable_block_on(native_bridge::start_language_server(
+            notification_rx,
+            request_rx,
+        ));
+    });
+
+    let options = FormattingOptions {
+        tab_size: 2,
+        insert_spaces: true,
+        ..Default::default()
+    };
+
+    let source = "(define-read-only (read-counter)\n(ok    (var-get counter)))";
+    let _ = request_tx.send((
+        LspRequest::GetFormatting(source.to_string(), options.clone()),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    let text_edits = response.text_edits.expect("Unable to get text edits");
+    assert_eq!(text_edits.len(), 1);
+    assert_eq!(text_edits[0].range.end.line, 1);
+    assert_eq!(text_edits[0].range.end.character, 26);
+    assert_eq!(
+        text_edits[0].new_text,
+        "(define-read-only (read-counter)\n  (ok (var-get counter)))\n"
+    );
+
+    // sources that can't be parsed are not formatted
+    let _ = request_tx.send((
+        LspRequest::GetFormatting("(ok (var-get counter)".to_string(), options),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    assert!(response.text_edits.is_none());
+}
+
+#[test]
+fn test_counter_contract_symbols() {
+    use clarinet_files::FileLocation;
+    use clarity_lsp::backend::{LspNotification, LspRequest};
+    use clarity_lsp::lsp_types::SymbolKind;
+    use crossbeam_channel::unbounded;
+
+    let (notification_tx, notification_rx) = unbounded();
+    let (request_tx, request_rx) = unbounded();
+    let (response_tx, response_rx) = unbounded();
+    std::thread::spawn(move || {
+        crate::utils::nestable_block_on(native_bridge::start_language_server(
+            notification_rx,
+            request_rx,
+        ));
+    });
+
+    let contract_location = {
+        let mut counter_path = std::env::current_dir().expect("Unable to get current dir");
+        counter_path.push("examples");
+        counter_path.push("counter");
+        counter_path.push("contracts");
+        counter_path.push("counter.clar");
+        FileLocation::from_path(counter_path)
+    };
+
+    let _ = notification_tx.send((
+        LspNotification::ContractOpened(contract_location.clone()),
+        response_tx.clone(),
+    ));
+    let _ = response_rx.recv().expect("Unable to get response");
+
+    // the outline follows the order of the definitions
+    let _ = request_tx.send((
+        LspRequest::GetDocumentSymbols(contract_location),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    let symbols = response
+        .document_symbols
+        .iter()
+        .map(|symbol| (symbol.name.as_str(), symbol.kind))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        symbols,
+        vec![
+            ("counter", SymbolKind::VARIABLE),
+            ("increment", SymbolKind::FUNCTION),
+            ("decrement", SymbolKind::FUNCTION),
+            ("read-counter", SymbolKind::FUNCTION),
+        ]
+    );
+
+    let _ = request_tx.send((
+        LspRequest::GetWorkspaceSymbols("COUNTER".to_string()),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    let mut names = response
+        .workspace_symbols
+        .iter()
+        .map(|symbol| symbol.name.as_str())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, vec!["counter", "read-counter"]);
+}
+
+#[test]
+fn test_counter_contract_rename() {
+    use clarinet_files::FileLocation;
+    use clarity_lsp::backend::{LspNotification, LspRequest};
+    use clarity_lsp::lsp_types::{MessageType, Position};
+    use crossbeam_channel::unbounded;
+
+    let (notification_tx, notification_rx) = unbounded();
+    let (request_tx, request_rx) = unbounded();
+    let (response_tx, response_rx) = unbounded();
+    std::thread::spawn(move || {
+        crate::utils::nestable_block_on(native_bridge::start_language_server(
+            notification_rx,
+            request_rx,
+        ));
+    });
+
+    let contract_location = {
+        let mut counter_path = std::env::current_dir().expect("Unable to get current dir");
+        counter_path.push("examples");
+        counter_path.push("counter");
+        counter_path.push("contracts");
+        counter_path.push("counter.clar");
+        FileLocation::from_path(counter_path)
+    };
+
+    let _ = notification_tx.send((
+        LspNotification::ContractOpened(contract_location.clone()),
+        response_tx.clone(),
+    ));
+    let _ = response_rx.recv().expect("Unable to get response");
+
+    // `counter` in `(define-data-var counter uint u1)`
+    let position = Position {
+        line: 3,
+        character: 18,
+    };
+    let _ = request_tx.send((
+        LspRequest::PrepareRename(contract_location.clone(), position),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    let range = response.rename_range.expect("Unable to prepare rename");
+    assert_eq!(range.start.character, 17);
+    assert_eq!(range.end.character, 24);
+
+    // the declaration and the 5 usages are renamed
+    let _ = request_tx.send((
+        LspRequest::Rename(contract_location.clone(), position, "total".to_string()),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    let changes = response
+        .workspace_edit
+        .and_then(|workspace_edit| workspace_edit.changes)
+        .expect("Unable to rename");
+    assert_eq!(changes.len(), 1);
+    let text_edits = changes.values().next().unwrap();
+    assert_eq!(text_edits.len(), 6);
+    assert!(text_edits.iter().all(|edit| edit.new_text == "total"));
+
+    // `increment` is already defined
+    let _ = request_tx.send((
+        LspRequest::Rename(contract_location.clone(), position, "increment".to_string()),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    assert_eq!(response.workspace_edit, None);
+    assert_eq!(response.notification.unwrap().0, MessageType::ERROR);
+
+    // `new-val` is bound in `increment` and `decrement`
+    let _ = request_tx.send((
+        LspRequest::Rename(contract_location.clone(), position, "new-val".to_string()),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    assert_eq!(response.workspace_edit, None);
+
+    // `var-get` is a native function
+    let _ = request_tx.send((
+        LspRequest::Rename(contract_location.clone(), position, "var-get".to_string()),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    assert_eq!(response.workspace_edit, None);
+
+    // local bindings can't be renamed: `new-val` in `increment`
+    let _ = request_tx.send((
+        LspRequest::PrepareRename(
+            contract_location,
+            Position {
+                line: 6,
+                character: 12,
+            },
+        ),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    assert_eq!(response.rename_range, None);
+}
+
+#[test]
+fn test_counter_contract_signature_help() {
+    use clarinet_files::FileLocation;
+    use clarity_lsp::backend::{LspNotification, LspRequest};
+    use clarity_lsp::lsp_types::{ParameterLabel, Position};
+    use crossbeam_channel::unbounded;
+
+    let (notification_tx, notification_rx) = unbounded();
+    let (request_tx, request_rx) = unbounded();
+    let (response_tx, response_rx) = unbounded();
+    std::thread::spawn(move || {
+        crate::utils::nestable_block_on(native_bridge::start_language_server(
+            notification_rx,
+            request_rx,
+        ));
+    });
+
+    let contract_location = {
+        let mut counter_path = std::env::current_dir().expect("Unable to get current dir");
+        counter_path.push("examples");
+        counter_path.push("counter");
+        counter_path.push("contracts");
+        counter_path.push("counter.clar");
+        FileLocation::from_path(counter_path)
+    };
+
+    let _ = notification_tx.send((
+        LspNotification::ContractOpened(contract_location.clone()),
+        response_tx.clone(),
+    ));
+    let _ = response_rx.recv().expect("Unable to get response");
+
+    // unsaved changes, with the cursor at the end of the last line
+    let get_signature_help = |source: &str| {
+        let source = format!("(define-data-var counter uint u1)\n{}", source);
+        let _ = request_tx.send((
+            LspRequest::GetSignatureHelp(
+                contract_location.clone(),
+                Position {
+                    line: 1,
+                    character: source.lines().last().unwrap().len() as u32,
+                },
+                Some(source),
+            ),
+            response_tx.clone(),
+        ));
+        response_rx
+            .recv()
+            .expect("Unable to get response")
+            .signature_help
+    };
+
+    let signature_help = get_signature_help("(increment ").expect("Unable to get signature");
+    let signature = &signature_help.signatures[0];
+    assert_eq!(signature.label, "(increment (step uint))");
+    assert_eq!(
+        signature.parameters.as_ref().unwrap()[0].label,
+        ParameterLabel::LabelOffsets([11, 22])
+    );
+    assert_eq!(signature_help.active_parameter, Some(0));
+
+    let signature_help = get_signature_help("(contract-call? .counter decrement u")
+        .expect("Unable to get signature");
+    assert_eq!(
+        signature_help.signatures[0].label,
+        "(decrement (step uint))"
+    );
+    assert_eq!(signature_help.active_parameter, Some(0));
+
+    // the active parameter of a variadic function is the last one
+    let signature_help =
+        get_signature_help("(+ u1 u2 (var-get counter) ").expect("Unable to get signature");
+    assert!(signature_help.signatures[0].label.starts_with("(+ "));
+    assert_eq!(signature_help.active_parameter, Some(1));
+
+    assert_eq!(get_signature_help("(read-counter) "), None);
+}
+
+#[test]
+fn test_simple_nft_contract_code_actions() {
+    use clarinet_files::FileLocation;
+    use clarity_lsp::backend::{LspNotification, LspRequest};
+    use clarity_lsp::lsp_types::{Position, Range};
+    use crossbeam_channel::unbounded;
+
+    let (notification_tx, notification_rx) = unbounded();
+    let (request_tx, request_rx) = unbounded();
+    let (response_tx, response_rx) = unbounded();
+    std::thread::spawn(move || {
+        crate::utils::nestable_block_on(native_bridge::start_language_server(
+            notification_rx,
+            request_rx,
+        ));
+    });
+
+    let contract_location = {
+        let mut contract_path = std::env::current_dir().expect("Unable to get current dir");
+        contract_path.push("examples");
+        contract_path.push("simple-nft");
+        contract_path.push("contracts");
+        contract_path.push("simple-nft.clar");
+        FileLocation::from_path(contract_path)
+    };
+
+    let _ = notification_tx.send((
+        LspNotification::ContractOpened(contract_location.clone()),
+        response_tx.clone(),
+    ));
+    let _ = response_rx.recv().expect("Unable to get response");
+
+    // `id` and `recipient` are unchecked in `(nft-transfer? nft id sender recipient)`
+    let _ = request_tx.send((
+        LspRequest::GetCodeActions(
+            contract_location,
+            Range {
+                start: Position {
+                    line: 28,
+                    character: 0,
+                },
+                end: Position {
+                    line: 28,
+                    character: 43,
+                },
+            },
+        ),
+        response_tx.clone(),
+    ));
+    let response = response_rx.recv().expect("Unable to get response");
+    let code_actions = response
+        .code_actions
+        .iter()
+        .map(|code_action| {
+            let text_edits = code_action
+                .edit
+                .as_ref()
+                .and_then(|edit| edit.changes.as_ref())
+                .and_then(|changes| changes.values().next().cloned())
+                .unwrap_or_default();
+            (
+                code_action.title.as_str(),
+                text_edits
+                    .iter()
+                    .map(|text_edit| (text_edit.range.start, text_edit.new_text.clone()))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let statement_start = Position {
+        line: 27,
+        character: 4,
+    };
+    assert_eq!(
+        code_actions,
+        vec![
+            (
+                "Allow unchecked data with an annotation",
+                vec![(
+                    Position {
+                        line: 28,
+                        character: 0,
+                    },
+                    "    ;; #[allow(unchecked_data)]\n".to_string()
+                )]
+            ),
+            (
+                "Check `id` with asserts!",
+                vec![(
+                    statement_start,
+                    "(asserts! (> id u0) (err u0))\n    ".to_string()
+                )]
+            ),
+            (
+                "Check `recipient` with asserts!",
+                vec![(
+                    statement_start,
+                    "(asserts! (is-eq recipient tx-sender) (err u0))\n    ".to_string()
+                )]
+            ),
+        ]
+    );
+}
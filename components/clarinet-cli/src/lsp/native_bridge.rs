@@ -1,23 +1,39 @@
 use super::utils;
 
-use crate::lsp::{clarity_diagnostics_to_tower_lsp_type, completion_item_type_to_tower_lsp_type};
+use crate::lsp::{
+    clarity_diagnostics_to_tower_lsp_type, code_action_lsp_type_to_tower_lsp_type,
+    completion_item_type_to_tower_lsp_type, document_symbol_lsp_type_to_tower_lsp_type,
+    formatting_options_tower_lsp_type_to_lsp_type, hover_lsp_type_to_tower_lsp_type,
+    location_lsp_type_to_tower_lsp_type, position_tower_lsp_type_to_lsp_type,
+    range_lsp_type_to_tower_lsp_type, range_tower_lsp_type_to_lsp_type,
+    signature_help_lsp_type_to_tower_lsp_type, symbol_information_lsp_type_to_tower_lsp_type,
+    text_edit_lsp_type_to_tower_lsp_type, workspace_edit_lsp_type_to_tower_lsp_type,
+};
 use clarity_lsp::backend::{
     process_notification, process_request, LspNotification, LspRequest, LspResponse,
 };
 use clarity_lsp::state::EditorState;
-use crossbeam_channel::{Receiver as MultiplexableReceiver, Select, Sender as MultiplexableSender};
+use crossbeam_channel::{
+    bounded, Receiver as MultiplexableReceiver, Select, Sender as MultiplexableSender,
+};
 use serde_json::Value;
-use std::sync::mpsc::{Receiver, Sender};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::sync::Mutex;
-use tower_lsp::jsonrpc::Result;
+use std::time::Duration;
+use tower_lsp::jsonrpc::{Error, Result};
 use tower_lsp::lsp_types::{
+    CodeActionOrCommand, CodeActionParams, CodeActionProviderCapability, CodeActionResponse,
     CompletionOptions, CompletionParams, CompletionResponse, DeclarationCapability,
     DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
-    DidSaveTextDocumentParams, ExecuteCommandParams, HoverProviderCapability, InitializeParams,
-    InitializeResult, InitializedParams, MessageType, ServerCapabilities,
-    TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
-    TextDocumentSyncSaveOptions, Url,
+    DidSaveTextDocumentParams, DocumentFormattingParams, DocumentSymbolParams,
+    DocumentSymbolResponse, ExecuteCommandParams, GotoDefinitionParams, GotoDefinitionResponse,
+    Hover, HoverParams, HoverProviderCapability, InitializeParams, InitializeResult,
+    InitializedParams, Location, MessageType, OneOf, PrepareRenameResponse, ReferenceParams,
+    RenameOptions, RenameParams, ServerCapabilities, SignatureHelp, SignatureHelpOptions,
+    SignatureHelpParams, SymbolInformation, TextDocumentPositionParams, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextDocumentSyncOptions, TextDocumentSyncSaveOptions, TextEdit, Url,
+    WorkspaceEdit, WorkspaceSymbolParams,
 };
 use tower_lsp::{async_trait, Client, LanguageServer};
 
@@ -31,11 +47,19 @@ use tower_lsp::{async_trait, Client, LanguageServer};
 //      - if indexed in `Clarinet.toml`:
 //      - if not indexed:
 // - Clarinet.toml file saved
+// - .clar files edited (not saved yet): debounced, only the edited contract and
+//   its dependents are re-analysed.
+
+const DID_CHANGE_DEBOUNCE_MS: u64 = 300;
+
+// Every message comes with its own reply channel: handlers can be running concurrently
+// (e.g. a debounced `did_change` and a completion request), and each of them must get
+// the response to its own message.
+pub type LspResponseSender = MultiplexableSender<LspResponse>;
 
 pub async fn start_language_server(
-    notification_rx: MultiplexableReceiver<LspNotification>,
-    request_rx: MultiplexableReceiver<LspRequest>,
-    response_tx: Sender<LspResponse>,
+    notification_rx: MultiplexableReceiver<(LspNotification, LspResponseSender)>,
+    request_rx: MultiplexableReceiver<(LspRequest, LspResponseSender)>,
 ) {
     let mut editor_state = EditorState::new();
 
@@ -47,18 +71,21 @@ pub async fn start_language_server(
         let oper = sel.select();
         match oper.index() {
             i if i == notifications_oper => match oper.recv(&notification_rx) {
-                Ok(notification) => {
-                    let result = process_notification(notification, &mut editor_state, None).await;
-                    if let Ok(lsp_response) = result {
-                        let _ = response_tx.send(lsp_response);
-                    }
+                Ok((notification, response_tx)) => {
+                    // The sender is always waiting for a reply, even if the notification failed
+                    let lsp_response =
+                        match process_notification(notification, &mut editor_state, None).await {
+                            Ok(lsp_response) => lsp_response,
+                            Err(message) => LspResponse::error(&message),
+                        };
+                    let _ = response_tx.send(lsp_response);
                 }
                 Err(_e) => {
                     continue;
                 }
             },
             i if i == requests_oper => match oper.recv(&request_rx) {
-                Ok(request) => {
+                Ok((request, response_tx)) => {
                     let lsp_response = process_request(request, &mut editor_state);
                     let _ = response_tx.send(lsp_response);
                 }
@@ -74,23 +101,23 @@ pub async fn start_language_server(
 #[derive(Debug)]
 pub struct LspNativeBridge {
     client: Client,
-    request_tx: Arc<Mutex<MultiplexableSender<LspRequest>>>,
-    notification_tx: Arc<Mutex<MultiplexableSender<LspNotification>>>,
-    response_rx: Arc<Mutex<Receiver<LspResponse>>>,
+    request_tx: Arc<Mutex<MultiplexableSender<(LspRequest, LspResponseSender)>>>,
+    notification_tx: Arc<Mutex<MultiplexableSender<(LspNotification, LspResponseSender)>>>,
+    // Content and version of the documents opened in the editor
+    documents: Arc<Mutex<HashMap<Url, (i32, String)>>>,
 }
 
 impl LspNativeBridge {
     pub fn new(
         client: Client,
-        request_tx: MultiplexableSender<LspRequest>,
-        notification_tx: MultiplexableSender<LspNotification>,
-        response_rx: Receiver<LspResponse>,
+        request_tx: MultiplexableSender<(LspRequest, LspResponseSender)>,
+        notification_tx: MultiplexableSender<(LspNotification, LspResponseSender)>,
     ) -> Self {
         Self {
             client,
             request_tx: Arc::new(Mutex::new(request_tx)),
             notification_tx: Arc::new(Mutex::new(notification_tx)),
-            response_rx: Arc::new(Mutex::new(response_rx)),
+            documents: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 }
@@ -117,8 +144,23 @@ impl LanguageServer for LspNativeBridge {
                     work_done_progress_options: Default::default(),
                 }),
                 type_definition_provider: None,
-                hover_provider: Some(HoverProviderCapability::Simple(false)),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                signature_help_provider: Some(SignatureHelpOptions {
+                    trigger_characters: Some(vec![" ".to_string()]),
+                    retrigger_characters: None,
+                    work_done_progress_options: Default::default(),
+                }),
                 declaration_provider: Some(DeclarationCapability::Simple(false)),
+                definition_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+                document_formatting_provider: Some(OneOf::Left(true)),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                })),
                 ..ServerCapabilities::default()
             },
         })
@@ -143,16 +185,15 @@ impl LanguageServer for LspNativeBridge {
             _ => return Ok(None),
         };
 
+        let (response_tx, response_rx) = bounded(1);
         let _ = match self.request_tx.lock() {
-            Ok(tx) => tx.send(LspRequest::GetIntellisense(contract_location)),
+            Ok(tx) => tx.send((LspRequest::GetIntellisense(contract_location), response_tx)),
             Err(_) => return Ok(None),
         };
 
         let mut keywords = vec![];
-        if let Ok(response_rx) = self.response_rx.lock() {
-            if let Ok(ref mut response) = response_rx.recv() {
-                keywords.append(&mut response.completion_items);
-            }
+        if let Ok(ref mut response) = response_rx.recv() {
+            keywords.append(&mut response.completion_items);
         }
 
         let mut completion_items = vec![];
@@ -163,17 +204,352 @@ impl LanguageServer for LspNativeBridge {
         Ok(Some(CompletionResponse::from(completion_items)))
     }
 
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        let file_url = params.text_document_position_params.text_document.uri;
+        let contract_location = match utils::get_contract_location(&file_url) {
+            Some(location) => location,
+            _ => return Ok(None),
+        };
+        let position =
+            position_tower_lsp_type_to_lsp_type(&params.text_document_position_params.position);
+
+        let (response_tx, response_rx) = bounded(1);
+        let _ = match self.request_tx.lock() {
+            Ok(tx) => tx.send((
+                LspRequest::GetDefinition(contract_location, position),
+                response_tx,
+            )),
+            Err(_) => return Ok(None),
+        };
+
+        let mut locations = vec![];
+        if let Ok(ref mut response) = response_rx.recv() {
+            locations.append(&mut response.locations);
+        }
+
+        Ok(locations.first().map(|location| {
+            GotoDefinitionResponse::Scalar(location_lsp_type_to_tower_lsp_type(location))
+        }))
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let file_url = params.text_document_position_params.text_document.uri;
+        let contract_location = match utils::get_contract_location(&file_url) {
+            Some(location) => location,
+            _ => return Ok(None),
+        };
+        let position =
+            position_tower_lsp_type_to_lsp_type(&params.text_document_position_params.position);
+
+        let (response_tx, response_rx) = bounded(1);
+        let _ = match self.request_tx.lock() {
+            Ok(tx) => tx.send((
+                LspRequest::GetHover(contract_location, position),
+                response_tx,
+            )),
+            Err(_) => return Ok(None),
+        };
+
+        let mut hover = None;
+        if let Ok(ref mut response) = response_rx.recv() {
+            hover = response.hover.take();
+        }
+
+        Ok(hover.as_ref().map(hover_lsp_type_to_tower_lsp_type))
+    }
+
+    async fn signature_help(&self, params: SignatureHelpParams) -> Result<Option<SignatureHelp>> {
+        let file_url = params.text_document_position_params.text_document.uri;
+        let contract_location = match utils::get_contract_location(&file_url) {
+            Some(location) => location,
+            _ => return Ok(None),
+        };
+        let position =
+            position_tower_lsp_type_to_lsp_type(&params.text_document_position_params.position);
+        // The function call being typed is usually not saved yet
+        let source = match self.documents.lock() {
+            Ok(documents) => documents.get(&file_url).map(|(_, source)| source.clone()),
+            Err(_) => None,
+        };
+
+        let (response_tx, response_rx) = bounded(1);
+        let _ = match self.request_tx.lock() {
+            Ok(tx) => tx.send((
+                LspRequest::GetSignatureHelp(contract_location, position, source),
+                response_tx,
+            )),
+            Err(_) => return Ok(None),
+        };
+
+        let mut signature_help = None;
+        if let Ok(ref mut response) = response_rx.recv() {
+            signature_help = response.signature_help.take();
+        }
+
+        Ok(signature_help
+            .as_ref()
+            .map(signature_help_lsp_type_to_tower_lsp_type))
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        let file_url = params.text_document.uri;
+        let contract_location = match utils::get_contract_location(&file_url) {
+            Some(location) => location,
+            _ => return Ok(None),
+        };
+
+        let (response_tx, response_rx) = bounded(1);
+        let _ = match self.request_tx.lock() {
+            Ok(tx) => tx.send((
+                LspRequest::GetDocumentSymbols(contract_location),
+                response_tx,
+            )),
+            Err(_) => return Ok(None),
+        };
+
+        let mut document_symbols = vec![];
+        if let Ok(ref mut response) = response_rx.recv() {
+            document_symbols.append(&mut response.document_symbols);
+        }
+
+        Ok(Some(DocumentSymbolResponse::Nested(
+            document_symbols
+                .iter()
+                .map(document_symbol_lsp_type_to_tower_lsp_type)
+                .collect(),
+        )))
+    }
+
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> Result<Option<Vec<SymbolInformation>>> {
+        let (response_tx, response_rx) = bounded(1);
+        let _ = match self.request_tx.lock() {
+            Ok(tx) => tx.send((LspRequest::GetWorkspaceSymbols(params.query), response_tx)),
+            Err(_) => return Ok(None),
+        };
+
+        let mut workspace_symbols = vec![];
+        if let Ok(ref mut response) = response_rx.recv() {
+            workspace_symbols.append(&mut response.workspace_symbols);
+        }
+
+        Ok(Some(
+            workspace_symbols
+                .iter()
+                .map(symbol_information_lsp_type_to_tower_lsp_type)
+                .collect(),
+        ))
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let file_url = params.text_document.uri;
+        let contract_location = match utils::get_contract_location(&file_url) {
+            Some(location) => location,
+            _ => return Ok(None),
+        };
+        let range = range_tower_lsp_type_to_lsp_type(&params.range);
+
+        let (response_tx, response_rx) = bounded(1);
+        let _ = match self.request_tx.lock() {
+            Ok(tx) => tx.send((
+                LspRequest::GetCodeActions(contract_location, range),
+                response_tx,
+            )),
+            Err(_) => return Ok(None),
+        };
+
+        let mut code_actions = vec![];
+        if let Ok(ref mut response) = response_rx.recv() {
+            code_actions.append(&mut response.code_actions);
+        }
+
+        Ok(Some(
+            code_actions
+                .iter()
+                .map(|code_action| {
+                    CodeActionOrCommand::CodeAction(code_action_lsp_type_to_tower_lsp_type(
+                        code_action,
+                    ))
+                })
+                .collect(),
+        ))
+    }
+
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let file_url = params.text_document.uri;
+        let contract_location = match utils::get_contract_location(&file_url) {
+            Some(location) => location,
+            _ => return Ok(None),
+        };
+        // Unsaved changes are formatted as well
+        let source = match self.documents.lock() {
+            Ok(documents) => documents.get(&file_url).map(|(_, source)| source.clone()),
+            Err(_) => None,
+        };
+        let source = match source {
+            Some(source) => source,
+            None => match contract_location.read_content_as_utf8() {
+                Ok(source) => source,
+                Err(_) => return Ok(None),
+            },
+        };
+        let options = formatting_options_tower_lsp_type_to_lsp_type(&params.options);
+
+        let (response_tx, response_rx) = bounded(1);
+        let _ = match self.request_tx.lock() {
+            Ok(tx) => tx.send((LspRequest::GetFormatting(source, options), response_tx)),
+            Err(_) => return Ok(None),
+        };
+
+        let mut text_edits = None;
+        if let Ok(ref mut response) = response_rx.recv() {
+            text_edits = response.text_edits.take();
+        }
+
+        Ok(text_edits.map(|text_edits| {
+            text_edits
+                .iter()
+                .map(text_edit_lsp_type_to_tower_lsp_type)
+                .collect()
+        }))
+    }
+
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let file_url = params.text_document_position.text_document.uri;
+        let contract_location = match utils::get_contract_location(&file_url) {
+            Some(location) => location,
+            _ => return Ok(None),
+        };
+        let position = position_tower_lsp_type_to_lsp_type(&params.text_document_position.position);
+
+        let (response_tx, response_rx) = bounded(1);
+        let _ = match self.request_tx.lock() {
+            Ok(tx) => tx.send((
+                LspRequest::GetReferences(
+                    contract_location,
+                    position,
+                    params.context.include_declaration,
+                ),
+                response_tx,
+            )),
+            Err(_) => return Ok(None),
+        };
+
+        let mut locations = vec![];
+        if let Ok(ref mut response) = response_rx.recv() {
+            locations.append(&mut response.locations);
+        }
+
+        Ok(Some(
+            locations
+                .iter()
+                .map(location_lsp_type_to_tower_lsp_type)
+                .collect(),
+        ))
+    }
+
+    async fn prepare_rename(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<PrepareRenameResponse>> {
+        let file_url = params.text_document.uri;
+        let contract_location = match utils::get_contract_location(&file_url) {
+            Some(location) => location,
+            _ => return Ok(None),
+        };
+        let position = position_tower_lsp_type_to_lsp_type(&params.position);
+
+        let (response_tx, response_rx) = bounded(1);
+        let _ = match self.request_tx.lock() {
+            Ok(tx) => tx.send((
+                LspRequest::PrepareRename(contract_location, position),
+                response_tx,
+            )),
+            Err(_) => return Ok(None),
+        };
+
+        let mut response = LspResponse::default();
+        if let Ok(res) = response_rx.recv() {
+            response = res;
+        }
+
+        // Refused renames are surfaced to the user as errors
+        if let Some((_, message)) = response.notification {
+            return Err(Error::invalid_params(message));
+        }
+        Ok(response
+            .rename_range
+            .as_ref()
+            .map(range_lsp_type_to_tower_lsp_type)
+            .map(PrepareRenameResponse::Range))
+    }
+
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let file_url = params.text_document_position.text_document.uri;
+        let contract_location = match utils::get_contract_location(&file_url) {
+            Some(location) => location,
+            _ => return Ok(None),
+        };
+        let position = position_tower_lsp_type_to_lsp_type(&params.text_document_position.position);
+
+        let (response_tx, response_rx) = bounded(1);
+        let _ = match self.request_tx.lock() {
+            Ok(tx) => tx.send((
+                LspRequest::Rename(contract_location, position, params.new_name),
+                response_tx,
+            )),
+            Err(_) => return Ok(None),
+        };
+
+        let mut response = LspResponse::default();
+        if let Ok(res) = response_rx.recv() {
+            response = res;
+        }
+
+        if let Some((_, message)) = response.notification {
+            return Err(Error::invalid_params(message));
+        }
+        Ok(response
+            .workspace_edit
+            .as_ref()
+            .map(workspace_edit_lsp_type_to_tower_lsp_type))
+    }
+
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        if let Ok(mut documents) = self.documents.lock() {
+            documents.insert(
+                params.text_document.uri.clone(),
+                (
+                    params.text_document.version,
+                    params.text_document.text.clone(),
+                ),
+            );
+        }
+        let (response_tx, response_rx) = bounded(1);
         if let Some(contract_location) = utils::get_contract_location(&params.text_document.uri) {
             let _ = match self.notification_tx.lock() {
-                Ok(tx) => tx.send(LspNotification::ContractOpened(contract_location)),
+                Ok(tx) => tx.send((
+                    LspNotification::ContractOpened(contract_location),
+                    response_tx,
+                )),
                 Err(_) => return,
             };
         } else if let Some(manifest_location) =
             utils::get_manifest_location(&params.text_document.uri)
         {
             let _ = match self.notification_tx.lock() {
-                Ok(tx) => tx.send(LspNotification::ManifestOpened(manifest_location)),
+                Ok(tx) => tx.send((
+                    LspNotification::ManifestOpened(manifest_location),
+                    response_tx,
+                )),
                 Err(_) => return,
             };
         } else {
@@ -191,11 +567,9 @@ impl LanguageServer for LspNativeBridge {
             .await;
         let mut aggregated_diagnostics = vec![];
         let mut notification = None;
-        if let Ok(response_rx) = self.response_rx.lock() {
-            if let Ok(ref mut response) = response_rx.recv() {
-                aggregated_diagnostics.append(&mut response.aggregated_diagnostics);
-                notification = response.notification.take();
-            }
+        if let Ok(ref mut response) = response_rx.recv() {
+            aggregated_diagnostics.append(&mut response.aggregated_diagnostics);
+            notification = response.notification.take();
         }
         for (location, mut diags) in aggregated_diagnostics.drain(..) {
             if let Ok(url) = location.to_url_string() {
@@ -216,16 +590,23 @@ impl LanguageServer for LspNativeBridge {
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        let (response_tx, response_rx) = bounded(1);
         if let Some(contract_location) = utils::get_contract_location(&params.text_document.uri) {
             let _ = match self.notification_tx.lock() {
-                Ok(tx) => tx.send(LspNotification::ContractChanged(contract_location)),
+                Ok(tx) => tx.send((
+                    LspNotification::ContractChanged(contract_location),
+                    response_tx,
+                )),
                 Err(_) => return,
             };
         } else if let Some(manifest_location) =
             utils::get_manifest_location(&params.text_document.uri)
         {
             let _ = match self.notification_tx.lock() {
-                Ok(tx) => tx.send(LspNotification::ManifestChanged(manifest_location)),
+                Ok(tx) => tx.send((
+                    LspNotification::ManifestChanged(manifest_location),
+                    response_tx,
+                )),
                 Err(_) => return,
             };
         } else {
@@ -234,11 +615,9 @@ impl LanguageServer for LspNativeBridge {
 
         let mut aggregated_diagnostics = vec![];
         let mut notification = None;
-        if let Ok(response_rx) = self.response_rx.lock() {
-            if let Ok(ref mut response) = response_rx.recv() {
-                aggregated_diagnostics.append(&mut response.aggregated_diagnostics);
-                notification = response.notification.take();
-            }
+        if let Ok(ref mut response) = response_rx.recv() {
+            aggregated_diagnostics.append(&mut response.aggregated_diagnostics);
+            notification = response.notification.take();
         }
 
         for (location, mut diags) in aggregated_diagnostics.drain(..) {
@@ -259,9 +638,68 @@ impl LanguageServer for LspNativeBridge {
         }
     }
 
-    async fn did_change(&self, _changes: DidChangeTextDocumentParams) {}
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        let uri = params.text_document.uri;
+        let contract_location = match utils::get_contract_location(&uri) {
+            Some(contract_location) => contract_location,
+            None => return,
+        };
+        // Documents are synchronized in full, the last change holds the whole content
+        let source = match params.content_changes.into_iter().last() {
+            Some(change) => change.text,
+            None => return,
+        };
 
-    async fn did_close(&self, _: DidCloseTextDocumentParams) {}
+        // Only analyse the latest version, once the user stopped typing
+        let version = params.text_document.version;
+        if let Ok(mut documents) = self.documents.lock() {
+            documents.insert(uri.clone(), (version, source.clone()));
+        }
+        tokio::time::sleep(Duration::from_millis(DID_CHANGE_DEBOUNCE_MS)).await;
+        match self.documents.lock() {
+            Ok(documents) if documents.get(&uri).map(|(v, _)| *v) == Some(version) => {}
+            _ => return,
+        };
+
+        let (response_tx, response_rx) = bounded(1);
+        let _ = match self.notification_tx.lock() {
+            Ok(tx) => tx.send((
+                LspNotification::ContractEdited(contract_location, source),
+                response_tx,
+            )),
+            Err(_) => return,
+        };
+
+        let mut aggregated_diagnostics = vec![];
+        let mut notification = None;
+        if let Ok(ref mut response) = response_rx.recv() {
+            aggregated_diagnostics.append(&mut response.aggregated_diagnostics);
+            notification = response.notification.take();
+        }
+
+        for (location, mut diags) in aggregated_diagnostics.drain(..) {
+            if let Ok(url) = location.to_url_string() {
+                self.client
+                    .publish_diagnostics(
+                        Url::parse(&url).unwrap(),
+                        clarity_diagnostics_to_tower_lsp_type(&mut diags),
+                        None,
+                    )
+                    .await;
+            }
+        }
+        if let Some((level, message)) = notification {
+            self.client
+                .show_message(message_level_type_to_tower_lsp_type(&level), message)
+                .await;
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        if let Ok(mut documents) = self.documents.lock() {
+            documents.remove(&params.text_document.uri);
+        }
+    }
 }
 
 pub fn message_level_type_to_tower_lsp_type(
@@ -13,7 +13,7 @@ use clarity_repl::clarity::vm::analysis::contract_interface_builder::build_contr
 use clarity_repl::clarity::vm::EvaluationResult;
 use clarity_repl::repl::Session;
 use clarity_repl::utils;
-use deno_core::error::AnyError;
+use deno_core::error::{generic_error, AnyError};
 use deno_core::located_script_name;
 use deno_core::serde_json::{json, Value};
 use deno_core::{op, Extension};
@@ -52,6 +52,16 @@ pub async fn run_bridge(
     call_read_only_fn_decl.name = "api/v1/call_read_only_fn";
     let mut get_assets_maps_decl = get_assets_maps::decl();
     get_assets_maps_decl.name = "api/v1/get_assets_maps";
+    let mut save_state_decl = save_state::decl();
+    save_state_decl.name = "api/v1/save_state";
+    let mut load_state_decl = load_state::decl();
+    load_state_decl.name = "api/v1/load_state";
+    let mut rewind_decl = rewind::decl();
+    rewind_decl.name = "api/v1/rewind";
+    let mut branch_decl = branch::decl();
+    branch_decl.name = "api/v1/branch";
+    let mut switch_decl = switch::decl();
+    switch_decl.name = "api/v1/switch";
     let mut deprecation_notice_decl = deprecation_notice::decl();
     deprecation_notice_decl.name = "api/v1/mine_empty_blocks";
 
@@ -64,6 +74,11 @@ pub async fn run_bridge(
             mine_empty_blocks_decl,
             call_read_only_fn_decl,
             get_assets_maps_decl,
+            save_state_decl,
+            load_state_decl,
+            rewind_decl,
+            branch_decl,
+            switch_decl,
         ])
         .build();
     custom_extensions.push(clarinet);
@@ -616,6 +631,37 @@ fn mine_block(state: &mut OpState, args: MineBlockArgs) -> Result<String, AnyErr
     Ok(payload.to_string())
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionStateArgs {
+    session_id: u32,
+    path: String,
+}
+
+#[op]
+fn save_state(state: &mut OpState, args: SessionStateArgs) -> Result<String, AnyError> {
+    perform_block(state, args.session_id, |_name, session| {
+        session.save_state(&args.path).map_err(generic_error)
+    })?;
+    Ok(json!({
+      "session_id": args.session_id,
+    })
+    .to_string())
+}
+
+#[op]
+fn load_state(state: &mut OpState, args: SessionStateArgs) -> Result<String, AnyError> {
+    let block_height = perform_block(state, args.session_id, |_name, session| {
+        session.load_state(&args.path).map_err(generic_error)?;
+        Ok(session.interpreter.get_block_height())
+    })?;
+    Ok(json!({
+      "session_id": args.session_id,
+      "block_height": block_height,
+    })
+    .to_string())
+}
+
 fn perform_block<F, R>(state: &mut OpState, session_id: u32, handler: F) -> Result<R, AnyError>
 where
     F: FnOnce(&str, &mut Session) -> Result<R, AnyError>,
@@ -633,3 +679,55 @@ where
         Some((name, ref mut session)) => handler(name.as_str(), session),
     }
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RewindArgs {
+    session_id: u32,
+    height: u32,
+}
+
+#[op]
+fn rewind(state: &mut OpState, args: RewindArgs) -> Result<String, AnyError> {
+    let block_height = perform_block(state, args.session_id, |_name, session| {
+        session.rewind(args.height).map_err(generic_error)
+    })?;
+    Ok(json!({
+      "session_id": args.session_id,
+      "block_height": block_height,
+    })
+    .to_string())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BranchArgs {
+    session_id: u32,
+    name: String,
+}
+
+#[op]
+fn branch(state: &mut OpState, args: BranchArgs) -> Result<String, AnyError> {
+    let block_height = perform_block(state, args.session_id, |_name, session| {
+        session.new_branch(&args.name).map_err(generic_error)?;
+        Ok(session.interpreter.get_block_height())
+    })?;
+    Ok(json!({
+      "session_id": args.session_id,
+      "block_height": block_height,
+    })
+    .to_string())
+}
+
+#[op]
+fn switch(state: &mut OpState, args: BranchArgs) -> Result<String, AnyError> {
+    let block_height = perform_block(state, args.session_id, |_name, session| {
+        session.switch(&args.name).map_err(generic_error)?;
+        Ok(session.interpreter.get_block_height())
+    })?;
+    Ok(json!({
+      "session_id": args.session_id,
+      "block_height": block_height,
+    })
+    .to_string())
+}
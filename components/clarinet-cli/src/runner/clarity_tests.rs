@@ -0,0 +1,347 @@
+use super::vendor::deno_cli::display;
+use super::vendor::deno_cli::fs_util::collect_specifiers;
+use super::vendor::deno_cli::tools::test::TestFilter;
+use super::vendor::deno_runtime::colors;
+use super::{DeploymentCache, SessionArtifacts};
+use clarinet_deployments::setup_session_with_deployment;
+use clarinet_files::ProjectManifest;
+use clarity_repl::clarity::vm::types::{ResponseData, Value};
+use clarity_repl::clarity::vm::{EvaluationResult, SymbolicExpression};
+use clarity_repl::repl::Session;
+use deno_core::error::AnyError;
+use deno_core::ModuleSpecifier;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::time::Instant;
+
+/// A `test-*` public function, discovered in a test contract.
+struct TestFunction {
+    name: String,
+    line: u32,
+    column: u32,
+    takes_args: bool,
+}
+
+#[derive(Default)]
+struct TestSummary {
+    passed: usize,
+    failed: usize,
+    filtered_out: usize,
+    failures: Vec<(String, String)>,
+}
+
+fn is_clarity_test_path(path: &Path) -> bool {
+    match path.file_name() {
+        Some(name) => name.to_string_lossy().ends_with("_test.clar"),
+        None => false,
+    }
+}
+
+/// Collects the test contracts (`*_test.clar`) found in the included paths.
+pub fn collect_test_contracts(include: &[String]) -> Result<Vec<ModuleSpecifier>, AnyError> {
+    let specifiers = collect_specifiers(include.to_vec(), &[], is_clarity_test_path)?;
+    Ok(specifiers
+        .into_iter()
+        .filter(|specifier| specifier.path().ends_with(".clar"))
+        .collect())
+}
+
+/// Runs the `test-*` public functions of the test contracts, each one against a fresh
+/// session where the deployment and the test contract have been executed.
+/// Tests are passing when the function returns an `ok` response.
+pub fn run_tests(
+    specifiers: &[ModuleSpecifier],
+    manifest: &ProjectManifest,
+    cache: &DeploymentCache,
+    fail_fast: Option<u16>,
+    filter: &Option<String>,
+) -> (bool, Vec<SessionArtifacts>) {
+    let earlier = Instant::now();
+    let filter = TestFilter::from_flag(filter);
+    let fail_fast = match fail_fast {
+        None | Some(0) => None,
+        Some(limit) => Some(limit as usize),
+    };
+
+    let asts = cache
+        .contracts_artifacts
+        .iter()
+        .map(|(contract_id, artifacts)| (contract_id.clone(), artifacts.ast.clone()))
+        .collect::<HashMap<_, _>>();
+    let mut deployment_session =
+        setup_session_with_deployment(manifest, &cache.deployment, Some(&asts)).session;
+    deployment_session.coverage_reports.clear();
+    deployment_session.costs_reports.clear();
+
+    let mut summary = TestSummary::default();
+    let mut sessions_artifacts = vec![];
+
+    'contracts: for specifier in specifiers.iter() {
+        let origin = to_relative_path(specifier);
+        let mut session = deployment_session.clone();
+        let (contract_id, tests) = match deploy_test_contract(&mut session, specifier) {
+            Ok(res) => res,
+            Err(error) => {
+                println!("Uncaught error from {} {}", origin, colors::red("FAILED"));
+                summary.failed += 1;
+                summary
+                    .failures
+                    .push((format!("{} (uncaught error)", origin), error));
+                if fail_fast.map_or(false, |limit| summary.failed >= limit) {
+                    break 'contracts;
+                }
+                continue;
+            }
+        };
+
+        let (tests, filtered_out): (Vec<_>, Vec<_>) = tests
+            .into_iter()
+            .partition(|test| filter.includes(&test.name));
+        summary.filtered_out += filtered_out.len();
+
+        let inflection = if tests.len() == 1 { "test" } else { "tests" };
+        println!(
+            "{}",
+            colors::gray(format!(
+                "running {} {} from {}",
+                tests.len(),
+                inflection,
+                origin
+            ))
+        );
+
+        for test in tests.iter() {
+            print!("{} ...", test.name);
+            // flush for faster feedback when line buffered
+            std::io::stdout().flush().unwrap();
+
+            let started = Instant::now();
+            let mut test_session = session.clone();
+            let result = run_test(&mut test_session, &contract_id, test);
+            let elapsed = started.elapsed().as_millis();
+
+            let status = match result {
+                Ok(()) => {
+                    summary.passed += 1;
+                    colors::green("ok").to_string()
+                }
+                Err(error) => {
+                    summary.failed += 1;
+                    let failure_title = format!(
+                        "{} {}",
+                        test.name,
+                        colors::gray(format!("=> {}:{}:{}", origin, test.line, test.column))
+                    );
+                    summary.failures.push((failure_title, error));
+                    colors::red("FAILED").to_string()
+                }
+            };
+            println!(
+                " {} {}",
+                status,
+                colors::gray(format!("({})", display::human_elapsed(elapsed)))
+            );
+
+            sessions_artifacts.push(SessionArtifacts {
+                coverage_reports: std::mem::take(&mut test_session.coverage_reports),
+                costs_reports: std::mem::take(&mut test_session.costs_reports),
+            });
+
+            if fail_fast.map_or(false, |limit| summary.failed >= limit) {
+                break 'contracts;
+            }
+        }
+    }
+
+    report_summary(&summary, earlier.elapsed().as_millis());
+    (summary.failed == 0, sessions_artifacts)
+}
+
+fn to_relative_path(specifier: &ModuleSpecifier) -> String {
+    let cwd = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| ModuleSpecifier::from_directory_path(cwd).ok());
+    if let Some(relative_path) = cwd.and_then(|cwd| cwd.make_relative(specifier)) {
+        if !relative_path.starts_with("../") {
+            return format!("./{}", relative_path);
+        }
+        return relative_path;
+    }
+    specifier.to_string()
+}
+
+/// Deploys the test contract, and returns its identifier along with the test functions it
+/// defines, in the order of their definitions.
+fn deploy_test_contract(
+    session: &mut Session,
+    specifier: &ModuleSpecifier,
+) -> Result<(String, Vec<TestFunction>), String> {
+    let path = specifier
+        .to_file_path()
+        .map_err(|_| format!("unable to read {}", specifier))?;
+    let source = std::fs::read_to_string(&path)
+        .map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+    let contract_name = path
+        .file_stem()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("unable to read {}", path.display()))?;
+
+    let execution = session
+        .interpret(source, Some(contract_name), None, false, None, None)
+        .map_err(|diagnostics| {
+            diagnostics
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })?;
+    let contract = match execution.result {
+        EvaluationResult::Contract(contract_result) => contract_result.contract,
+        _ => unreachable!("Snippet result from contract"),
+    };
+    let tests = contract
+        .ast
+        .expressions
+        .iter()
+        .filter_map(get_test_function)
+        .collect();
+    Ok((contract.contract_identifier, tests))
+}
+
+fn get_test_function(expr: &SymbolicExpression) -> Option<TestFunction> {
+    let list = expr.match_list()?;
+    if list.get(0)?.match_atom()?.as_str() != "define-public" {
+        return None;
+    }
+    let signature = list.get(1)?.match_list()?;
+    let name = signature.get(0)?.match_atom()?;
+    if !name.starts_with("test-") {
+        return None;
+    }
+    Some(TestFunction {
+        name: name.to_string(),
+        line: expr.span.start_line,
+        column: expr.span.start_column,
+        takes_args: signature.len() > 1,
+    })
+}
+
+fn run_test(session: &mut Session, contract_id: &str, test: &TestFunction) -> Result<(), String> {
+    if test.takes_args {
+        return Err("test functions can not take arguments".to_string());
+    }
+    let sender = session.get_tx_sender();
+    let execution = session
+        .invoke_contract_call(contract_id, &test.name, &vec![], &sender, test.name.clone())
+        .map_err(|diagnostics| match diagnostics.last() {
+            Some(diagnostic) => format!("Runtime error: {}", diagnostic.message),
+            None => "Runtime error".to_string(),
+        })?;
+    match execution.result {
+        EvaluationResult::Snippet(result) => match result.result {
+            Value::Response(ResponseData {
+                committed: true, ..
+            }) => Ok(()),
+            value => Err(format!("test returned {}", value)),
+        },
+        _ => unreachable!("Contract result from snippet"),
+    }
+}
+
+fn report_summary(summary: &TestSummary, elapsed: u128) {
+    if !summary.failures.is_empty() {
+        println!("\n{}\n", colors::white_bold_on_red(" ERRORS "));
+        for (failure_title, error) in summary.failures.iter() {
+            println!("{}", failure_title);
+            println!("{}: {}", colors::red_bold("error"), error);
+            println!();
+        }
+        println!("{}\n", colors::white_bold_on_red(" FAILURES "));
+        for (failure_title, _) in summary.failures.iter() {
+            println!("{}", failure_title);
+        }
+    }
+
+    let status = if summary.failed > 0 {
+        colors::red("FAILED").to_string()
+    } else {
+        colors::green("ok").to_string()
+    };
+    let mut summary_result = format!("{} passed | {} failed", summary.passed, summary.failed);
+    if summary.filtered_out > 0 {
+        summary_result.push_str(&format!(" | {} filtered out", summary.filtered_out));
+    }
+    println!(
+        "\n{} | {} {}\n",
+        status,
+        summary_result,
+        colors::gray(format!("({})", display::human_elapsed(elapsed))),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::repl::SessionSettings;
+
+    fn deploy(name: &str, source: &str) -> (Session, String, Vec<TestFunction>) {
+        let mut path = std::env::temp_dir();
+        path.push(format!("{}_test.clar", name));
+        std::fs::write(&path, source).unwrap();
+        let specifier = ModuleSpecifier::from_file_path(&path).unwrap();
+        let mut session = Session::new(SessionSettings::default());
+        let (contract_id, tests) = deploy_test_contract(&mut session, &specifier).unwrap();
+        (session, contract_id, tests)
+    }
+
+    #[test]
+    fn test_get_test_functions() {
+        let (_, contract_id, tests) = deploy(
+            "get_test_functions",
+            "(define-public (helper) (ok true))
+(define-read-only (test-read-only) (ok true))
+(define-public (test-no-args)
+  (ok true))
+(define-public (test-with-args (amount uint))
+  (ok amount))",
+        );
+        assert!(contract_id.ends_with(".get_test_functions_test"));
+        assert_eq!(
+            tests
+                .iter()
+                .map(|test| test.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["test-no-args", "test-with-args"]
+        );
+        assert_eq!((tests[0].line, tests[0].column), (3, 1));
+        assert!(!tests[0].takes_args);
+        assert_eq!((tests[1].line, tests[1].column), (5, 1));
+        assert!(tests[1].takes_args);
+    }
+
+    #[test]
+    fn test_run_test() {
+        let (mut session, contract_id, tests) = deploy(
+            "run_test",
+            "(define-public (test-ok) (ok true))
+(define-public (test-err) (err u1))
+(define-public (test-runtime-error) (ok (/ u1 u0)))
+(define-public (test-with-args (amount uint)) (ok amount))",
+        );
+        let results = tests
+            .iter()
+            .map(|test| run_test(&mut session, &contract_id, test))
+            .collect::<Vec<_>>();
+        assert_eq!(results[0], Ok(()));
+        assert_eq!(results[1], Err("test returned (err u1)".to_string()));
+        assert!(results[2]
+            .as_ref()
+            .unwrap_err()
+            .starts_with("Runtime error"));
+        assert_eq!(
+            results[3],
+            Err("test functions can not take arguments".to_string())
+        );
+    }
+}
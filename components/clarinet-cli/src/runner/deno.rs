@@ -19,9 +19,8 @@ use super::vendor::deno_cli::tools::test::{
 
 use super::vendor::deno_runtime::permissions::Permissions;
 use super::vendor::deno_runtime::tokio_util::run_local;
-use super::{api_v1, costs, DeploymentCache, SessionArtifacts};
+use super::{api_v1, costs, report_sessions_artifacts, DeploymentCache, SessionArtifacts};
 use clarinet_files::{FileLocation, ProjectManifest};
-use clarity_repl::analysis::coverage::CoverageReporter;
 use deno_ast::swc::common::comments::CommentKind;
 use deno_ast::MediaType;
 use deno_ast::SourceRangedForSpanned;
@@ -63,6 +62,7 @@ pub async fn do_run_scripts(
     import_map: Option<String>,
     allow_net: bool,
     cache_location: FileLocation,
+    native_tests: (bool, Vec<SessionArtifacts>),
 ) -> Result<usize, (AnyError, usize)> {
     let project_root = manifest.location.get_project_root_location().unwrap();
     let cwd = PathBuf::from(&project_root.to_string());
@@ -145,6 +145,7 @@ pub async fn do_run_scripts(
             Some(cache),
             display_costs_report,
             generate_coverage,
+            native_tests,
         )
         .await?
     };
@@ -625,6 +626,13 @@ async fn test_specifiers(
     }
 }
 
+/// Whether the included paths contain TypeScript or JavaScript test files.
+pub fn has_test_scripts(include: &[String]) -> bool {
+    collect_specifiers(include.to_vec(), &[], is_supported_test_path)
+        .map(|specifiers| !specifiers.is_empty())
+        .unwrap_or(false)
+}
+
 /// Collects specifiers marking them with the appropriate test mode while maintaining the natural
 /// input order.
 ///
@@ -730,6 +738,7 @@ pub async fn run_tests(
     deployment_cache: Option<DeploymentCache>,
     display_costs_report: bool,
     generate_coverage: bool,
+    native_tests: (bool, Vec<SessionArtifacts>),
 ) -> Result<usize, (AnyError, usize)> {
     let ps = ProcState::build(flags).await.map_err(|e| (e, 0))?;
     let permissions = Permissions::from_options(&ps.options.permissions_options());
@@ -755,7 +764,8 @@ pub async fn run_tests(
     }
 
     let compat = ps.options.compat();
-    let (success, artifacts) = test_specifiers(
+    let (native_success, mut artifacts) = native_tests;
+    let (success, mut scripts_artifacts) = test_specifiers(
         ps,
         permissions,
         specifiers_with_mode,
@@ -772,36 +782,15 @@ pub async fn run_tests(
     )
     .await
     .map_err(|e| (e, 0))?;
+    artifacts.append(&mut scripts_artifacts);
 
-    if !success {
+    if !success || !native_success {
         return Err((AnyError::msg("Test suite failed"), artifacts.len()));
     }
 
-    if display_costs_report {
-        costs::display_costs_report(&artifacts)
-    }
-
     if let Some(ref cache) = deployment_cache {
-        if generate_coverage {
-            let mut coverage_reporter = CoverageReporter::new();
-            for (contract_id, analysis_artifacts) in cache.contracts_artifacts.iter() {
-                coverage_reporter
-                    .asts
-                    .insert(contract_id.clone(), analysis_artifacts.ast.clone());
-            }
-            for (contract_id, (_, contract_location)) in cache.deployment.contracts.iter() {
-                coverage_reporter
-                    .contract_paths
-                    .insert(contract_id.name.to_string(), contract_location.to_string());
-            }
-            for artifact in artifacts.iter() {
-                let mut coverage_reports = artifact.coverage_reports.clone();
-                coverage_reporter.reports.append(&mut coverage_reports);
-            }
-            coverage_reporter
-                .write_lcov_file("coverage.lcov")
-                .map_err(|e| (AnyError::from(e), 0))?;
-        }
+        report_sessions_artifacts(&artifacts, cache, display_costs_report, generate_coverage)
+            .map_err(|e| (e, 0))?;
     }
 
     Ok(artifacts.len())
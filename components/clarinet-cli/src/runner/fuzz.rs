@@ -0,0 +1,854 @@
+use clarinet_deployments::types::{DeploymentGenerationArtifacts, DeploymentSpecification};
+use clarinet_files::ProjectManifest;
+use clarity_repl::clarity::util::hash;
+use clarity_repl::clarity::vm::analysis::ContractAnalysis;
+use clarity_repl::clarity::vm::types::{
+    ASCIIData, BuffData, CharType, FunctionType, ListData, OptionalData, PrincipalData,
+    QualifiedContractIdentifier, ResponseData, SequenceData, SequenceSubtype, StringSubtype,
+    TupleData, TypeSignature, UTF8Data, Value,
+};
+use clarity_repl::clarity::vm::{ClarityName, EvaluationResult};
+use clarity_repl::repl::Session;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// Maximum number of executions of a sequence of calls when shrinking a failure.
+const MAX_SHRINK_EXECUTIONS: usize = 2000;
+
+/// Characters used to build `string-utf8` values, on top of printable ASCII characters.
+const UTF8_CHARS: [char; 8] = ['é', 'ß', 'λ', 'ж', '€', '中', '𝄞', '😀'];
+
+pub struct FuzzSettings {
+    /// Contracts to fuzz, all the contracts of the project if empty.
+    pub contracts: Vec<String>,
+    /// Number of sequences of calls executed against each contract.
+    pub runs: u32,
+    /// Number of calls in each sequence.
+    pub calls: u32,
+    /// Seed of the first run. Run `i` is seeded with `seed + i`.
+    pub seed: u64,
+}
+
+/// A public function of a contract, with the read-only function checking its results, if any.
+struct FuzzedFunction {
+    name: ClarityName,
+    args: Vec<TypeSignature>,
+    postcondition: Option<ClarityName>,
+}
+
+struct FuzzedContract {
+    contract_id: QualifiedContractIdentifier,
+    functions: Vec<FuzzedFunction>,
+}
+
+#[derive(Clone)]
+struct Call {
+    function: usize,
+    sender: String,
+    args: Vec<Value>,
+}
+
+/// Index of the call that failed in a sequence, along with the reason of the failure.
+struct Failure {
+    index: usize,
+    reason: String,
+}
+
+/// Calls the public functions of the contracts with random arguments, from random senders,
+/// and reports the calls aborting with a runtime error or rejected by a postcondition.
+/// The failing sequences of calls are shrunk before being reported.
+pub fn fuzz_contracts(
+    manifest: &ProjectManifest,
+    deployment: &DeploymentSpecification,
+    artifacts: DeploymentGenerationArtifacts,
+    settings: &FuzzSettings,
+) -> bool {
+    for name in settings.contracts.iter() {
+        if !manifest.contracts.contains_key(name) {
+            println!(
+                "{}: contract {} not found in Clarinet.toml",
+                red!("error"),
+                name
+            );
+            return false;
+        }
+    }
+
+    let mut session = artifacts.session;
+    session.coverage_reports.clear();
+    session.costs_reports.clear();
+
+    let mut senders = match deployment.genesis {
+        Some(ref genesis) => genesis
+            .wallets
+            .iter()
+            .map(|wallet| wallet.address.to_address())
+            .collect(),
+        None => vec![],
+    };
+    if senders.is_empty() {
+        senders.push(session.get_tx_sender());
+    }
+    let mut principals = senders
+        .iter()
+        .filter_map(|sender| PrincipalData::parse(sender).ok())
+        .collect::<Vec<_>>();
+    for contract_id in deployment.contracts.keys() {
+        principals.push(PrincipalData::Contract(contract_id.clone()));
+    }
+
+    let mut success = true;
+    for contract_id in deployment.contracts.keys() {
+        let name = contract_id.name.to_string();
+        if !manifest.contracts.contains_key(&name)
+            || !(settings.contracts.is_empty() || settings.contracts.contains(&name))
+        {
+            continue;
+        }
+        let analysis = match artifacts.analysis.get(contract_id) {
+            Some(analysis) => analysis,
+            None => continue,
+        };
+        let contract = match get_fuzzed_contract(analysis) {
+            Some(contract) => contract,
+            None => {
+                println!(
+                    "{}: {} has no public function to fuzz",
+                    yellow!("note"),
+                    name
+                );
+                continue;
+            }
+        };
+
+        let fuzzer = Fuzzer {
+            session: &session,
+            contract: &contract,
+            senders: &senders,
+            principals: &principals,
+        };
+        if !fuzzer.run(settings) {
+            success = false;
+        }
+    }
+    success
+}
+
+fn get_fuzzed_contract(analysis: &ContractAnalysis) -> Option<FuzzedContract> {
+    let mut functions = vec![];
+    for (name, function_type) in analysis.public_function_types.iter() {
+        let args = match function_type {
+            FunctionType::Fixed(function) => function
+                .args
+                .iter()
+                .map(|arg| arg.signature.clone())
+                .collect::<Vec<_>>(),
+            _ => continue,
+        };
+        if !args.iter().all(can_generate) {
+            println!(
+                "{}: {} takes arguments that can not be generated, such as traits",
+                yellow!("note"),
+                name
+            );
+            continue;
+        }
+        let postcondition = ClarityName::try_from(format!("postcondition-{}", name))
+            .ok()
+            .filter(|postcondition| {
+                analysis
+                    .read_only_function_types
+                    .contains_key(postcondition)
+            });
+        functions.push(FuzzedFunction {
+            name: name.clone(),
+            args,
+            postcondition,
+        });
+    }
+    if functions.is_empty() {
+        return None;
+    }
+    Some(FuzzedContract {
+        contract_id: analysis.contract_identifier.clone(),
+        functions,
+    })
+}
+
+struct Fuzzer<'a> {
+    session: &'a Session,
+    contract: &'a FuzzedContract,
+    senders: &'a [String],
+    principals: &'a [PrincipalData],
+}
+
+impl<'a> Fuzzer<'a> {
+    fn run(&self, settings: &FuzzSettings) -> bool {
+        let name = &self.contract.contract_id.name;
+        let functions = self
+            .contract
+            .functions
+            .iter()
+            .map(|function| function.name.to_string())
+            .collect::<Vec<_>>();
+        println!(
+            "Fuzzing {} ({}) with {} runs of {} calls",
+            name,
+            functions.join(", "),
+            settings.runs,
+            settings.calls
+        );
+
+        for run in 0..settings.runs {
+            let seed = settings.seed.wrapping_add(run as u64);
+            let mut rng = StdRng::seed_from_u64(seed);
+            let calls = match self.generate_calls(&mut rng, settings.calls) {
+                Some(calls) => calls,
+                None => {
+                    println!(
+                        "{}: unable to generate arguments for the functions of {}",
+                        yellow!("note"),
+                        name
+                    );
+                    return true;
+                }
+            };
+            if let Some(failure) = self.execute(&calls) {
+                println!(
+                    "{} {}: call {} of run {} failed: {}",
+                    red!("x"),
+                    name,
+                    failure.index + 1,
+                    run + 1,
+                    failure.reason
+                );
+                let (calls, failure) = self.shrink(calls, failure);
+                println!("Minimal failing sequence of calls:");
+                for call in calls.iter() {
+                    println!("  {}", self.display_call(call));
+                }
+                println!("Failure: {}", failure.reason);
+                println!(
+                    "Reproduce with: clarinet fuzz {} --seed {} --runs 1 --calls {}",
+                    name, seed, settings.calls
+                );
+                return false;
+            }
+        }
+        println!("{} {}: no failure found", green!("✔"), name);
+        true
+    }
+
+    /// Random sequence of calls, or None if the arguments of the functions can not be generated.
+    fn generate_calls(&self, rng: &mut StdRng, count: u32) -> Option<Vec<Call>> {
+        let mut calls = vec![];
+        for _ in 0..count {
+            let function = rng.gen_range(0..self.contract.functions.len());
+            let sender = self.senders.choose(rng)?.clone();
+            let mut generator = ValueGenerator {
+                rng: &mut *rng,
+                principals: self.principals,
+            };
+            let args = self.contract.functions[function]
+                .args
+                .iter()
+                .map(|arg| generator.generate(arg))
+                .collect::<Option<Vec<_>>>()?;
+            calls.push(Call {
+                function,
+                sender,
+                args,
+            });
+        }
+        Some(calls)
+    }
+
+    /// Executes the calls against a fresh session, and returns the first failure.
+    fn execute(&self, calls: &[Call]) -> Option<Failure> {
+        let mut session = self.session.clone();
+        let contract_id = self.contract.contract_id.to_string();
+        for (index, call) in calls.iter().enumerate() {
+            let function = &self.contract.functions[call.function];
+            let mut args = call.args.iter().map(value_to_source).collect::<Vec<_>>();
+            let result = session.invoke_contract_call(
+                &contract_id,
+                &function.name,
+                &args,
+                &call.sender,
+                "fuzz".to_string(),
+            );
+            let result = match result {
+                Ok(execution) => match execution.result {
+                    EvaluationResult::Snippet(result) => result.result,
+                    _ => unreachable!("Contract result from snippet"),
+                },
+                Err(diagnostics) => {
+                    let reason = match diagnostics.last() {
+                        Some(diagnostic) => format!("runtime error: {}", diagnostic.message),
+                        None => "runtime error".to_string(),
+                    };
+                    return Some(Failure { index, reason });
+                }
+            };
+
+            if let Some(ref postcondition) = function.postcondition {
+                args.push(value_to_source(&result));
+                let reason = match session.invoke_contract_call(
+                    &contract_id,
+                    postcondition,
+                    &args,
+                    &call.sender,
+                    "fuzz".to_string(),
+                ) {
+                    Ok(execution) => match execution.result {
+                        EvaluationResult::Snippet(ref check)
+                            if check.result == Value::Bool(true) =>
+                        {
+                            None
+                        }
+                        _ => Some(format!("{} rejected the result {}", postcondition, result)),
+                    },
+                    Err(diagnostics) => Some(match diagnostics.last() {
+                        Some(diagnostic) => {
+                            format!("runtime error in {}: {}", postcondition, diagnostic.message)
+                        }
+                        None => format!("runtime error in {}", postcondition),
+                    }),
+                };
+                if let Some(reason) = reason {
+                    return Some(Failure { index, reason });
+                }
+            }
+
+            session.coverage_reports.clear();
+            session.costs_reports.clear();
+            session.advance_chain_tip(1);
+        }
+        None
+    }
+
+    /// Removes the calls, and simplifies the senders and arguments of the remaining ones,
+    /// as long as the sequence keeps failing.
+    fn shrink(&self, calls: Vec<Call>, failure: Failure) -> (Vec<Call>, Failure) {
+        let mut calls = calls[..=failure.index].to_vec();
+        let mut failure = failure;
+        let mut executions = 0;
+
+        let mut try_candidate =
+            |candidate: Vec<Call>, calls: &mut Vec<Call>, failure: &mut Failure| {
+                if executions >= MAX_SHRINK_EXECUTIONS {
+                    return false;
+                }
+                executions += 1;
+                match self.execute(&candidate) {
+                    Some(new_failure) => {
+                        *calls = candidate[..=new_failure.index].to_vec();
+                        *failure = new_failure;
+                        true
+                    }
+                    None => false,
+                }
+            };
+
+        // Each pass either makes progress, or is the last one
+        for _ in 0..MAX_SHRINK_EXECUTIONS {
+            let mut progress = false;
+
+            let mut i = 0;
+            while i < calls.len() && calls.len() > 1 {
+                let mut candidate = calls.clone();
+                candidate.remove(i);
+                if try_candidate(candidate, &mut calls, &mut failure) {
+                    progress = true;
+                } else {
+                    i += 1;
+                }
+            }
+
+            for i in 0..calls.len() {
+                if i >= calls.len() {
+                    break;
+                }
+                if calls[i].sender != self.senders[0] {
+                    let mut candidate = calls.clone();
+                    candidate[i].sender = self.senders[0].clone();
+                    if try_candidate(candidate, &mut calls, &mut failure) {
+                        progress = true;
+                    }
+                }
+                let mut j = 0;
+                while i < calls.len() && j < calls[i].args.len() {
+                    let simpler_values = shrink_value(&calls[i].args[j], self.principals);
+                    for value in simpler_values.into_iter() {
+                        let mut candidate = calls.clone();
+                        candidate[i].args[j] = value;
+                        if try_candidate(candidate, &mut calls, &mut failure) {
+                            progress = true;
+                            break;
+                        }
+                    }
+                    j += 1;
+                }
+            }
+
+            if !progress {
+                break;
+            }
+        }
+        (calls, failure)
+    }
+
+    fn display_call(&self, call: &Call) -> String {
+        let function = &self.contract.functions[call.function];
+        let mut expr = vec![
+            "contract-call?".to_string(),
+            format!("'{}", self.contract.contract_id),
+            function.name.to_string(),
+        ];
+        expr.extend(call.args.iter().map(value_to_source));
+        format!("({}) ;; sender: {}", expr.join(" "), call.sender)
+    }
+}
+
+fn can_generate(type_signature: &TypeSignature) -> bool {
+    match type_signature {
+        TypeSignature::UIntType
+        | TypeSignature::IntType
+        | TypeSignature::BoolType
+        | TypeSignature::PrincipalType
+        | TypeSignature::SequenceType(SequenceSubtype::BufferType(_))
+        | TypeSignature::SequenceType(SequenceSubtype::StringType(_)) => true,
+        TypeSignature::SequenceType(SequenceSubtype::ListType(list_type)) => {
+            can_generate(list_type.get_list_item_type())
+        }
+        TypeSignature::TupleType(tuple_type) => {
+            tuple_type.get_type_map().values().all(can_generate)
+        }
+        TypeSignature::OptionalType(inner_type) => can_generate(inner_type),
+        TypeSignature::ResponseType(response_type) => {
+            can_generate(&response_type.0) && can_generate(&response_type.1)
+        }
+        _ => false,
+    }
+}
+
+struct ValueGenerator<'a> {
+    rng: &'a mut StdRng,
+    principals: &'a [PrincipalData],
+}
+
+impl<'a> ValueGenerator<'a> {
+    /// Random value of the given type, with a bias towards edge cases.
+    /// Returns None for types that can not be generated, such as traits.
+    fn generate(&mut self, type_signature: &TypeSignature) -> Option<Value> {
+        let edge_case = self.rng.gen_ratio(1, 4);
+        let value = match type_signature {
+            TypeSignature::UIntType => Value::UInt(if edge_case {
+                *[0, 1, u128::MAX].choose(self.rng)?
+            } else if self.rng.gen_bool(0.5) {
+                self.rng.gen_range(0..=1000)
+            } else {
+                self.rng.gen()
+            }),
+            TypeSignature::IntType => Value::Int(if edge_case {
+                *[0, 1, -1, i128::MIN, i128::MAX].choose(self.rng)?
+            } else if self.rng.gen_bool(0.5) {
+                self.rng.gen_range(-1000..=1000)
+            } else {
+                self.rng.gen()
+            }),
+            TypeSignature::BoolType => Value::Bool(self.rng.gen()),
+            TypeSignature::PrincipalType => {
+                Value::Principal(self.principals.choose(self.rng)?.clone())
+            }
+            TypeSignature::SequenceType(SequenceSubtype::BufferType(length)) => {
+                let length = self.generate_length(u32::from(length), edge_case);
+                let data = (0..length).map(|_| self.rng.gen()).collect();
+                Value::Sequence(SequenceData::Buffer(BuffData { data }))
+            }
+            TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::ASCII(
+                length,
+            ))) => {
+                let length = self.generate_length(u32::from(length), edge_case);
+                let data = (0..length)
+                    .map(|_| self.rng.gen_range(0x20..0x7f))
+                    .collect();
+                Value::Sequence(SequenceData::String(CharType::ASCII(ASCIIData { data })))
+            }
+            TypeSignature::SequenceType(SequenceSubtype::StringType(StringSubtype::UTF8(
+                length,
+            ))) => {
+                let length = self.generate_length(u32::from(length), edge_case);
+                let data = (0..length)
+                    .map(|_| {
+                        let c = if self.rng.gen_bool(0.5) {
+                            self.rng.gen_range(0x20u8..0x7f) as char
+                        } else {
+                            *UTF8_CHARS.choose(self.rng).unwrap()
+                        };
+                        c.to_string().into_bytes()
+                    })
+                    .collect();
+                Value::Sequence(SequenceData::String(CharType::UTF8(UTF8Data { data })))
+            }
+            TypeSignature::SequenceType(SequenceSubtype::ListType(list_type)) => {
+                let length = self.generate_length(list_type.get_max_len(), edge_case);
+                let data = (0..length)
+                    .map(|_| self.generate(list_type.get_list_item_type()))
+                    .collect::<Option<Vec<_>>>()?;
+                Value::Sequence(SequenceData::List(ListData {
+                    data,
+                    type_signature: list_type.clone(),
+                }))
+            }
+            TypeSignature::TupleType(tuple_type) => {
+                let data = tuple_type
+                    .get_type_map()
+                    .iter()
+                    .map(|(name, field_type)| Some((name.clone(), self.generate(field_type)?)))
+                    .collect::<Option<Vec<_>>>()?;
+                Value::Tuple(TupleData::from_data(data).ok()?)
+            }
+            TypeSignature::OptionalType(inner_type) => {
+                let data = if edge_case || self.rng.gen_bool(0.5) {
+                    None
+                } else {
+                    Some(Box::new(self.generate(inner_type)?))
+                };
+                Value::Optional(OptionalData { data })
+            }
+            TypeSignature::ResponseType(response_type) => {
+                let (ok_type, err_type) = &**response_type;
+                let committed = self.rng.gen_bool(0.5);
+                let data = match committed {
+                    true => self.generate(ok_type)?,
+                    false => self.generate(err_type)?,
+                };
+                Value::Response(ResponseData {
+                    committed,
+                    data: Box::new(data),
+                })
+            }
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Length of a sequence: empty or full for edge cases, random otherwise.
+    fn generate_length(&mut self, max_length: u32, edge_case: bool) -> u32 {
+        match edge_case {
+            true if self.rng.gen_bool(0.5) => 0,
+            true => max_length,
+            false => self.rng.gen_range(0..=max_length),
+        }
+    }
+}
+
+/// Simpler values than the given one, the simplest first.
+fn shrink_value(value: &Value, principals: &[PrincipalData]) -> Vec<Value> {
+    let mut values = match value {
+        Value::UInt(n) if *n > 0 => vec![Value::UInt(0), Value::UInt(n / 2), Value::UInt(n - 1)],
+        Value::Int(n) if *n != 0 => {
+            vec![Value::Int(0), Value::Int(n / 2), Value::Int(n - n.signum())]
+        }
+        Value::Bool(true) => vec![Value::Bool(false)],
+        Value::Principal(principal) => match principals.first() {
+            Some(first) if first != principal => vec![Value::Principal(first.clone())],
+            _ => vec![],
+        },
+        Value::Sequence(SequenceData::Buffer(buff)) => shrink_sequence(&buff.data)
+            .into_iter()
+            .map(|data| Value::Sequence(SequenceData::Buffer(BuffData { data })))
+            .collect(),
+        Value::Sequence(SequenceData::String(CharType::ASCII(string))) => {
+            shrink_sequence(&string.data)
+                .into_iter()
+                .map(|data| {
+                    Value::Sequence(SequenceData::String(CharType::ASCII(ASCIIData { data })))
+                })
+                .collect()
+        }
+        Value::Sequence(SequenceData::String(CharType::UTF8(string))) => {
+            shrink_sequence(&string.data)
+                .into_iter()
+                .map(|data| {
+                    Value::Sequence(SequenceData::String(CharType::UTF8(UTF8Data { data })))
+                })
+                .collect()
+        }
+        Value::Sequence(SequenceData::List(list)) => {
+            let mut lists = shrink_sequence(&list.data);
+            for (i, item) in list.data.iter().enumerate() {
+                for simpler_item in shrink_value(item, principals).into_iter() {
+                    let mut data = list.data.clone();
+                    data[i] = simpler_item;
+                    lists.push(data);
+                }
+            }
+            lists
+                .into_iter()
+                .map(|data| {
+                    Value::Sequence(SequenceData::List(ListData {
+                        data,
+                        type_signature: list.type_signature.clone(),
+                    }))
+                })
+                .collect()
+        }
+        Value::Tuple(tuple) => {
+            let mut tuples = vec![];
+            for (name, field) in tuple.data_map.iter() {
+                for simpler_field in shrink_value(field, principals).into_iter() {
+                    let mut simpler_tuple = tuple.clone();
+                    simpler_tuple.data_map.insert(name.clone(), simpler_field);
+                    tuples.push(Value::Tuple(simpler_tuple));
+                }
+            }
+            tuples
+        }
+        Value::Optional(OptionalData { data: Some(inner) }) => {
+            let mut optionals = vec![Value::Optional(OptionalData { data: None })];
+            for simpler_inner in shrink_value(inner, principals).into_iter() {
+                optionals.push(Value::Optional(OptionalData {
+                    data: Some(Box::new(simpler_inner)),
+                }));
+            }
+            optionals
+        }
+        Value::Response(response) => shrink_value(&response.data, principals)
+            .into_iter()
+            .map(|data| {
+                Value::Response(ResponseData {
+                    committed: response.committed,
+                    data: Box::new(data),
+                })
+            })
+            .collect(),
+        _ => vec![],
+    };
+    values.dedup();
+    values.retain(|simpler_value| simpler_value != value);
+    values
+}
+
+/// Empty sequence, first half and all but the last element of a non-empty sequence.
+fn shrink_sequence<T: Clone>(data: &[T]) -> Vec<Vec<T>> {
+    if data.is_empty() {
+        return vec![];
+    }
+    let mut sequences = vec![vec![]];
+    if data.len() > 2 {
+        sequences.push(data[..data.len() / 2].to_vec());
+    }
+    if data.len() > 1 {
+        sequences.push(data[..data.len() - 1].to_vec());
+    }
+    sequences
+}
+
+/// Clarity expression evaluating to the given value, used as an argument of a call.
+fn value_to_source(value: &Value) -> String {
+    match value {
+        Value::Int(n) => format!("{}", n),
+        Value::UInt(n) => format!("u{}", n),
+        Value::Bool(b) => format!("{}", b),
+        Value::Principal(principal) => format!("'{}", principal),
+        Value::Sequence(SequenceData::Buffer(buff)) => format!("0x{}", hash::to_hex(&buff.data)),
+        Value::Sequence(SequenceData::String(CharType::ASCII(string))) => {
+            let mut source = String::from("\"");
+            for c in string.data.iter().map(|b| *b as char) {
+                match c {
+                    '"' | '\\' => {
+                        source.push('\\');
+                        source.push(c);
+                    }
+                    _ => source.push(c),
+                }
+            }
+            source.push('"');
+            source
+        }
+        Value::Sequence(SequenceData::String(CharType::UTF8(string))) => {
+            let mut source = String::from("u\"");
+            for c in string
+                .data
+                .iter()
+                .filter_map(|bytes| std::str::from_utf8(bytes).ok())
+                .flat_map(|s| s.chars())
+            {
+                match c {
+                    '"' | '\\' => {
+                        source.push('\\');
+                        source.push(c);
+                    }
+                    c if c.is_ascii() => source.push(c),
+                    c => source.push_str(&format!("\\u{{{:x}}}", c as u32)),
+                }
+            }
+            source.push('"');
+            source
+        }
+        Value::Sequence(SequenceData::List(list)) => {
+            let mut items = vec!["list".to_string()];
+            items.extend(list.data.iter().map(value_to_source));
+            format!("({})", items.join(" "))
+        }
+        Value::Tuple(tuple) => {
+            let mut fields = vec!["tuple".to_string()];
+            for (name, field) in tuple.data_map.iter() {
+                fields.push(format!("({} {})", name, value_to_source(field)));
+            }
+            format!("({})", fields.join(" "))
+        }
+        Value::Optional(OptionalData { data: Some(inner) }) => {
+            format!("(some {})", value_to_source(inner))
+        }
+        Value::Optional(OptionalData { data: None }) => "none".to_string(),
+        Value::Response(response) => match response.committed {
+            true => format!("(ok {})", value_to_source(&response.data)),
+            false => format!("(err {})", value_to_source(&response.data)),
+        },
+        _ => format!("{}", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clarity_repl::repl::SessionSettings;
+
+    fn deploy(source: &str) -> (Session, FuzzedContract) {
+        let mut session = Session::new(SessionSettings::default());
+        let execution = session
+            .interpret(
+                source.to_string(),
+                Some("fuzzed".to_string()),
+                None,
+                false,
+                None,
+                None,
+            )
+            .unwrap();
+        let analysis = match execution.result {
+            EvaluationResult::Contract(contract_result) => contract_result.contract.analysis,
+            _ => unreachable!("Snippet result from contract"),
+        };
+        (session, get_fuzzed_contract(&analysis).unwrap())
+    }
+
+    #[test]
+    fn test_generate_respects_bounds() {
+        let (session, contract) = deploy(
+            "(define-public (bounded (data (buff 4)) (items (list 3 uint)) (text (string-utf8 5)))
+                (ok true))",
+        );
+        let principals = vec![PrincipalData::parse(&session.get_tx_sender()).unwrap()];
+        let args = &contract.functions[0].args;
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut generator = ValueGenerator {
+                rng: &mut rng,
+                principals: &principals,
+            };
+            match generator.generate(&args[0]) {
+                Some(Value::Sequence(SequenceData::Buffer(buff))) => {
+                    assert!(buff.data.len() <= 4)
+                }
+                value => panic!("expected a buffer, got {:?}", value),
+            }
+            match generator.generate(&args[1]) {
+                Some(Value::Sequence(SequenceData::List(list))) => {
+                    assert!(list.data.len() <= 3);
+                    assert!(list.data.iter().all(|item| matches!(item, Value::UInt(_))));
+                }
+                value => panic!("expected a list, got {:?}", value),
+            }
+            match generator.generate(&args[2]) {
+                Some(Value::Sequence(SequenceData::String(CharType::UTF8(string)))) => {
+                    assert!(string.data.len() <= 5);
+                    for bytes in string.data.iter() {
+                        let c = std::str::from_utf8(bytes).unwrap();
+                        assert_eq!(c.chars().count(), 1);
+                    }
+                }
+                value => panic!("expected a utf8 string, got {:?}", value),
+            }
+        }
+    }
+
+    #[test]
+    fn test_shrink_failing_sequence() {
+        let (session, contract) = deploy(
+            "(define-data-var total uint u0)
+            (define-public (add (amount uint))
+                (begin
+                    (var-set total (+ (var-get total) amount))
+                    (ok (var-get total))))
+            (define-read-only (postcondition-add (amount uint) (result (response uint uint)))
+                (< (var-get total) u1000))",
+        );
+        let senders = vec![session.get_tx_sender()];
+        let principals = vec![PrincipalData::parse(&senders[0]).unwrap()];
+        let fuzzer = Fuzzer {
+            session: &session,
+            contract: &contract,
+            senders: &senders,
+            principals: &principals,
+        };
+
+        let calls = [400, 1, 700, 3]
+            .iter()
+            .map(|amount| Call {
+                function: 0,
+                sender: senders[0].clone(),
+                args: vec![Value::UInt(*amount)],
+            })
+            .collect::<Vec<_>>();
+        let failure = fuzzer.execute(&calls).unwrap();
+        assert_eq!(failure.index, 2);
+
+        let (calls, failure) = fuzzer.shrink(calls, failure);
+        let amounts = calls
+            .iter()
+            .map(|call| match call.args[0] {
+                Value::UInt(amount) => amount,
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(amounts.len(), 2);
+        assert_eq!(amounts.iter().sum::<u128>(), 1000);
+        assert_eq!(failure.index, 1);
+        assert!(failure.reason.starts_with("postcondition-add rejected"));
+    }
+
+    #[test]
+    fn test_same_seed_same_calls() {
+        let (session, contract) = deploy(
+            "(define-public (transfer (amount uint) (memo (optional (buff 8))))
+                (ok amount))
+            (define-public (vote (choice (string-ascii 10)) (approve bool))
+                (ok approve))",
+        );
+        let senders = vec![session.get_tx_sender()];
+        let principals = vec![PrincipalData::parse(&senders[0]).unwrap()];
+        let fuzzer = Fuzzer {
+            session: &session,
+            contract: &contract,
+            senders: &senders,
+            principals: &principals,
+        };
+
+        let generate = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            fuzzer
+                .generate_calls(&mut rng, 10)
+                .unwrap()
+                .iter()
+                .map(|call| fuzzer.display_call(call))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(generate(42), generate(42));
+        assert_ne!(generate(42), generate(43));
+    }
+}
@@ -1,10 +1,10 @@
 use clarinet_deployments::types::DeploymentGenerationArtifacts;
 use clarinet_deployments::{
-    initiate_session_from_deployment, update_session_with_contracts_executions,
+    fork_session, initiate_session_from_deployment, update_session_with_contracts_executions,
     update_session_with_genesis_accounts,
 };
 use clarinet_files::{FileLocation, ProjectManifest};
-use clarity_repl::analysis::coverage::TestCoverageReport;
+use clarity_repl::analysis::coverage::{CoverageReporter, TestCoverageReport};
 use clarity_repl::clarity::vm::analysis::contract_interface_builder::{
     build_contract_interface, ContractInterface,
 };
@@ -18,10 +18,14 @@ use std::collections::HashMap;
 use clarinet_deployments::types::DeploymentSpecification;
 
 mod api_v1;
+mod clarity_tests;
 mod costs;
 mod deno;
+mod fuzz;
 mod vendor;
 
+pub use fuzz::{fuzz_contracts, FuzzSettings};
+
 #[derive(Clone)]
 pub struct DeploymentCache {
     pub session: Session,
@@ -39,6 +43,9 @@ impl DeploymentCache {
         artifacts: DeploymentGenerationArtifacts,
     ) -> DeploymentCache {
         let mut session_accounts_only = initiate_session_from_deployment(&manifest);
+        if let Err(e) = fork_session(&mut session_accounts_only, manifest) {
+            eprintln!("{}", e);
+        }
         update_session_with_genesis_accounts(&mut session_accounts_only, &deployment);
         let mut session = session_accounts_only.clone();
 
@@ -108,8 +115,45 @@ pub fn run_scripts(
     allow_net: bool,
     cache_location: FileLocation,
 ) -> Result<usize, (AnyError, usize)> {
+    let include = if include.is_empty() {
+        let mut tests_default = manifest.location.get_project_root_location().unwrap();
+        tests_default.append_path("tests").unwrap();
+        vec![tests_default.to_string()]
+    } else {
+        include
+    };
+
+    // Test contracts written in Clarity are run natively, before the TypeScript tests
+    let test_contracts = clarity_tests::collect_test_contracts(&include).map_err(|e| (e, 0))?;
+    // The watcher only re-runs the TypeScript tests
+    if watch && !test_contracts.is_empty() {
+        return Err((
+            AnyError::msg("--watch is not supported with Clarity test contracts (*_test.clar)"),
+            0,
+        ));
+    }
+    let native_tests = if test_contracts.is_empty() {
+        (true, vec![])
+    } else {
+        clarity_tests::run_tests(&test_contracts, manifest, &cache, fail_fast, &filter)
+    };
+
+    let scripts: Vec<String> = include
+        .into_iter()
+        .filter(|path| !path.ends_with(".clar"))
+        .collect();
+    if !test_contracts.is_empty() && !deno::has_test_scripts(&scripts) {
+        let (success, artifacts) = native_tests;
+        if !success {
+            return Err((AnyError::msg("Test suite failed"), artifacts.len()));
+        }
+        report_sessions_artifacts(&artifacts, &cache, include_costs_report, include_coverage)
+            .map_err(|e| (e, 0))?;
+        return Ok(artifacts.len());
+    }
+
     block_on(deno::do_run_scripts(
-        include,
+        scripts,
         include_coverage,
         include_costs_report,
         watch,
@@ -123,9 +167,41 @@ pub fn run_scripts(
         import_map,
         allow_net,
         cache_location,
+        native_tests,
     ))
 }
 
+fn report_sessions_artifacts(
+    artifacts: &Vec<SessionArtifacts>,
+    cache: &DeploymentCache,
+    display_costs_report: bool,
+    generate_coverage: bool,
+) -> Result<(), AnyError> {
+    if display_costs_report {
+        costs::display_costs_report(artifacts)
+    }
+
+    if generate_coverage {
+        let mut coverage_reporter = CoverageReporter::new();
+        for (contract_id, analysis_artifacts) in cache.contracts_artifacts.iter() {
+            coverage_reporter
+                .asts
+                .insert(contract_id.clone(), analysis_artifacts.ast.clone());
+        }
+        for (contract_id, (_, contract_location)) in cache.deployment.contracts.iter() {
+            coverage_reporter
+                .contract_paths
+                .insert(contract_id.name.to_string(), contract_location.to_string());
+        }
+        for artifact in artifacts.iter() {
+            let mut coverage_reports = artifact.coverage_reports.clone();
+            coverage_reporter.reports.append(&mut coverage_reports);
+        }
+        coverage_reporter.write_lcov_file("coverage.lcov")?;
+    }
+    Ok(())
+}
+
 pub fn block_on<F, R>(future: F) -> R
 where
     F: std::future::Future<Output = R>,
@@ -23,7 +23,11 @@ use clarity_repl::clarity::vm::ExecutionResult;
 use clarity_repl::repl::Session;
 use clarity_repl::repl::SessionSettings;
 use orchestra_types::StacksNetwork;
+#[cfg(feature = "cli")]
+use stacks_rpc_client::StacksNodeDatastore;
 use std::collections::{BTreeMap, HashMap, VecDeque};
+#[cfg(feature = "cli")]
+use std::sync::Arc;
 use types::ContractPublishSpecification;
 use types::DeploymentGenerationArtifacts;
 use types::RequirementPublishSpecification;
@@ -35,6 +39,10 @@ pub fn setup_session_with_deployment(
     contracts_asts: Option<&HashMap<QualifiedContractIdentifier, ContractAST>>,
 ) -> DeploymentGenerationArtifacts {
     let mut session = initiate_session_from_deployment(&manifest);
+    #[cfg(feature = "cli")]
+    if let Err(e) = fork_session(&mut session, manifest) {
+        eprintln!("{}", e);
+    }
     update_session_with_genesis_accounts(&mut session, deployment);
     let results =
         update_session_with_contracts_executions(&mut session, deployment, contracts_asts, false);
@@ -81,8 +89,23 @@ pub fn initiate_session_from_deployment(manifest: &ProjectManifest) -> Session {
         .append(&mut manifest.project.boot_contracts.clone());
     settings.repl_settings = manifest.repl_settings.clone();
     settings.disk_cache_enabled = true;
-    let session = Session::new(settings);
-    session
+    Session::new(settings)
+}
+
+/// In fork mode (`repl.fork` set in the manifest), read the contracts deployed
+/// on the chain of a Stacks node from this node. Sessions are not forked by
+/// `initiate_session_from_deployment`, so that the language server never
+/// reaches the node.
+#[cfg(feature = "cli")]
+pub fn fork_session(session: &mut Session, manifest: &ProjectManifest) -> Result<(), String> {
+    let fork = match manifest.repl_settings.fork {
+        Some(ref fork) => fork,
+        None => return Ok(()),
+    };
+    let remote = StacksNodeDatastore::new(fork)
+        .map_err(|e| format!("unable to fork {}: {}", fork.node_url, e))?;
+    session.interpreter.datastore.set_remote(Arc::new(remote));
+    Ok(())
 }
 
 pub fn update_session_with_genesis_accounts(
@@ -122,6 +145,14 @@ pub fn update_session_with_contracts_executions(
                 TransactionSpecification::EmulatedContractPublish(tx) => {
                     let default_tx_sender = session.get_tx_sender();
                     session.set_tx_sender(tx.emulated_sender.to_string());
+                    let default_epoch = session.interpreter.get_epoch();
+                    let default_clarity_version = session.interpreter.get_clarity_version();
+                    if let Some(epoch) = tx.epoch {
+                        session.interpreter.set_epoch(epoch);
+                    }
+                    if let Some(clarity_version) = tx.clarity_version {
+                        session.interpreter.set_clarity_version(clarity_version);
+                    }
 
                     let contract_id = QualifiedContractIdentifier::new(
                         tx.emulated_sender.clone(),
@@ -141,6 +172,10 @@ pub fn update_session_with_contracts_executions(
                     );
                     results.insert(contract_id, result);
                     session.set_tx_sender(default_tx_sender);
+                    session.interpreter.set_epoch(default_epoch);
+                    session
+                        .interpreter
+                        .set_clarity_version(default_clarity_version);
                 }
                 TransactionSpecification::EmulatedContractCall(tx) => {
                     let _ = session.invoke_contract_call(
@@ -328,6 +363,8 @@ pub async fn generate_default_deployment(
                             emulated_sender: contract_id.issuer.clone(),
                             source: source.clone(),
                             location: contract_location,
+                            clarity_version: None,
+                            epoch: None,
                         };
                         emulated_contracts_publish.insert(contract_id.clone(), data);
                     } else if network.either_devnet_or_testnet() {
@@ -495,6 +532,8 @@ pub async fn generate_default_deployment(
                     emulated_sender: sender,
                     source,
                     location: contract_location,
+                    clarity_version: contract_config.clarity_version,
+                    epoch: contract_config.epoch,
                 },
             )
         } else {
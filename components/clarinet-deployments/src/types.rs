@@ -1,11 +1,12 @@
 use clarinet_files::FileLocation;
+use clarity_repl::clarity::types::StacksEpochId;
 use clarity_repl::clarity::vm::analysis::ContractAnalysis;
 use clarity_repl::clarity::vm::ast::ContractAST;
 use clarity_repl::clarity::vm::diagnostic::Diagnostic;
 use clarity_repl::clarity::vm::types::{
     PrincipalData, QualifiedContractIdentifier, StandardPrincipalData,
 };
-use clarity_repl::clarity::vm::{ClarityName, ContractName};
+use clarity_repl::clarity::vm::{ClarityName, ClarityVersion, ContractName};
 use orchestra_types::StacksNetwork;
 use serde::{Deserialize, Serialize};
 use serde_yaml;
@@ -125,6 +126,10 @@ pub struct EmulatedContractPublishSpecificationFile {
     pub path: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clarity_version: Option<ClarityVersion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<StacksEpochId>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -421,6 +426,11 @@ pub struct EmulatedContractPublishSpecification {
     pub emulated_sender: StandardPrincipalData,
     pub source: String,
     pub location: FileLocation,
+    /// Clarity version of the contract, the default version of the epoch of
+    /// the session if not set
+    pub clarity_version: Option<ClarityVersion>,
+    /// Epoch to which the session switches before deploying the contract
+    pub epoch: Option<StacksEpochId>,
 }
 
 impl EmulatedContractPublishSpecification {
@@ -466,6 +476,8 @@ impl EmulatedContractPublishSpecification {
             emulated_sender,
             source,
             location,
+            clarity_version: specs.clarity_version,
+            epoch: specs.epoch,
         })
     }
 }
@@ -803,6 +815,8 @@ impl TransactionPlanSpecification {
                                 location: Some(tx.location.clone()),
                                 path: None,
                                 url: None,
+                                clarity_version: tx.clarity_version,
+                                epoch: tx.epoch,
                             },
                         )
                     }
@@ -1,7 +1,9 @@
 use crate::FileAccessor;
 
 use super::FileLocation;
+use clarity_repl::analysis::lint_checker;
 use clarity_repl::clarity::types::StacksEpochId;
+use clarity_repl::clarity::vm::ClarityVersion;
 use clarity_repl::repl;
 use std::collections::BTreeMap;
 use toml::value::Value;
@@ -61,6 +63,20 @@ pub struct RequirementConfig {
 pub struct ContractConfig {
     pub path: String,
     pub deployer: Option<String>,
+    /// Clarity version with which the contract is deployed, the default
+    /// version of its epoch if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clarity_version: Option<ClarityVersion>,
+    /// Epoch in which the contract is deployed, the epoch of the session
+    /// if not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<StacksEpochId>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LintRulesFile {
+    #[serde(default)]
+    rule: Vec<lint_checker::Rule>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -120,6 +136,9 @@ impl ProjectManifest {
 
         let project_name = project_manifest_file.project.name;
         let mut project_root_location = manifest_location.get_parent_location()?;
+        repl_settings
+            .analysis
+            .add_lint_rules(load_lint_rules(&project_root_location)?);
         let cache_location = match project_manifest_file.project.cache_dir {
             Some(ref path) => FileLocation::try_parse(path, Some(&project_root_location))
                 .ok_or(format!("unable to parse path {}", path))?,
@@ -210,9 +229,26 @@ impl ProjectManifest {
                                 Some(Value::String(path)) => Some(path.to_string()),
                                 _ => None,
                             };
+                            let clarity_version = match contract_settings.get("clarity_version") {
+                                Some(value) => Some(value.clone().try_into().map_err(|e| {
+                                    format!("invalid clarity_version for {}: {}", contract_name, e)
+                                })?),
+                                None => None,
+                            };
+                            let epoch = match contract_settings.get("epoch") {
+                                Some(value) => Some(value.clone().try_into().map_err(|e| {
+                                    format!("invalid epoch for {}: {}", contract_name, e)
+                                })?),
+                                None => None,
+                            };
                             config_contracts.insert(
                                 contract_name.to_string(),
-                                ContractConfig { path, deployer },
+                                ContractConfig {
+                                    path,
+                                    deployer,
+                                    clarity_version,
+                                    epoch,
+                                },
                             );
                         }
                         _ => {}
@@ -226,3 +262,46 @@ impl ProjectManifest {
         Ok(config)
     }
 }
+
+/// Load the custom lint rules declared in the `settings/lints/*.toml` files
+/// of the project, in the order of the file names.
+#[cfg(not(feature = "wasm"))]
+fn load_lint_rules(
+    project_root_location: &FileLocation,
+) -> Result<Vec<lint_checker::Rule>, String> {
+    let mut lints_location = project_root_location.clone();
+    lints_location.append_path("settings/lints")?;
+    let lints_path = match lints_location {
+        FileLocation::FileSystem { path } => path,
+        FileLocation::Url { url } => match url.to_file_path() {
+            Ok(path) => path,
+            Err(_) => return Ok(vec![]),
+        },
+    };
+    if !lints_path.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut paths = std::fs::read_dir(&lints_path)
+        .map_err(|e| format!("unable to read directory {}\n{}", lints_path.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext == "toml"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    let mut rules = vec![];
+    for path in paths {
+        let content = FileLocation::from_path(path.clone()).read_content()?;
+        let lint_rules_file: LintRulesFile = toml::from_slice(&content)
+            .map_err(|e| format!("{} file malformatted {:?}", path.display(), e))?;
+        rules.extend(lint_rules_file.rule);
+    }
+    Ok(rules)
+}
+
+// The directories of the project cannot be listed from the browser
+#[cfg(feature = "wasm")]
+fn load_lint_rules(
+    _project_root_location: &FileLocation,
+) -> Result<Vec<lint_checker::Rule>, String> {
+    Ok(vec![])
+}
@@ -1,4 +1,8 @@
-use crate::lsp_types::MessageType;
+use crate::lsp_types::{
+    CodeAction, DocumentSymbol, FormattingOptions, Hover, Location, MessageType, Position, Range,
+    SignatureHelp, SymbolInformation, TextEdit, WorkspaceEdit,
+};
+use crate::requests::formatting::get_formatting_edits;
 use crate::state::{build_state, EditorState, ProtocolState};
 use crate::types::{CompletionItem, CompletionItemKind};
 use clarinet_files::{FileAccessor, FileLocation};
@@ -11,11 +15,22 @@ pub enum LspNotification {
     ManifestChanged(FileLocation),
     ContractOpened(FileLocation),
     ContractChanged(FileLocation),
+    ContractEdited(FileLocation, String),
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum LspRequest {
     GetIntellisense(FileLocation),
+    GetDefinition(FileLocation, Position),
+    GetReferences(FileLocation, Position, bool),
+    GetHover(FileLocation, Position),
+    GetSignatureHelp(FileLocation, Position, Option<String>),
+    GetCodeActions(FileLocation, Range),
+    GetFormatting(String, FormattingOptions),
+    GetDocumentSymbols(FileLocation),
+    GetWorkspaceSymbols(String),
+    PrepareRename(FileLocation, Position),
+    Rename(FileLocation, Position, String),
 }
 
 #[derive(Debug, PartialEq, Deserialize, Serialize)]
@@ -23,6 +38,15 @@ pub struct LspResponse {
     pub aggregated_diagnostics: Vec<(FileLocation, Vec<Diagnostic>)>,
     pub notification: Option<(MessageType, String)>,
     pub completion_items: Vec<CompletionItem>,
+    pub locations: Vec<Location>,
+    pub hover: Option<Hover>,
+    pub signature_help: Option<SignatureHelp>,
+    pub code_actions: Vec<CodeAction>,
+    pub text_edits: Option<Vec<TextEdit>>,
+    pub document_symbols: Vec<DocumentSymbol>,
+    pub workspace_symbols: Vec<SymbolInformation>,
+    pub rename_range: Option<Range>,
+    pub workspace_edit: Option<WorkspaceEdit>,
 }
 
 impl LspResponse {
@@ -31,6 +55,15 @@ impl LspResponse {
             aggregated_diagnostics: vec![],
             notification: None,
             completion_items: vec![],
+            locations: vec![],
+            hover: None,
+            signature_help: None,
+            code_actions: vec![],
+            text_edits: None,
+            document_symbols: vec![],
+            workspace_symbols: vec![],
+            rename_range: None,
+            workspace_edit: None,
         }
     }
 }
@@ -38,9 +71,8 @@ impl LspResponse {
 impl LspResponse {
     pub fn error(message: &str) -> LspResponse {
         LspResponse {
-            aggregated_diagnostics: vec![],
-            completion_items: vec![],
             notification: Some((MessageType::ERROR, format!("Internal error: {}", message))),
+            ..LspResponse::default()
         }
     }
 }
@@ -77,7 +109,7 @@ pub async fn process_notification(
                     return Ok(LspResponse {
                         aggregated_diagnostics,
                         notification,
-                        completion_items: vec![],
+                        ..LspResponse::default()
                     });
                 }
                 Err(e) => return Ok(LspResponse::error(&e)),
@@ -105,7 +137,7 @@ pub async fn process_notification(
                     return Ok(LspResponse {
                         aggregated_diagnostics,
                         notification,
-                        completion_items: vec![],
+                        ..LspResponse::default()
                     });
                 }
                 Err(e) => return Ok(LspResponse::error(&e)),
@@ -124,7 +156,7 @@ pub async fn process_notification(
                     return Ok(LspResponse {
                         aggregated_diagnostics,
                         notification,
-                        completion_items: vec![],
+                        ..LspResponse::default()
                     });
                 }
                 Err(e) => return Ok(LspResponse::error(&e)),
@@ -153,7 +185,30 @@ pub async fn process_notification(
                     return Ok(LspResponse {
                         aggregated_diagnostics,
                         notification,
-                        completion_items: vec![],
+                        ..LspResponse::default()
+                    });
+                }
+                Err(e) => return Ok(LspResponse::error(&e)),
+            };
+        }
+        LspNotification::ContractEdited(contract_location, source) => {
+            // Unsaved changes: only the edited contract and its dependents are re-analysed
+            let manifest_location = match editor_state.contracts_lookup.get(&contract_location) {
+                Some(contract_metadata) => contract_metadata.manifest_location.clone(),
+                None => return Ok(LspResponse::default()),
+            };
+            let protocol_state = match editor_state.protocols.get_mut(&manifest_location) {
+                Some(protocol_state) => protocol_state,
+                None => return Ok(LspResponse::default()),
+            };
+            match protocol_state.run_incremental_analysis(&contract_location, source) {
+                Ok(_) => {
+                    let (aggregated_diagnostics, notification) =
+                        editor_state.get_aggregated_diagnostics();
+                    return Ok(LspResponse {
+                        aggregated_diagnostics,
+                        notification,
+                        ..LspResponse::default()
                     });
                 }
                 Err(e) => return Ok(LspResponse::error(&e)),
@@ -197,9 +252,94 @@ pub fn process_request(command: LspRequest, editor_state: &EditorState) -> LspRe
             }
 
             LspResponse {
-                aggregated_diagnostics: vec![],
-                notification: None,
                 completion_items,
+                ..LspResponse::default()
+            }
+        }
+        LspRequest::GetDefinition(contract_location, position) => {
+            let locations = editor_state
+                .get_definition_location(&contract_location, &position)
+                .into_iter()
+                .collect();
+            LspResponse {
+                locations,
+                ..LspResponse::default()
+            }
+        }
+        LspRequest::GetReferences(contract_location, position, include_declaration) => {
+            let locations =
+                editor_state.get_references(&contract_location, &position, include_declaration);
+            LspResponse {
+                locations,
+                ..LspResponse::default()
+            }
+        }
+        LspRequest::GetHover(contract_location, position) => {
+            let hover = editor_state.get_hover_content(&contract_location, &position);
+            LspResponse {
+                hover,
+                ..LspResponse::default()
+            }
+        }
+        LspRequest::GetSignatureHelp(contract_location, position, source) => {
+            let signature_help =
+                editor_state.get_signature_help(&contract_location, &position, source.as_deref());
+            LspResponse {
+                signature_help,
+                ..LspResponse::default()
+            }
+        }
+        LspRequest::GetCodeActions(contract_location, range) => {
+            let code_actions = editor_state.get_code_actions(&contract_location, &range);
+            LspResponse {
+                code_actions,
+                ..LspResponse::default()
+            }
+        }
+        LspRequest::GetFormatting(source, options) => {
+            let text_edits = get_formatting_edits(&source, &options);
+            LspResponse {
+                text_edits,
+                ..LspResponse::default()
+            }
+        }
+        LspRequest::GetDocumentSymbols(contract_location) => {
+            let document_symbols = editor_state.get_document_symbols(&contract_location);
+            LspResponse {
+                document_symbols,
+                ..LspResponse::default()
+            }
+        }
+        LspRequest::GetWorkspaceSymbols(query) => {
+            let workspace_symbols = editor_state.get_workspace_symbols(&query);
+            LspResponse {
+                workspace_symbols,
+                ..LspResponse::default()
+            }
+        }
+        // Refused renames are reported with an error notification, for the client to display
+        LspRequest::PrepareRename(contract_location, position) => {
+            match editor_state.prepare_rename(&contract_location, &position) {
+                Ok(range) => LspResponse {
+                    rename_range: Some(range),
+                    ..LspResponse::default()
+                },
+                Err(message) => LspResponse {
+                    notification: Some((MessageType::ERROR, message)),
+                    ..LspResponse::default()
+                },
+            }
+        }
+        LspRequest::Rename(contract_location, position, new_name) => {
+            match editor_state.rename(&contract_location, &position, &new_name) {
+                Ok(workspace_edit) => LspResponse {
+                    workspace_edit: Some(workspace_edit),
+                    ..LspResponse::default()
+                },
+                Err(message) => LspResponse {
+                    notification: Some((MessageType::ERROR, message)),
+                    ..LspResponse::default()
+                },
             }
         }
     }
@@ -0,0 +1,206 @@
+use crate::utils::{clarity_diagnostic_to_lsp_type, span_to_range};
+use clarity_repl::clarity::vm::diagnostic::{
+    Diagnostic as ClarityDiagnostic, Level as ClarityLevel,
+};
+use clarity_repl::clarity::vm::functions::define::DefineFunctions;
+use clarity_repl::clarity::vm::representations::Span;
+use clarity_repl::clarity::vm::SymbolicExpression;
+use lsp_types::{CodeAction, CodeActionKind, Position, Range, TextEdit, Url, WorkspaceEdit};
+use std::collections::HashMap;
+
+const UNCHECKED_DATA_MESSAGE: &str = "use of potentially unchecked data";
+
+/// Warnings reported by the check_checker pass, along with the spans of the sources of
+/// untrusted input, reported as the notes following each warning.
+pub fn get_unchecked_data_warnings(
+    diagnostics: &[ClarityDiagnostic],
+) -> Vec<(ClarityDiagnostic, Vec<Span>)> {
+    let mut warnings: Vec<(ClarityDiagnostic, Vec<Span>)> = vec![];
+    let mut is_unchecked_data = false;
+    for diagnostic in diagnostics.iter() {
+        match diagnostic.level {
+            ClarityLevel::Warning if diagnostic.message == UNCHECKED_DATA_MESSAGE => {
+                warnings.push((diagnostic.clone(), vec![]));
+                is_unchecked_data = true;
+            }
+            ClarityLevel::Note if is_unchecked_data => {
+                if let Some((_, sources)) = warnings.last_mut() {
+                    sources.extend(diagnostic.spans.iter().cloned());
+                }
+            }
+            _ => is_unchecked_data = false,
+        }
+    }
+    warnings
+}
+
+/// Quick fixes for an "unchecked data" warning: silence it with an annotation, or check
+/// the tainted parameters with an `asserts!`.
+pub fn build_unchecked_data_code_actions(
+    uri: &Url,
+    source: &str,
+    expressions: &[SymbolicExpression],
+    warning: &ClarityDiagnostic,
+    sources: &[Span],
+) -> Vec<CodeAction> {
+    let span = match warning.spans.first() {
+        Some(span) => span,
+        None => return vec![],
+    };
+    let diagnostics = Some(vec![clarity_diagnostic_to_lsp_type(warning)]);
+    let mut code_actions = vec![];
+
+    // The annotation applies to the expression starting on the next line
+    let line = span.start_line.saturating_sub(1);
+    let indentation = source
+        .lines()
+        .nth(line as usize)
+        .map(|line| {
+            line.chars()
+                .take_while(|c| c.is_whitespace())
+                .collect::<String>()
+        })
+        .unwrap_or_default();
+    code_actions.push(build_quick_fix(
+        "Allow unchecked data with an annotation".to_string(),
+        uri,
+        vec![TextEdit {
+            range: Range {
+                start: Position { line, character: 0 },
+                end: Position { line, character: 0 },
+            },
+            new_text: format!("{};; #[allow(unchecked_data)]\n", indentation),
+        }],
+        diagnostics.clone(),
+    ));
+
+    for source_span in sources.iter() {
+        if let Some((name, text_edits)) = build_asserts_edits(expressions, span, source_span) {
+            code_actions.push(build_quick_fix(
+                format!("Check `{}` with asserts!", name),
+                uri,
+                text_edits,
+                diagnostics.clone(),
+            ));
+        }
+    }
+    code_actions
+}
+
+fn build_quick_fix(
+    title: String,
+    uri: &Url,
+    text_edits: Vec<TextEdit>,
+    diagnostics: Option<Vec<lsp_types::Diagnostic>>,
+) -> CodeAction {
+    let mut changes = HashMap::new();
+    changes.insert(uri.clone(), text_edits);
+    CodeAction {
+        title,
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics,
+        edit: Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..WorkspaceEdit::default()
+        }),
+        ..CodeAction::default()
+    }
+}
+
+fn contains_span(outer: &Span, inner: &Span) -> bool {
+    (outer.start_line, outer.start_column) <= (inner.start_line, inner.start_column)
+        && (outer.end_line, outer.end_column) >= (inner.end_line, inner.end_column)
+}
+
+/// Guard on a parameter, suited to its type. Parameters of other types are not supported.
+fn build_condition(name: &str, param_type: &SymbolicExpression) -> Option<String> {
+    let type_name = match param_type.match_list() {
+        Some(list) => list.first()?.match_atom()?,
+        None => param_type.match_atom()?,
+    };
+    let condition = match type_name.as_str() {
+        "uint" => format!("(> {} u0)", name),
+        "int" => format!("(> {} 0)", name),
+        "principal" => format!("(is-eq {} tx-sender)", name),
+        "bool" => name.to_string(),
+        "buff" | "string-ascii" | "string-utf8" | "list" => format!("(> (len {}) u0)", name),
+        "optional" => format!("(is-some {})", name),
+        _ => return None,
+    };
+    Some(condition)
+}
+
+/// Insert `(asserts! <condition> (err u0))` at the beginning of the body of the function
+/// declaring the tainted parameter. A body that is neither a `begin` nor a `let` is
+/// wrapped in a `begin`.
+fn build_asserts_edits(
+    expressions: &[SymbolicExpression],
+    warning_span: &Span,
+    source_span: &Span,
+) -> Option<(String, Vec<TextEdit>)> {
+    let function = expressions
+        .iter()
+        .find(|expr| contains_span(&expr.span, warning_span))?
+        .match_list()?;
+    match DefineFunctions::lookup_by_name(function.get(0)?.match_atom()?)? {
+        DefineFunctions::PublicFunction
+        | DefineFunctions::ReadOnlyFunction
+        | DefineFunctions::PrivateFunction => {}
+        _ => return None,
+    };
+    let signature = function.get(1)?.match_list()?;
+    let body = function.get(2)?;
+
+    let (name, param_type) = signature.iter().skip(1).find_map(|param| {
+        let param = param.match_list()?;
+        let name = param.get(0)?;
+        if (name.span.start_line, name.span.start_column)
+            != (source_span.start_line, source_span.start_column)
+        {
+            return None;
+        }
+        Some((name.match_atom()?, param.get(1)?))
+    })?;
+    let guard = format!("(asserts! {} (err u0))", build_condition(name, param_type)?);
+
+    let first_statement = match body.match_list() {
+        Some(list) => match list.get(0).and_then(|e| e.match_atom()).map(|a| a.as_str()) {
+            Some("begin") => list.get(1),
+            Some("let") => list.get(2),
+            _ => None,
+        },
+        None => None,
+    };
+    let text_edits = match first_statement {
+        Some(statement) => {
+            let range = span_to_range(&statement.span);
+            vec![TextEdit {
+                range: Range {
+                    start: range.start,
+                    end: range.start,
+                },
+                new_text: format!("{}\n{}", guard, " ".repeat(range.start.character as usize)),
+            }]
+        }
+        None => {
+            let range = span_to_range(&body.span);
+            vec![
+                TextEdit {
+                    range: Range {
+                        start: range.start,
+                        end: range.start,
+                    },
+                    new_text: format!("(begin {} ", guard),
+                },
+                TextEdit {
+                    range: Range {
+                        start: range.end,
+                        end: range.end,
+                    },
+                    new_text: ")".to_string(),
+                },
+            ]
+        }
+    };
+    Some((name.to_string(), text_edits))
+}
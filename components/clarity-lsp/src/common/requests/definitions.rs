@@ -0,0 +1,296 @@
+use crate::utils::{is_position_within_range, span_to_range};
+use clarity_repl::clarity::vm::functions::define::DefineFunctions;
+use clarity_repl::clarity::vm::representations::SymbolicExpressionType::*;
+use clarity_repl::clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, Value};
+use clarity_repl::clarity::vm::{ClarityName, SymbolicExpression};
+use lsp_types::{Position, Range};
+use std::collections::HashMap;
+
+/// What a token of a contract is pointing to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Definition {
+    /// Let binding, match binding or function argument, declared in the same contract.
+    Local(Range),
+    /// Anything defined at the top level of a contract with a `define-*` or `use-trait`.
+    TopLevel(QualifiedContractIdentifier, ClarityName),
+    /// Contract principal, such as the target of a `contract-call?`.
+    Contract(QualifiedContractIdentifier),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Definitions {
+    pub top_level: HashMap<ClarityName, Range>,
+    pub tokens: Vec<(Range, Definition)>,
+}
+
+impl Definitions {
+    pub fn new(
+        contract_id: &QualifiedContractIdentifier,
+        expressions: &[SymbolicExpression],
+    ) -> Definitions {
+        let mut top_level = HashMap::new();
+        for expr in expressions.iter() {
+            if let Some((name, range)) = get_top_level_definition(expr) {
+                top_level.insert(name.clone(), range);
+            }
+        }
+
+        let mut indexer = DefinitionsIndexer {
+            contract_id,
+            top_level: &top_level,
+            scopes: vec![],
+            tokens: vec![],
+        };
+        for expr in expressions.iter() {
+            indexer.traverse_expr(expr);
+        }
+        let tokens = indexer.tokens;
+
+        Definitions { top_level, tokens }
+    }
+
+    pub fn get_definition_at_position(&self, position: &Position) -> Option<&Definition> {
+        self.get_token_at_position(position)
+            .map(|(_, definition)| definition)
+    }
+
+    pub fn get_token_at_position(&self, position: &Position) -> Option<&(Range, Definition)> {
+        self.tokens
+            .iter()
+            .find(|(range, _)| is_position_within_range(position, range))
+    }
+
+    pub fn get_ranges_of_definition(&self, definition: &Definition) -> Vec<Range> {
+        self.tokens
+            .iter()
+            .filter(|(_, d)| d.eq(definition))
+            .map(|(range, _)| *range)
+            .collect()
+    }
+}
+
+/// Name of the definition made by a top level expression, along with the range of the name.
+pub fn get_top_level_definition(expr: &SymbolicExpression) -> Option<(&ClarityName, Range)> {
+    let (define, args) = expr.match_list()?.split_first()?;
+    let name = match DefineFunctions::lookup_by_name(define.match_atom()?)? {
+        DefineFunctions::PublicFunction
+        | DefineFunctions::ReadOnlyFunction
+        | DefineFunctions::PrivateFunction => args.get(0)?.match_list()?.get(0)?,
+        DefineFunctions::ImplTrait => return None,
+        _ => args.get(0)?,
+    };
+    Some((name.match_atom()?, span_to_range(&name.span)))
+}
+
+struct DefinitionsIndexer<'a> {
+    contract_id: &'a QualifiedContractIdentifier,
+    top_level: &'a HashMap<ClarityName, Range>,
+    scopes: Vec<HashMap<ClarityName, Range>>,
+    tokens: Vec<(Range, Definition)>,
+}
+
+impl<'a> DefinitionsIndexer<'a> {
+    fn resolve(&self, name: &ClarityName) -> Option<Definition> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(range) = scope.get(name) {
+                return Some(Definition::Local(*range));
+            }
+        }
+        if self.top_level.contains_key(name) {
+            return Some(Definition::TopLevel(self.contract_id.clone(), name.clone()));
+        }
+        None
+    }
+
+    fn declare_local(&mut self, expr: &SymbolicExpression) {
+        if let Some(name) = expr.match_atom() {
+            let range = span_to_range(&expr.span);
+            if let Some(scope) = self.scopes.last_mut() {
+                scope.insert(name.clone(), range);
+            }
+            self.tokens.push((range, Definition::Local(range)));
+        }
+    }
+
+    fn traverse_in_scope(
+        &mut self,
+        binding: Option<&SymbolicExpression>,
+        body: &[SymbolicExpression],
+    ) {
+        self.scopes.push(HashMap::new());
+        if let Some(binding) = binding {
+            self.declare_local(binding);
+        }
+        for expr in body.iter() {
+            self.traverse_expr(expr);
+        }
+        self.scopes.pop();
+    }
+
+    fn traverse_expr(&mut self, expr: &SymbolicExpression) {
+        let definition = match &expr.expr {
+            Atom(name) => self.resolve(name),
+            TraitReference(name, _) => self.resolve(name),
+            Field(trait_identifier) => Some(Definition::TopLevel(
+                trait_identifier.contract_identifier.clone(),
+                trait_identifier.name.clone(),
+            )),
+            LiteralValue(Value::Principal(PrincipalData::Contract(contract_id)))
+            | AtomValue(Value::Principal(PrincipalData::Contract(contract_id))) => {
+                Some(Definition::Contract(contract_id.clone()))
+            }
+            List(list) => {
+                self.traverse_list(list);
+                None
+            }
+            _ => None,
+        };
+        if let Some(definition) = definition {
+            self.tokens.push((span_to_range(&expr.span), definition));
+        }
+    }
+
+    fn traverse_list(&mut self, list: &[SymbolicExpression]) {
+        let (function_name, args) = match list.split_first() {
+            Some(res) => res,
+            None => return,
+        };
+
+        let function_name = match function_name.match_atom() {
+            Some(function_name) => function_name,
+            None => {
+                for expr in list.iter() {
+                    self.traverse_expr(expr);
+                }
+                return;
+            }
+        };
+
+        if let Some(define_function) = DefineFunctions::lookup_by_name(function_name) {
+            match define_function {
+                DefineFunctions::PublicFunction
+                | DefineFunctions::ReadOnlyFunction
+                | DefineFunctions::PrivateFunction => {
+                    let signature = match args.get(0).and_then(|e| e.match_list()) {
+                        Some(signature) => signature,
+                        None => return,
+                    };
+                    if let Some(name) = signature.get(0) {
+                        self.traverse_expr(name);
+                    }
+                    // Arguments types are not traversed
+                    self.scopes.push(HashMap::new());
+                    for param in signature.iter().skip(1) {
+                        if let Some(param_name) = param.match_list().and_then(|p| p.get(0)) {
+                            self.declare_local(param_name);
+                        }
+                    }
+                    for expr in args.iter().skip(1) {
+                        self.traverse_expr(expr);
+                    }
+                    self.scopes.pop();
+                }
+                DefineFunctions::Constant | DefineFunctions::FungibleToken => {
+                    for expr in args.iter() {
+                        self.traverse_expr(expr);
+                    }
+                }
+                DefineFunctions::PersistedVariable => {
+                    // (define-data-var name type value)
+                    for expr in args.iter().take(1).chain(args.iter().skip(2)) {
+                        self.traverse_expr(expr);
+                    }
+                }
+                DefineFunctions::NonFungibleToken
+                | DefineFunctions::Map
+                | DefineFunctions::Trait => {
+                    // Only the name is relevant, the rest of the expression is made of types
+                    if let Some(name) = args.get(0) {
+                        self.traverse_expr(name);
+                    }
+                }
+                DefineFunctions::UseTrait | DefineFunctions::ImplTrait => {
+                    for expr in args.iter() {
+                        self.traverse_expr(expr);
+                    }
+                }
+            }
+            return;
+        }
+
+        match function_name.as_str() {
+            "let" => {
+                let bindings = args.get(0).and_then(|e| e.match_list()).unwrap_or_default();
+                self.scopes.push(HashMap::new());
+                // Bindings are sequential: a binding can refer to the previous ones
+                for binding in bindings.iter() {
+                    if let Some(pair) = binding.match_list() {
+                        if let Some(value) = pair.get(1) {
+                            self.traverse_expr(value);
+                        }
+                        if let Some(name) = pair.get(0) {
+                            self.declare_local(name);
+                        }
+                    }
+                }
+                for expr in args.iter().skip(1) {
+                    self.traverse_expr(expr);
+                }
+                self.scopes.pop();
+            }
+            "match" if args.len() == 4 => {
+                // (match option some-binding some-branch none-branch)
+                self.traverse_expr(&args[0]);
+                self.traverse_in_scope(Some(&args[1]), &args[2..3]);
+                self.traverse_expr(&args[3]);
+            }
+            "match" if args.len() == 5 => {
+                // (match response ok-binding ok-branch err-binding err-branch)
+                self.traverse_expr(&args[0]);
+                self.traverse_in_scope(Some(&args[1]), &args[2..3]);
+                self.traverse_in_scope(Some(&args[3]), &args[4..5]);
+            }
+            "tuple" => {
+                // Tuple keys are not references
+                for pair in args.iter().filter_map(|e| e.match_list()) {
+                    if let Some(value) = pair.get(1) {
+                        self.traverse_expr(value);
+                    }
+                }
+            }
+            "get" => {
+                if let Some(tuple) = args.get(1) {
+                    self.traverse_expr(tuple);
+                }
+            }
+            "contract-call?" => {
+                if let Some(contract) = args.get(0) {
+                    self.traverse_expr(contract);
+                    match (&contract.expr, args.get(1)) {
+                        (
+                            LiteralValue(Value::Principal(PrincipalData::Contract(contract_id)))
+                            | AtomValue(Value::Principal(PrincipalData::Contract(contract_id))),
+                            Some(method),
+                        ) => {
+                            if let Some(method_name) = method.match_atom() {
+                                self.tokens.push((
+                                    span_to_range(&method.span),
+                                    Definition::TopLevel(contract_id.clone(), method_name.clone()),
+                                ));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                for expr in args.iter().skip(2) {
+                    self.traverse_expr(expr);
+                }
+            }
+            _ => {
+                for expr in list.iter() {
+                    self.traverse_expr(expr);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use clarity_repl::repl::formatter::{format_source, FormatterSettings};
+use lsp_types::{FormattingOptions, Position, Range, TextEdit};
+
+/// Format a whole document. The edit replaces the entire content, it is empty when the
+/// document is already formatted, and `None` is returned when the document can't be parsed.
+pub fn get_formatting_edits(source: &str, options: &FormattingOptions) -> Option<Vec<TextEdit>> {
+    let settings = FormatterSettings {
+        indentation: options.tab_size as usize,
+        ..FormatterSettings::default()
+    };
+    let formatted = format_source(source, &settings).ok()?;
+    if formatted == source {
+        return Some(vec![]);
+    }
+
+    let mut lines = source.split('\n');
+    let last_line = lines.next_back().unwrap_or_default();
+    let end = Position {
+        line: lines.count() as u32,
+        character: last_line.encode_utf16().count() as u32,
+    };
+    Some(vec![TextEdit {
+        range: Range {
+            start: Position::default(),
+            end,
+        },
+        new_text: formatted,
+    }])
+}
@@ -0,0 +1,30 @@
+use crate::utils::{is_position_within_range, span_to_range};
+use clarity_repl::clarity::vm::representations::SymbolicExpressionType::List;
+use clarity_repl::clarity::vm::SymbolicExpression;
+use lsp_types::Position;
+
+/// Find the innermost non-list expression (atom, literal, field...) covering `position`.
+pub fn get_atom_at_position<'a>(
+    position: &Position,
+    expressions: &'a [SymbolicExpression],
+) -> Option<&'a SymbolicExpression> {
+    for expr in expressions.iter() {
+        match &expr.expr {
+            // Some lists, such as the key-value pairs of a sugared tuple, are not carrying a
+            // span, so the children are always inspected.
+            List(list) => {
+                if let Some(atom) = get_atom_at_position(position, list) {
+                    return Some(atom);
+                }
+            }
+            _ => {
+                if expr.span.start_line > 0
+                    && is_position_within_range(position, &span_to_range(&expr.span))
+                {
+                    return Some(expr);
+                }
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,122 @@
+use clarity_repl::clarity::vm::analysis::ContractAnalysis;
+use clarity_repl::clarity::vm::docs::{
+    make_api_reference, make_define_reference, make_keyword_reference,
+};
+use clarity_repl::clarity::vm::functions::define::DefineFunctions;
+use clarity_repl::clarity::vm::functions::NativeFunctions;
+use clarity_repl::clarity::vm::types::FunctionType;
+use clarity_repl::clarity::vm::variables::NativeVariables;
+use clarity_repl::clarity::vm::{ClarityName, ClarityVersion};
+
+fn format_code(code: &str) -> String {
+    format!("```clarity\n{}\n```", code)
+}
+
+fn format_api_documentation(signature: &str, description: &str, example: &str) -> String {
+    format!(
+        "{}\n\n{}\n\n**Example**\n\n{}",
+        format_code(signature),
+        description,
+        format_code(example.trim_end())
+    )
+}
+
+pub fn get_native_documentation(name: &str, clarity_version: &ClarityVersion) -> Option<String> {
+    if let Some(function) = NativeFunctions::lookup_by_name_at_version(name, clarity_version) {
+        let api = make_api_reference(&function);
+        return Some(format_api_documentation(
+            &api.signature,
+            &api.description,
+            &api.example,
+        ));
+    }
+
+    if let Some(define_function) = DefineFunctions::lookup_by_name(name) {
+        let api = make_define_reference(&define_function);
+        return Some(format_api_documentation(
+            &api.signature,
+            &api.description,
+            &api.example,
+        ));
+    }
+
+    if let Some(variable) = NativeVariables::lookup_by_name_at_version(name, clarity_version) {
+        if let Some(api) = make_keyword_reference(&variable) {
+            return Some(format_api_documentation(
+                &format!("{}: {}", api.name, api.output_type),
+                api.description,
+                api.example,
+            ));
+        }
+    }
+
+    None
+}
+
+fn format_function_signature(define: &str, name: &ClarityName, function: &FunctionType) -> String {
+    match function {
+        FunctionType::Fixed(function) => {
+            let mut signature = vec![name.to_string()];
+            for arg in function.args.iter() {
+                signature.push(format!("({} {})", arg.name, arg.signature));
+            }
+            format!(
+                "{}\n\n**Returns** `{}`",
+                format_code(&format!("({} ({}))", define, signature.join(" "))),
+                function.returns
+            )
+        }
+        _ => format_code(&format!("({} ({}))", define, name)),
+    }
+}
+
+pub fn get_user_defined_documentation(
+    name: &ClarityName,
+    analysis: &ContractAnalysis,
+) -> Option<String> {
+    let documentation = if let Some(function) = analysis.public_function_types.get(name) {
+        format_function_signature("define-public", name, function)
+    } else if let Some(function) = analysis.read_only_function_types.get(name) {
+        format_function_signature("define-read-only", name, function)
+    } else if let Some(function) = analysis.private_function_types.get(name) {
+        format_function_signature("define-private", name, function)
+    } else if let Some(data_type) = analysis.persisted_variable_types.get(name) {
+        format_code(&format!("(define-data-var {} {})", name, data_type))
+    } else if let Some(constant_type) = analysis.variable_types.get(name) {
+        format_code(&format!("(define-constant {} {})", name, constant_type))
+    } else if let Some((key_type, value_type)) = analysis.map_types.get(name) {
+        format_code(&format!(
+            "(define-map {} {} {})",
+            name, key_type, value_type
+        ))
+    } else if analysis.fungible_tokens.contains(name) {
+        format_code(&format!("(define-fungible-token {})", name))
+    } else if let Some(asset_type) = analysis.non_fungible_tokens.get(name) {
+        format_code(&format!(
+            "(define-non-fungible-token {} {})",
+            name, asset_type
+        ))
+    } else if let Some(functions) = analysis.defined_traits.get(name) {
+        let functions = functions
+            .iter()
+            .map(|(function_name, signature)| {
+                let args = signature
+                    .args
+                    .iter()
+                    .map(|arg| arg.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!("    ({} ({}) {})", function_name, args, signature.returns)
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        format_code(&format!("(define-trait {} (\n{}\n))", name, functions))
+    } else {
+        return None;
+    };
+
+    Some(format!(
+        "{}\n\n*Defined in `{}`*",
+        documentation, analysis.contract_identifier
+    ))
+}
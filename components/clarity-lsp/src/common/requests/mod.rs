@@ -0,0 +1,8 @@
+pub mod code_actions;
+pub mod definitions;
+pub mod formatting;
+pub mod helpers;
+pub mod hover;
+pub mod rename;
+pub mod signature_help;
+pub mod symbols;
@@ -0,0 +1,42 @@
+use crate::types::Symbol;
+use clarity_repl::clarity::vm::functions::define::DefineFunctions;
+use clarity_repl::clarity::vm::functions::NativeFunctions;
+use clarity_repl::clarity::vm::representations::SymbolicExpressionType::*;
+use clarity_repl::clarity::vm::variables::NativeVariables;
+use clarity_repl::clarity::vm::{ClarityName, ClarityVersion, SymbolicExpression};
+
+/// Only user-defined functions, constants, maps and data variables can be renamed.
+pub fn is_renameable(symbol: &Symbol) -> bool {
+    matches!(
+        symbol,
+        Symbol::PublicFunction
+            | Symbol::ReadonlyFunction
+            | Symbol::PrivateFunction
+            | Symbol::Constant
+            | Symbol::DataMap
+            | Symbol::DataVar
+    )
+}
+
+pub fn validate_new_name(
+    new_name: &str,
+    clarity_version: &ClarityVersion,
+) -> Result<ClarityName, String> {
+    if NativeFunctions::lookup_by_name_at_version(new_name, clarity_version).is_some()
+        || DefineFunctions::lookup_by_name(new_name).is_some()
+        || NativeVariables::lookup_by_name_at_version(new_name, clarity_version).is_some()
+    {
+        return Err(format!("`{}` is a native keyword", new_name));
+    }
+    ClarityName::try_from(new_name.to_string())
+        .map_err(|_| format!("`{}` is not a valid Clarity name", new_name))
+}
+
+/// Whether `name` is used anywhere in `expressions`, either as a definition, a binding or a reference.
+pub fn contains_atom(expressions: &[SymbolicExpression], name: &str) -> bool {
+    expressions.iter().any(|expr| match &expr.expr {
+        Atom(atom) | TraitReference(atom, _) => atom.as_str() == name,
+        List(list) => contains_atom(list, name),
+        _ => false,
+    })
+}
@@ -0,0 +1,212 @@
+use crate::utils::build_signature_args;
+use clarity_repl::clarity::vm::docs::make_api_reference;
+use clarity_repl::clarity::vm::functions::NativeFunctions;
+use clarity_repl::clarity::vm::types::FunctionType;
+use clarity_repl::clarity::vm::{ClarityName, ClarityVersion};
+use lsp_types::{
+    Documentation, MarkupContent, MarkupKind, ParameterInformation, ParameterLabel, Position,
+    SignatureHelp, SignatureInformation,
+};
+
+/// Tokens of the innermost list still open at `position`, such as `["my-fn", "u1"]` for
+/// `(my-fn u1 |`. Nested expressions count as one token, and the token being typed is
+/// not included: the active argument is always the last slot of the list.
+/// The source is scanned rather than parsed, since the expression is usually incomplete.
+pub fn get_enclosing_call(source: &str, position: &Position) -> Option<Vec<String>> {
+    let mut frames: Vec<(char, Vec<String>)> = vec![];
+    let mut token = String::new();
+    let mut in_comment = false;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in get_text_before_position(source, position).chars() {
+        if in_comment {
+            in_comment = c != '\n';
+            continue;
+        }
+        if in_string {
+            token.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            ';' => {
+                flush_token(&mut frames, &mut token);
+                in_comment = true;
+            }
+            '"' => {
+                token.push(c);
+                in_string = true;
+            }
+            '(' | '{' => {
+                flush_token(&mut frames, &mut token);
+                frames.push((c, vec![]));
+            }
+            ')' | '}' => {
+                flush_token(&mut frames, &mut token);
+                frames.pop();
+                // The closed expression is an argument of the enclosing list
+                if let Some((_, tokens)) = frames.last_mut() {
+                    tokens.push("()".to_string());
+                }
+            }
+            ',' => flush_token(&mut frames, &mut token),
+            c if c.is_whitespace() => flush_token(&mut frames, &mut token),
+            c => token.push(c),
+        }
+    }
+
+    // A tuple being typed is itself an argument of the enclosing list
+    let (_, tokens) = frames.into_iter().rev().find(|(open, _)| *open == '(')?;
+    if tokens.is_empty() {
+        return None;
+    }
+    Some(tokens)
+}
+
+fn flush_token(frames: &mut Vec<(char, Vec<String>)>, token: &mut String) {
+    if token.is_empty() {
+        return;
+    }
+    match frames.last_mut() {
+        Some((_, tokens)) => tokens.push(std::mem::take(token)),
+        None => token.clear(),
+    }
+}
+
+fn get_text_before_position(source: &str, position: &Position) -> String {
+    let mut text = String::new();
+    for (i, line) in source.split('\n').enumerate() {
+        if i < position.line as usize {
+            text.push_str(line);
+            text.push('\n');
+            continue;
+        }
+        let mut utf16_len = 0;
+        for c in line.chars() {
+            utf16_len += c.len_utf16() as u32;
+            if utf16_len > position.character {
+                break;
+            }
+            text.push(c);
+        }
+        break;
+    }
+    text
+}
+
+/// Ranges of the parameters of a signature such as `(+ i1 i2...)`.
+fn get_parameters_offsets(signature: &str) -> Vec<[u32; 2]> {
+    let mut offsets = vec![];
+    let mut depth = 0;
+    let mut start = None;
+    let mut offset = 0;
+    for c in signature.chars() {
+        match c {
+            '(' => {
+                if depth == 1 && start.is_none() {
+                    start = Some(offset);
+                }
+                depth += 1;
+            }
+            ')' => {
+                depth -= 1;
+                if depth <= 1 {
+                    if let Some(s) = start {
+                        if depth == 1 {
+                            offsets.push([s, offset + 1]);
+                        } else {
+                            offsets.push([s, offset]);
+                        }
+                        start = None;
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if depth == 1 {
+                    if let Some(s) = start {
+                        offsets.push([s, offset]);
+                        start = None;
+                    }
+                }
+            }
+            _ => {
+                if depth == 1 && start.is_none() {
+                    start = Some(offset);
+                }
+            }
+        }
+        offset += c.len_utf16() as u32;
+    }
+    // The first element is the name of the function
+    offsets.into_iter().skip(1).collect()
+}
+
+fn build_signature_help(
+    signature: String,
+    documentation: String,
+    active_parameter: usize,
+) -> SignatureHelp {
+    let parameters = get_parameters_offsets(&signature);
+    // Variadic parameters are documented by the last one (`i2...`)
+    let active_parameter = active_parameter.min(parameters.len().saturating_sub(1)) as u32;
+    SignatureHelp {
+        signatures: vec![SignatureInformation {
+            label: signature,
+            documentation: Some(Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: documentation,
+            })),
+            parameters: Some(
+                parameters
+                    .into_iter()
+                    .map(|offsets| ParameterInformation {
+                        label: ParameterLabel::LabelOffsets(offsets),
+                        documentation: None,
+                    })
+                    .collect(),
+            ),
+            active_parameter: Some(active_parameter),
+        }],
+        active_signature: Some(0),
+        active_parameter: Some(active_parameter),
+    }
+}
+
+pub fn get_native_signature_help(
+    name: &str,
+    clarity_version: &ClarityVersion,
+    active_parameter: usize,
+) -> Option<SignatureHelp> {
+    let function = NativeFunctions::lookup_by_name_at_version(name, clarity_version)?;
+    let api = make_api_reference(&function);
+    Some(build_signature_help(
+        api.signature,
+        api.description,
+        active_parameter,
+    ))
+}
+
+pub fn get_user_defined_signature_help(
+    name: &ClarityName,
+    function: &FunctionType,
+    active_parameter: usize,
+) -> Option<SignatureHelp> {
+    let returns = match function {
+        FunctionType::Fixed(function) => &function.returns,
+        _ => return None,
+    };
+    let mut signature = vec![name.to_string()];
+    signature.append(&mut build_signature_args(function));
+    Some(build_signature_help(
+        format!("({})", signature.join(" ")),
+        format!("**Returns** `{}`", returns),
+        active_parameter,
+    ))
+}
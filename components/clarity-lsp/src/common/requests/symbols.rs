@@ -0,0 +1,68 @@
+use crate::requests::definitions::get_top_level_definition;
+use crate::types::Symbol;
+use crate::utils::span_to_range;
+use clarity_repl::clarity::vm::SymbolicExpression;
+use lsp_types::{DocumentSymbol, SymbolKind};
+
+fn get_symbol_kind(symbol: &Symbol) -> SymbolKind {
+    match symbol {
+        Symbol::PublicFunction | Symbol::ReadonlyFunction | Symbol::PrivateFunction => {
+            SymbolKind::FUNCTION
+        }
+        Symbol::ImportedTrait | Symbol::Trait => SymbolKind::INTERFACE,
+        Symbol::LocalVariable | Symbol::DataVar => SymbolKind::VARIABLE,
+        Symbol::Constant => SymbolKind::CONSTANT,
+        Symbol::DataMap => SymbolKind::STRUCT,
+        Symbol::FungibleToken | Symbol::NonFungibleToken => SymbolKind::OBJECT,
+    }
+}
+
+fn get_symbol_detail(symbol: &Symbol) -> &'static str {
+    match symbol {
+        Symbol::PublicFunction => "define-public",
+        Symbol::ReadonlyFunction => "define-read-only",
+        Symbol::PrivateFunction => "define-private",
+        Symbol::ImportedTrait => "use-trait",
+        Symbol::LocalVariable => "let",
+        Symbol::Constant => "define-constant",
+        Symbol::DataMap => "define-map",
+        Symbol::DataVar => "define-data-var",
+        Symbol::FungibleToken => "define-fungible-token",
+        Symbol::NonFungibleToken => "define-non-fungible-token",
+        Symbol::Trait => "define-trait",
+    }
+}
+
+/// Outline of a contract: the top level definitions, in the order of the source.
+/// `symbols` (built from the contract analysis) tells what each definition is.
+#[allow(deprecated)]
+pub fn build_document_symbols(
+    expressions: &Vec<SymbolicExpression>,
+    symbols: &Vec<(String, Symbol)>,
+) -> Vec<DocumentSymbol> {
+    let mut document_symbols = vec![];
+    for expr in expressions.iter() {
+        let (name, selection_range) = match get_top_level_definition(expr) {
+            Some(definition) => definition,
+            None => continue,
+        };
+        let symbol = match symbols
+            .iter()
+            .find(|(symbol_name, _)| symbol_name.as_str() == name.as_str())
+        {
+            Some((_, symbol)) => symbol,
+            None => continue,
+        };
+        document_symbols.push(DocumentSymbol {
+            name: name.to_string(),
+            detail: Some(get_symbol_detail(symbol).to_string()),
+            kind: get_symbol_kind(symbol),
+            tags: None,
+            deprecated: None,
+            range: span_to_range(&expr.span),
+            selection_range,
+            children: None,
+        });
+    }
+    document_symbols
+}
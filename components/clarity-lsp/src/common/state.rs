@@ -1,3 +1,14 @@
+use crate::requests::code_actions::{
+    build_unchecked_data_code_actions, get_unchecked_data_warnings,
+};
+use crate::requests::definitions::{Definition, Definitions};
+use crate::requests::helpers::get_atom_at_position;
+use crate::requests::hover::{get_native_documentation, get_user_defined_documentation};
+use crate::requests::rename::{contains_atom, is_renameable, validate_new_name};
+use crate::requests::signature_help::{
+    get_enclosing_call, get_native_signature_help, get_user_defined_signature_help,
+};
+use crate::requests::symbols::build_document_symbols;
 use crate::types::{CompletionItem, CompletionMaps};
 use crate::utils;
 use clarinet_deployments::{
@@ -6,18 +17,25 @@ use clarinet_deployments::{
 };
 use clarinet_files::ProjectManifest;
 use clarinet_files::{FileAccessor, FileLocation};
-use clarity_repl::analysis::ast_dependency_detector::DependencySet;
+use clarity_repl::analysis::ast_dependency_detector::{ASTDependencyDetector, DependencySet};
 use clarity_repl::clarity::vm::analysis::ContractAnalysis;
 use clarity_repl::clarity::vm::ast::ContractAST;
 use clarity_repl::clarity::vm::diagnostic::{
     Diagnostic as ClarityDiagnostic, Level as ClarityLevel,
 };
+use clarity_repl::clarity::vm::representations::Span;
 use clarity_repl::clarity::vm::types::QualifiedContractIdentifier;
-use clarity_repl::clarity::vm::EvaluationResult;
-use lsp_types::MessageType;
+use clarity_repl::clarity::vm::{
+    ClarityName, ClarityVersion, EvaluationResult, SymbolicExpression,
+};
+use clarity_repl::repl::ast::build_ast;
+use lsp_types::{
+    CodeAction, DocumentSymbol, Hover, HoverContents, Location, MarkupContent, MarkupKind,
+    MessageType, Position, Range, SignatureHelp, SymbolInformation, TextEdit, Url, WorkspaceEdit,
+};
 use orchestra_types::StacksNetwork;
 use std::borrow::BorrowMut;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ContractState {
@@ -25,23 +43,30 @@ pub struct ContractState {
     errors: Vec<ClarityDiagnostic>,
     warnings: Vec<ClarityDiagnostic>,
     notes: Vec<ClarityDiagnostic>,
+    unchecked_data: Vec<(ClarityDiagnostic, Vec<Span>)>,
     contract_id: QualifiedContractIdentifier,
     analysis: Option<ContractAnalysis>,
+    expressions: Vec<SymbolicExpression>,
+    definitions: Definitions,
+    dependencies: HashSet<QualifiedContractIdentifier>,
     location: FileLocation,
+    source: String,
 }
 
 impl ContractState {
     pub fn new(
         contract_id: QualifiedContractIdentifier,
-        _ast: ContractAST,
-        _deps: DependencySet,
+        ast: ContractAST,
+        deps: DependencySet,
         mut diags: Vec<ClarityDiagnostic>,
         analysis: Option<ContractAnalysis>,
         location: FileLocation,
+        source: String,
     ) -> ContractState {
         let mut errors = vec![];
         let mut warnings = vec![];
         let mut notes = vec![];
+        let unchecked_data = get_unchecked_data_warnings(&diags);
 
         for diag in diags.drain(..) {
             match diag.level {
@@ -62,14 +87,22 @@ impl ContractState {
             None => CompletionMaps::default(),
         };
 
+        let definitions = Definitions::new(&contract_id, &ast.expressions);
+        let dependencies = deps.iter().map(|dep| dep.contract_id.clone()).collect();
+
         ContractState {
             contract_id,
             intellisense,
             errors,
             warnings,
             notes,
+            unchecked_data,
             analysis,
+            expressions: ast.expressions,
+            definitions,
+            dependencies,
             location,
+            source,
         }
     }
 }
@@ -171,6 +204,112 @@ impl EditorState {
         keywords
     }
 
+    pub fn get_definition_location(
+        &self,
+        contract_location: &FileLocation,
+        position: &Position,
+    ) -> Option<Location> {
+        self.contracts_lookup
+            .get(&contract_location)
+            .and_then(|d| self.protocols.get(&d.manifest_location))
+            .and_then(|p| p.get_definition_location(contract_location, position))
+    }
+
+    pub fn get_references(
+        &self,
+        contract_location: &FileLocation,
+        position: &Position,
+        include_declaration: bool,
+    ) -> Vec<Location> {
+        self.contracts_lookup
+            .get(&contract_location)
+            .and_then(|d| self.protocols.get(&d.manifest_location))
+            .map(|p| p.get_references(contract_location, position, include_declaration))
+            .unwrap_or_default()
+    }
+
+    pub fn get_hover_content(
+        &self,
+        contract_location: &FileLocation,
+        position: &Position,
+    ) -> Option<Hover> {
+        self.contracts_lookup
+            .get(&contract_location)
+            .and_then(|d| self.protocols.get(&d.manifest_location))
+            .and_then(|p| p.get_hover_content(contract_location, position))
+    }
+
+    pub fn get_signature_help(
+        &self,
+        contract_location: &FileLocation,
+        position: &Position,
+        source: Option<&str>,
+    ) -> Option<SignatureHelp> {
+        self.contracts_lookup
+            .get(&contract_location)
+            .and_then(|d| self.protocols.get(&d.manifest_location))
+            .and_then(|p| p.get_signature_help(contract_location, position, source))
+    }
+
+    pub fn get_code_actions(
+        &self,
+        contract_location: &FileLocation,
+        range: &Range,
+    ) -> Vec<CodeAction> {
+        self.contracts_lookup
+            .get(&contract_location)
+            .and_then(|d| self.protocols.get(&d.manifest_location))
+            .map(|p| p.get_code_actions(contract_location, range))
+            .unwrap_or_default()
+    }
+
+    pub fn get_document_symbols(&self, contract_location: &FileLocation) -> Vec<DocumentSymbol> {
+        self.contracts_lookup
+            .get(&contract_location)
+            .and_then(|d| self.protocols.get(&d.manifest_location))
+            .map(|p| p.get_document_symbols(contract_location))
+            .unwrap_or_default()
+    }
+
+    pub fn prepare_rename(
+        &self,
+        contract_location: &FileLocation,
+        position: &Position,
+    ) -> Result<Range, String> {
+        match self
+            .contracts_lookup
+            .get(&contract_location)
+            .and_then(|d| self.protocols.get(&d.manifest_location))
+        {
+            Some(protocol_state) => protocol_state.prepare_rename(contract_location, position),
+            None => Err("contract not found".to_string()),
+        }
+    }
+
+    pub fn rename(
+        &self,
+        contract_location: &FileLocation,
+        position: &Position,
+        new_name: &str,
+    ) -> Result<WorkspaceEdit, String> {
+        match self
+            .contracts_lookup
+            .get(&contract_location)
+            .and_then(|d| self.protocols.get(&d.manifest_location))
+        {
+            Some(protocol_state) => protocol_state.rename(contract_location, position, new_name),
+            None => Err("contract not found".to_string()),
+        }
+    }
+
+    pub fn get_workspace_symbols(&self, query: &str) -> Vec<SymbolInformation> {
+        let mut symbols = vec![];
+        for (_, protocol_state) in self.protocols.iter() {
+            symbols.append(&mut protocol_state.get_workspace_symbols(query));
+        }
+        symbols
+    }
+
     pub fn get_aggregated_diagnostics(
         &self,
     ) -> (
@@ -246,18 +385,23 @@ impl EditorState {
 #[derive(Clone, Default, Debug)]
 pub struct ProtocolState {
     contracts: HashMap<FileLocation, ContractState>,
+    manifest: Option<ProjectManifest>,
+    requirements_analyses: HashMap<QualifiedContractIdentifier, ContractAnalysis>,
 }
 
 impl ProtocolState {
     pub fn new() -> ProtocolState {
         ProtocolState {
             contracts: HashMap::new(),
+            manifest: None,
+            requirements_analyses: HashMap::new(),
         }
     }
 
     pub fn consolidate(
         &mut self,
         locations: &mut HashMap<QualifiedContractIdentifier, FileLocation>,
+        sources: &mut HashMap<QualifiedContractIdentifier, String>,
         asts: &mut HashMap<QualifiedContractIdentifier, ContractAST>,
         deps: &mut HashMap<QualifiedContractIdentifier, DependencySet>,
         diags: &mut HashMap<QualifiedContractIdentifier, Vec<ClarityDiagnostic>>,
@@ -284,6 +428,10 @@ impl ProtocolState {
                 Some(analysis) => analysis,
                 None => None,
             };
+            let source = match sources.remove(&contract_id) {
+                Some(source) => source,
+                None => String::new(),
+            };
 
             let contract_state = ContractState::new(
                 contract_id,
@@ -292,12 +440,197 @@ impl ProtocolState {
                 diags,
                 analysis,
                 contract_location.clone(),
+                source,
             );
             self.contracts
                 .insert(contract_location.clone(), contract_state);
         }
     }
 
+    /// Re-analyse a contract being edited, along with the contracts depending on it
+    /// (directly or transitively), without touching the rest of the protocol.
+    /// `source` is the content of the editor buffer, which may not be saved yet.
+    /// Returns the locations of the contracts that were re-analysed.
+    pub fn run_incremental_analysis(
+        &mut self,
+        contract_location: &FileLocation,
+        source: String,
+    ) -> Result<Vec<FileLocation>, String> {
+        let manifest = match self.manifest {
+            Some(ref manifest) => manifest,
+            None => return Err("unable to retrieve project manifest".to_string()),
+        };
+        let edited_contract_id = match self.contracts.get(contract_location) {
+            Some(contract_state) => contract_state.contract_id.clone(),
+            None => {
+                return Err(format!(
+                    "unable to find contract {}",
+                    contract_location.to_string()
+                ))
+            }
+        };
+
+        // Collect the contracts that need to be re-analysed
+        let mut affected = HashSet::new();
+        affected.insert(edited_contract_id);
+        loop {
+            let dependents = self
+                .contracts
+                .values()
+                .filter(|contract_state| !affected.contains(&contract_state.contract_id))
+                .filter(|contract_state| {
+                    contract_state
+                        .dependencies
+                        .iter()
+                        .any(|dependency| affected.contains(dependency))
+                })
+                .map(|contract_state| contract_state.contract_id.clone())
+                .collect::<Vec<_>>();
+            if dependents.is_empty() {
+                break;
+            }
+            affected.extend(dependents);
+        }
+
+        // Only the affected contracts are parsed again: the expressions of the other
+        // ones are enough to resolve the dependencies of the affected ones.
+        let mut contracts = HashMap::new();
+        let mut asts = HashMap::new();
+        let mut preloaded = BTreeMap::new();
+        for (location, contract_state) in self.contracts.iter() {
+            let contract_id = &contract_state.contract_id;
+            if !affected.contains(contract_id) {
+                let mut ast = ContractAST::new(contract_id.clone(), vec![]);
+                ast.expressions = contract_state.expressions.clone();
+                preloaded.insert(contract_id.clone(), ast);
+                continue;
+            }
+            let contract_source = if location.eq(contract_location) {
+                source.clone()
+            } else {
+                contract_state.source.clone()
+            };
+            // As in the deployments, a contract pinned to an epoch defaults to
+            // the Clarity version of this epoch
+            let contract_config = manifest.contracts.get(contract_id.name.as_str());
+            let epoch = contract_config.and_then(|contract_config| contract_config.epoch);
+            let clarity_version = contract_config
+                .and_then(|contract_config| contract_config.clarity_version)
+                .or_else(|| epoch.map(ClarityVersion::default_for_epoch))
+                .unwrap_or(manifest.repl_settings.clarity_version);
+            let (ast, diags, success) =
+                build_ast(contract_id, &contract_source, &mut (), clarity_version);
+            contracts.insert(
+                contract_id.clone(),
+                (
+                    location.clone(),
+                    contract_source,
+                    epoch,
+                    clarity_version,
+                    diags,
+                    success,
+                ),
+            );
+            asts.insert(contract_id.clone(), ast);
+        }
+
+        let mut deps = match ASTDependencyDetector::detect_dependencies(&asts, &preloaded) {
+            Ok(deps) => deps,
+            Err((deps, _unresolved)) => deps,
+        };
+        // Circular dependencies will be reported by the analysis
+        let ordered_contracts = match ASTDependencyDetector::order_contracts(&deps) {
+            Ok(ordered_contracts) => ordered_contracts.into_iter().cloned().collect::<Vec<_>>(),
+            Err(_) => deps.keys().cloned().collect::<Vec<_>>(),
+        };
+
+        let mut session = initiate_session_from_deployment(manifest);
+        let default_epoch = session.interpreter.get_epoch();
+        for (contract_id, analysis) in self.requirements_analyses.iter() {
+            session
+                .interpreter
+                .insert_contract_analysis(contract_id, analysis)?;
+        }
+        for contract_state in self.contracts.values() {
+            if affected.contains(&contract_state.contract_id) {
+                continue;
+            }
+            if let Some(ref analysis) = contract_state.analysis {
+                session
+                    .interpreter
+                    .insert_contract_analysis(&contract_state.contract_id, analysis)?;
+            }
+        }
+
+        let mut analysed_locations = vec![];
+        for contract_id in ordered_contracts.into_iter() {
+            let (mut ast, (location, contract_source, epoch, clarity_version, mut diags, success)) =
+                match (asts.remove(&contract_id), contracts.remove(&contract_id)) {
+                    (Some(ast), Some(contract)) => (ast, contract),
+                    _ => continue,
+                };
+
+            let analysis = if success {
+                session
+                    .interpreter
+                    .set_epoch(epoch.unwrap_or(default_epoch));
+                session.interpreter.set_clarity_version(clarity_version);
+                let (annotations, mut annotations_diags) = session
+                    .interpreter
+                    .collect_annotations(&ast, &contract_source);
+                diags.append(&mut annotations_diags);
+                match session
+                    .interpreter
+                    .run_analysis(contract_id.clone(), &mut ast, &annotations)
+                {
+                    Ok((analysis, mut analysis_diags)) => {
+                        diags.append(&mut analysis_diags);
+                        Some(analysis)
+                    }
+                    Err((_, diagnostic, _)) => {
+                        if let Some(diagnostic) = diagnostic {
+                            diags.push(diagnostic);
+                        }
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+            // Fall back on the last successful analysis, so that intellisense keeps
+            // working, and the dependents are checked against the last known interface.
+            let analysis = analysis.or_else(|| {
+                self.contracts
+                    .get(&location)
+                    .and_then(|contract_state| contract_state.analysis.clone())
+            });
+            if let Some(ref analysis) = analysis {
+                session
+                    .interpreter
+                    .insert_contract_analysis(&contract_id, analysis)?;
+            }
+
+            let contract_deps = match deps.remove(&contract_id) {
+                Some(contract_deps) => contract_deps,
+                None => DependencySet::new(),
+            };
+            let contract_state = ContractState::new(
+                contract_id,
+                ast,
+                contract_deps,
+                diags,
+                analysis,
+                location.clone(),
+                contract_source,
+            );
+            self.contracts.insert(location.clone(), contract_state);
+            analysed_locations.push(location);
+        }
+
+        Ok(analysed_locations)
+    }
+
     pub fn get_completion_items_for_contract(
         &self,
         contract_uri: &FileLocation,
@@ -322,6 +655,389 @@ impl ProtocolState {
         keywords.append(&mut contract_calls);
         keywords
     }
+
+    fn get_contract_state_by_id(
+        &self,
+        contract_id: &QualifiedContractIdentifier,
+    ) -> Option<&ContractState> {
+        self.contracts
+            .values()
+            .find(|contract_state| contract_state.contract_id.eq(contract_id))
+    }
+
+    pub fn get_definition_location(
+        &self,
+        contract_location: &FileLocation,
+        position: &Position,
+    ) -> Option<Location> {
+        let contract_state = self.contracts.get(contract_location)?;
+        let (location, range) = match contract_state
+            .definitions
+            .get_definition_at_position(position)?
+        {
+            Definition::Local(range) => (contract_location, *range),
+            Definition::TopLevel(contract_id, name) => {
+                let target = self.get_contract_state_by_id(contract_id)?;
+                (&target.location, *target.definitions.top_level.get(name)?)
+            }
+            Definition::Contract(contract_id) => {
+                let target = self.get_contract_state_by_id(contract_id)?;
+                (&target.location, Range::default())
+            }
+        };
+        Some(Location {
+            uri: Url::parse(&location.to_url_string().ok()?).ok()?,
+            range,
+        })
+    }
+
+    pub fn get_hover_content(
+        &self,
+        contract_location: &FileLocation,
+        position: &Position,
+    ) -> Option<Hover> {
+        let contract_state = self.contracts.get(contract_location)?;
+        let atom = get_atom_at_position(position, &contract_state.expressions)?;
+        let name = atom.match_atom()?;
+
+        let documentation = match contract_state
+            .definitions
+            .get_definition_at_position(position)
+        {
+            Some(Definition::TopLevel(contract_id, name)) => {
+                let target = self.get_contract_state_by_id(contract_id)?;
+                get_user_defined_documentation(name, target.analysis.as_ref()?)?
+            }
+            Some(_) => return None,
+            None => {
+                let clarity_version = match contract_state.analysis {
+                    Some(ref analysis) => analysis.clarity_version,
+                    None => ClarityVersion::latest(),
+                };
+                get_native_documentation(name, &clarity_version)?
+            }
+        };
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: documentation,
+            }),
+            range: Some(utils::span_to_range(&atom.span)),
+        })
+    }
+
+    /// `source` is the content of the editor, when it has unsaved changes.
+    pub fn get_signature_help(
+        &self,
+        contract_location: &FileLocation,
+        position: &Position,
+        source: Option<&str>,
+    ) -> Option<SignatureHelp> {
+        let contract_state = self.contracts.get(contract_location)?;
+        let source = source.unwrap_or(&contract_state.source);
+        let tokens = get_enclosing_call(source, position)?;
+        let active_parameter = tokens.len() - 1;
+
+        if tokens[0] == "contract-call?" && tokens.len() >= 3 {
+            let contract_id = match tokens[1].strip_prefix('.') {
+                Some(contract_name) => QualifiedContractIdentifier::parse(&format!(
+                    "{}.{}",
+                    contract_state.contract_id.issuer, contract_name
+                )),
+                None => QualifiedContractIdentifier::parse(tokens[1].trim_start_matches('\'')),
+            }
+            .ok()?;
+            let analysis = match self.get_contract_state_by_id(&contract_id) {
+                Some(target) => target.analysis.as_ref()?,
+                None => self.requirements_analyses.get(&contract_id)?,
+            };
+            let name = ClarityName::try_from(tokens[2].clone()).ok()?;
+            let function = analysis
+                .public_function_types
+                .get(&name)
+                .or_else(|| analysis.read_only_function_types.get(&name))?;
+            return get_user_defined_signature_help(&name, function, active_parameter - 2);
+        }
+
+        let analysis = contract_state.analysis.as_ref();
+        if let Some(analysis) = analysis {
+            if let Ok(name) = ClarityName::try_from(tokens[0].clone()) {
+                if let Some(function) = analysis
+                    .public_function_types
+                    .get(&name)
+                    .or_else(|| analysis.read_only_function_types.get(&name))
+                    .or_else(|| analysis.private_function_types.get(&name))
+                {
+                    return get_user_defined_signature_help(&name, function, active_parameter);
+                }
+            }
+        }
+        let clarity_version = match analysis {
+            Some(analysis) => analysis.clarity_version,
+            None => ClarityVersion::latest(),
+        };
+        get_native_signature_help(&tokens[0], &clarity_version, active_parameter)
+    }
+
+    pub fn get_code_actions(
+        &self,
+        contract_location: &FileLocation,
+        range: &Range,
+    ) -> Vec<CodeAction> {
+        let mut code_actions = vec![];
+        let contract_state = match self.contracts.get(contract_location) {
+            Some(contract_state) => contract_state,
+            None => return code_actions,
+        };
+        let uri = match contract_location
+            .to_url_string()
+            .ok()
+            .and_then(|url| Url::parse(&url).ok())
+        {
+            Some(uri) => uri,
+            None => return code_actions,
+        };
+        for (warning, sources) in contract_state.unchecked_data.iter() {
+            let warning_range = match warning.spans.first() {
+                Some(span) => utils::span_to_range(span),
+                None => continue,
+            };
+            if warning_range.start > range.end || warning_range.end < range.start {
+                continue;
+            }
+            for code_action in build_unchecked_data_code_actions(
+                &uri,
+                &contract_state.source,
+                &contract_state.expressions,
+                warning,
+                sources,
+            ) {
+                // Warnings on the same line share the same fixes
+                if !code_actions.iter().any(|existing: &CodeAction| {
+                    existing.title == code_action.title && existing.edit == code_action.edit
+                }) {
+                    code_actions.push(code_action);
+                }
+            }
+        }
+        code_actions
+    }
+
+    pub fn get_document_symbols(&self, contract_location: &FileLocation) -> Vec<DocumentSymbol> {
+        match self.contracts.get(contract_location) {
+            Some(contract_state) => build_document_symbols(
+                &contract_state.expressions,
+                &contract_state.intellisense.symbols,
+            ),
+            None => vec![],
+        }
+    }
+
+    #[allow(deprecated)]
+    pub fn get_workspace_symbols(&self, query: &str) -> Vec<SymbolInformation> {
+        let query = query.to_lowercase();
+        let mut symbols = vec![];
+        for contract_state in self.contracts.values() {
+            let uri = match contract_state
+                .location
+                .to_url_string()
+                .ok()
+                .and_then(|url| Url::parse(&url).ok())
+            {
+                Some(uri) => uri,
+                None => continue,
+            };
+            let document_symbols = build_document_symbols(
+                &contract_state.expressions,
+                &contract_state.intellisense.symbols,
+            );
+            for symbol in document_symbols.into_iter() {
+                if !symbol.name.to_lowercase().contains(&query) {
+                    continue;
+                }
+                symbols.push(SymbolInformation {
+                    name: symbol.name,
+                    kind: symbol.kind,
+                    tags: None,
+                    deprecated: None,
+                    location: Location {
+                        uri: uri.clone(),
+                        range: symbol.selection_range,
+                    },
+                    container_name: Some(contract_state.contract_id.name.to_string()),
+                });
+            }
+        }
+        symbols
+    }
+
+    pub fn get_references(
+        &self,
+        contract_location: &FileLocation,
+        position: &Position,
+        include_declaration: bool,
+    ) -> Vec<Location> {
+        let mut references = vec![];
+        let contract_state = match self.contracts.get(contract_location) {
+            Some(contract_state) => contract_state,
+            None => return references,
+        };
+        let definition = match contract_state
+            .definitions
+            .get_definition_at_position(position)
+        {
+            Some(definition) => definition,
+            None => return references,
+        };
+
+        // Local bindings can only be referenced from within their own contract
+        let contracts = match definition {
+            Definition::Local(_) => vec![contract_state],
+            _ => self.contracts.values().collect(),
+        };
+
+        for contract in contracts.into_iter() {
+            let uri = match contract
+                .location
+                .to_url_string()
+                .ok()
+                .and_then(|url| Url::parse(&url).ok())
+            {
+                Some(uri) => uri,
+                None => continue,
+            };
+            let declaration = match definition {
+                Definition::Local(range) => Some(*range),
+                Definition::TopLevel(contract_id, name) if contract.contract_id.eq(contract_id) => {
+                    contract.definitions.top_level.get(name).cloned()
+                }
+                _ => None,
+            };
+            for range in contract.definitions.get_ranges_of_definition(definition) {
+                if !include_declaration && Some(range) == declaration {
+                    continue;
+                }
+                references.push(Location {
+                    uri: uri.clone(),
+                    range,
+                });
+            }
+        }
+        references
+    }
+
+    /// Resolve the token at `position` to a definition that can be renamed, along with the
+    /// contract defining it and the range of the token.
+    fn get_rename_target(
+        &self,
+        contract_location: &FileLocation,
+        position: &Position,
+    ) -> Result<(&ContractState, &ClarityName, Range), String> {
+        let contract_state = self
+            .contracts
+            .get(contract_location)
+            .ok_or_else(|| "contract not found".to_string())?;
+        let (range, definition) = contract_state
+            .definitions
+            .get_token_at_position(position)
+            .ok_or_else(|| "nothing to rename at this position".to_string())?;
+        let (contract_id, name) = match definition {
+            Definition::TopLevel(contract_id, name) => (contract_id, name),
+            _ => return Err("only top level definitions can be renamed".to_string()),
+        };
+        let target = self
+            .get_contract_state_by_id(contract_id)
+            .ok_or_else(|| format!("`{}` is defined outside of the project", name))?;
+        match target
+            .intellisense
+            .symbols
+            .iter()
+            .find(|(symbol_name, _)| symbol_name.as_str() == name.as_str())
+        {
+            Some((_, symbol)) if is_renameable(symbol) => Ok((target, name, *range)),
+            _ => Err(format!(
+                "`{}` can't be renamed, only functions, constants, maps and data variables can",
+                name
+            )),
+        }
+    }
+
+    pub fn prepare_rename(
+        &self,
+        contract_location: &FileLocation,
+        position: &Position,
+    ) -> Result<Range, String> {
+        self.get_rename_target(contract_location, position)
+            .map(|(_, _, range)| range)
+    }
+
+    pub fn rename(
+        &self,
+        contract_location: &FileLocation,
+        position: &Position,
+        new_name: &str,
+    ) -> Result<WorkspaceEdit, String> {
+        let (target, name, _) = self.get_rename_target(contract_location, position)?;
+        let clarity_version = match target.analysis {
+            Some(ref analysis) => analysis.clarity_version,
+            None => ClarityVersion::latest(),
+        };
+        let new_name = validate_new_name(new_name, &clarity_version)?;
+        if new_name.eq(name) {
+            return Ok(WorkspaceEdit::default());
+        }
+        if target.definitions.top_level.contains_key(&new_name) {
+            return Err(format!(
+                "`{}` is already defined in {}",
+                new_name, target.contract_id.name
+            ));
+        }
+        if contains_atom(&target.expressions, &new_name) {
+            return Err(format!(
+                "`{}` is already used in {}",
+                new_name, target.contract_id.name
+            ));
+        }
+
+        // The definition can only be referenced by its own contract, and by the contracts
+        // depending on it (through a `contract-call?`)
+        let definition = Definition::TopLevel(target.contract_id.clone(), name.clone());
+        let contracts = self.contracts.values().filter(|contract_state| {
+            contract_state.contract_id.eq(&target.contract_id)
+                || contract_state.dependencies.contains(&target.contract_id)
+        });
+
+        let mut changes = HashMap::new();
+        for contract_state in contracts {
+            let uri = match contract_state
+                .location
+                .to_url_string()
+                .ok()
+                .and_then(|url| Url::parse(&url).ok())
+            {
+                Some(uri) => uri,
+                None => continue,
+            };
+            let text_edits: Vec<TextEdit> = contract_state
+                .definitions
+                .get_ranges_of_definition(&definition)
+                .into_iter()
+                .map(|range| TextEdit {
+                    range,
+                    new_text: new_name.to_string(),
+                })
+                .collect();
+            if !text_edits.is_empty() {
+                changes.insert(uri, text_edits);
+            }
+        }
+
+        Ok(WorkspaceEdit {
+            changes: Some(changes),
+            ..WorkspaceEdit::default()
+        })
+    }
 }
 
 pub async fn build_state(
@@ -330,6 +1046,7 @@ pub async fn build_state(
     file_accessor: Option<&Box<dyn FileAccessor>>,
 ) -> Result<(), String> {
     let mut locations = HashMap::new();
+    let mut sources = HashMap::new();
     let mut analyses = HashMap::new();
 
     // In the LSP use case, trying to load an existing deployment
@@ -356,11 +1073,25 @@ pub async fn build_state(
         false,
     );
     for (contract_id, mut result) in results.into_iter() {
-        let (_, contract_location) = match deployment.contracts.get(&contract_id) {
+        let (source, contract_location) = match deployment.contracts.get(&contract_id) {
             Some(entry) => entry,
-            None => continue,
+            None => {
+                // Requirements are not edited, their analyses are kept aside
+                // for the incremental analysis of the contracts depending on them.
+                if let Ok(ref execution_result) = result {
+                    if let EvaluationResult::Contract(ref contract_result) = execution_result.result
+                    {
+                        protocol_state.requirements_analyses.insert(
+                            contract_id.clone(),
+                            contract_result.contract.analysis.clone(),
+                        );
+                    }
+                }
+                continue;
+            }
         };
         locations.insert(contract_id.clone(), contract_location.clone());
+        sources.insert(contract_id.clone(), source.clone());
 
         match result {
             Ok(mut execution_result) => {
@@ -386,11 +1117,13 @@ pub async fn build_state(
 
     protocol_state.consolidate(
         &mut locations,
+        &mut sources,
         &mut artifacts.asts,
         &mut artifacts.deps,
         &mut artifacts.diags,
         &mut analyses,
     );
+    protocol_state.manifest = Some(manifest);
 
     Ok(())
 }
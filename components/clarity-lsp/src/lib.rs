@@ -5,5 +5,6 @@ pub mod utils;
 pub mod vscode_bridge;
 
 pub use common::backend;
+pub use common::requests;
 pub use common::state;
 pub use lsp_types;
@@ -26,9 +26,8 @@ pub enum InsertTextFormat {
     PlainText,
 }
 
-#[allow(dead_code)]
-#[derive(Debug, Clone, Deserialize, Serialize)]
-enum Symbol {
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum Symbol {
     PublicFunction,
     ReadonlyFunction,
     PrivateFunction,
@@ -39,6 +38,7 @@ enum Symbol {
     DataVar,
     FungibleToken,
     NonFungibleToken,
+    Trait,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
@@ -46,4 +46,5 @@ pub struct CompletionMaps {
     pub inter_contract: Vec<CompletionItem>,
     pub intra_contract: Vec<CompletionItem>,
     pub data_fields: Vec<CompletionItem>,
+    pub symbols: Vec<(String, Symbol)>,
 }
@@ -9,6 +9,7 @@ use clarity_repl::clarity::vm::docs::{
 };
 use clarity_repl::clarity::vm::functions::define::DefineFunctions;
 use clarity_repl::clarity::vm::functions::NativeFunctions;
+use clarity_repl::clarity::vm::representations::Span;
 use clarity_repl::clarity::vm::types::{BlockInfoProperty, FunctionType};
 use clarity_repl::clarity::vm::variables::NativeVariables;
 use lsp_types::Diagnostic as LspDiagnostic;
@@ -65,6 +66,23 @@ pub fn clarity_diagnostic_to_lsp_type(diagnostic: &ClarityDiagnostic) -> LspDiag
     }
 }
 
+pub fn span_to_range(span: &Span) -> Range {
+    Range {
+        start: Position {
+            line: span.start_line.saturating_sub(1),
+            character: span.start_column.saturating_sub(1),
+        },
+        end: Position {
+            line: span.end_line.saturating_sub(1),
+            character: span.end_column,
+        },
+    }
+}
+
+pub fn is_position_within_range(position: &Position, range: &Range) -> bool {
+    position >= &range.start && position <= &range.end
+}
+
 fn build_intellisense_args(signature: &FunctionType) -> Vec<String> {
     let mut args = vec![];
     match signature {
@@ -78,6 +96,20 @@ fn build_intellisense_args(signature: &FunctionType) -> Vec<String> {
     args
 }
 
+/// Arguments of a user defined function, formatted as in its definition: `(name type)`.
+pub fn build_signature_args(signature: &FunctionType) -> Vec<String> {
+    let mut args = vec![];
+    match signature {
+        FunctionType::Fixed(function) => {
+            for arg in function.args.iter() {
+                args.push(format!("({} {})", arg.name, arg.signature));
+            }
+        }
+        _ => {}
+    }
+    args
+}
+
 pub fn build_intellisense(analysis: &ContractAnalysis) -> CompletionMaps {
     let mut intra_contract = vec![];
     let mut inter_contract = vec![];
@@ -163,7 +195,40 @@ pub fn build_intellisense(analysis: &ContractAnalysis) -> CompletionMaps {
         inter_contract,
         intra_contract,
         data_fields: vec![],
+        symbols: build_symbols(analysis),
+    }
+}
+
+fn build_symbols(analysis: &ContractAnalysis) -> Vec<(String, Symbol)> {
+    let mut symbols = vec![];
+    for name in analysis.public_function_types.keys() {
+        symbols.push((name.to_string(), Symbol::PublicFunction));
+    }
+    for name in analysis.read_only_function_types.keys() {
+        symbols.push((name.to_string(), Symbol::ReadonlyFunction));
+    }
+    for name in analysis.private_function_types.keys() {
+        symbols.push((name.to_string(), Symbol::PrivateFunction));
+    }
+    for name in analysis.map_types.keys() {
+        symbols.push((name.to_string(), Symbol::DataMap));
+    }
+    for name in analysis.persisted_variable_types.keys() {
+        symbols.push((name.to_string(), Symbol::DataVar));
+    }
+    for name in analysis.variable_types.keys() {
+        symbols.push((name.to_string(), Symbol::Constant));
+    }
+    for name in analysis.fungible_tokens.iter() {
+        symbols.push((name.to_string(), Symbol::FungibleToken));
+    }
+    for name in analysis.non_fungible_tokens.keys() {
+        symbols.push((name.to_string(), Symbol::NonFungibleToken));
+    }
+    for name in analysis.defined_traits.keys() {
+        symbols.push((name.to_string(), Symbol::Trait));
     }
+    symbols
 }
 
 pub fn build_default_native_keywords_list() -> Vec<CompletionItem> {
@@ -8,9 +8,14 @@ use clarinet_files::{FileAccessor, WASMFileSystemAccessor};
 use js_sys::{Function as JsFunction, Promise};
 use lsp_types::{
     notification::{DidOpenTextDocument, DidSaveTextDocument, Initialized, Notification},
-    request::{Completion, Request},
-    CompletionParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
-    PublishDiagnosticsParams, ShowMessageParams, Url,
+    request::{
+        CodeActionRequest, Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest,
+        PrepareRenameRequest, References, Rename, Request, SignatureHelpRequest, WorkspaceSymbol,
+    },
+    CodeActionParams, CompletionParams, DidOpenTextDocumentParams, DidSaveTextDocumentParams,
+    DocumentSymbolParams, GotoDefinitionParams, HoverParams, PrepareRenameResponse,
+    PublishDiagnosticsParams, ReferenceParams, RenameParams, ShowMessageParams,
+    SignatureHelpParams, TextDocumentPositionParams, Url, WorkspaceSymbolParams,
 };
 use serde_wasm_bindgen::{from_value as decode_from_js, to_value as encode_to_js};
 use std::{
@@ -209,6 +214,144 @@ impl LspVscodeBridge {
 
                 return encode_to_js(&lsp_response.completion_items).map_err(|_| JsValue::NULL);
             }
+            GotoDefinition::METHOD => {
+                let params: GotoDefinitionParams = decode_from_js(js_params)?;
+                let file_url = params.text_document_position_params.text_document.uri;
+                let location = get_contract_location(&file_url).ok_or(JsValue::NULL)?;
+                let command = LspRequest::GetDefinition(
+                    location,
+                    params.text_document_position_params.position,
+                );
+                let editor_state = self
+                    .editor_state_lock
+                    .try_read()
+                    .map_err(|_| JsValue::NULL)?;
+                let lsp_response = process_request(command, &editor_state);
+
+                return encode_to_js(&lsp_response.locations.first()).map_err(|_| JsValue::NULL);
+            }
+            DocumentSymbolRequest::METHOD => {
+                let params: DocumentSymbolParams = decode_from_js(js_params)?;
+                let file_url = params.text_document.uri;
+                let location = get_contract_location(&file_url).ok_or(JsValue::NULL)?;
+                let command = LspRequest::GetDocumentSymbols(location);
+                let editor_state = self
+                    .editor_state_lock
+                    .try_read()
+                    .map_err(|_| JsValue::NULL)?;
+                let lsp_response = process_request(command, &editor_state);
+
+                return encode_to_js(&lsp_response.document_symbols).map_err(|_| JsValue::NULL);
+            }
+            WorkspaceSymbol::METHOD => {
+                let params: WorkspaceSymbolParams = decode_from_js(js_params)?;
+                let command = LspRequest::GetWorkspaceSymbols(params.query);
+                let editor_state = self
+                    .editor_state_lock
+                    .try_read()
+                    .map_err(|_| JsValue::NULL)?;
+                let lsp_response = process_request(command, &editor_state);
+
+                return encode_to_js(&lsp_response.workspace_symbols).map_err(|_| JsValue::NULL);
+            }
+            HoverRequest::METHOD => {
+                let params: HoverParams = decode_from_js(js_params)?;
+                let file_url = params.text_document_position_params.text_document.uri;
+                let location = get_contract_location(&file_url).ok_or(JsValue::NULL)?;
+                let command =
+                    LspRequest::GetHover(location, params.text_document_position_params.position);
+                let editor_state = self
+                    .editor_state_lock
+                    .try_read()
+                    .map_err(|_| JsValue::NULL)?;
+                let lsp_response = process_request(command, &editor_state);
+
+                return encode_to_js(&lsp_response.hover).map_err(|_| JsValue::NULL);
+            }
+            SignatureHelpRequest::METHOD => {
+                let params: SignatureHelpParams = decode_from_js(js_params)?;
+                let file_url = params.text_document_position_params.text_document.uri;
+                let location = get_contract_location(&file_url).ok_or(JsValue::NULL)?;
+                let command = LspRequest::GetSignatureHelp(
+                    location,
+                    params.text_document_position_params.position,
+                    None,
+                );
+                let editor_state = self
+                    .editor_state_lock
+                    .try_read()
+                    .map_err(|_| JsValue::NULL)?;
+                let lsp_response = process_request(command, &editor_state);
+
+                return encode_to_js(&lsp_response.signature_help).map_err(|_| JsValue::NULL);
+            }
+            CodeActionRequest::METHOD => {
+                let params: CodeActionParams = decode_from_js(js_params)?;
+                let file_url = params.text_document.uri;
+                let location = get_contract_location(&file_url).ok_or(JsValue::NULL)?;
+                let command = LspRequest::GetCodeActions(location, params.range);
+                let editor_state = self
+                    .editor_state_lock
+                    .try_read()
+                    .map_err(|_| JsValue::NULL)?;
+                let lsp_response = process_request(command, &editor_state);
+
+                return encode_to_js(&lsp_response.code_actions).map_err(|_| JsValue::NULL);
+            }
+            References::METHOD => {
+                let params: ReferenceParams = decode_from_js(js_params)?;
+                let file_url = params.text_document_position.text_document.uri;
+                let location = get_contract_location(&file_url).ok_or(JsValue::NULL)?;
+                let command = LspRequest::GetReferences(
+                    location,
+                    params.text_document_position.position,
+                    params.context.include_declaration,
+                );
+                let editor_state = self
+                    .editor_state_lock
+                    .try_read()
+                    .map_err(|_| JsValue::NULL)?;
+                let lsp_response = process_request(command, &editor_state);
+
+                return encode_to_js(&lsp_response.locations).map_err(|_| JsValue::NULL);
+            }
+            PrepareRenameRequest::METHOD => {
+                let params: TextDocumentPositionParams = decode_from_js(js_params)?;
+                let file_url = params.text_document.uri;
+                let location = get_contract_location(&file_url).ok_or(JsValue::NULL)?;
+                let command = LspRequest::PrepareRename(location, params.position);
+                let editor_state = self
+                    .editor_state_lock
+                    .try_read()
+                    .map_err(|_| JsValue::NULL)?;
+                let lsp_response = process_request(command, &editor_state);
+                if let Some((_, message)) = lsp_response.notification {
+                    return Err(JsValue::from_str(&message));
+                }
+
+                return encode_to_js(&lsp_response.rename_range.map(PrepareRenameResponse::Range))
+                    .map_err(|_| JsValue::NULL);
+            }
+            Rename::METHOD => {
+                let params: RenameParams = decode_from_js(js_params)?;
+                let file_url = params.text_document_position.text_document.uri;
+                let location = get_contract_location(&file_url).ok_or(JsValue::NULL)?;
+                let command = LspRequest::Rename(
+                    location,
+                    params.text_document_position.position,
+                    params.new_name,
+                );
+                let editor_state = self
+                    .editor_state_lock
+                    .try_read()
+                    .map_err(|_| JsValue::NULL)?;
+                let lsp_response = process_request(command, &editor_state);
+                if let Some((_, message)) = lsp_response.notification {
+                    return Err(JsValue::from_str(&message));
+                }
+
+                return encode_to_js(&lsp_response.workspace_edit).map_err(|_| JsValue::NULL);
+            }
             _ => {
                 log!("unexpected request ({})", method);
             }
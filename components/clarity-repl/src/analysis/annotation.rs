@@ -1,11 +1,14 @@
 use clarity::vm::representations::Span;
 use clarity::vm::ClarityName;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 
 #[derive(Debug)]
 pub enum AnnotationKind {
     Allow(WarningKind),
+    Warn(WarningKind),
+    Deny(WarningKind),
     Filter(Vec<ClarityName>),
     FilterAll,
 }
@@ -22,9 +25,19 @@ impl std::str::FromStr for AnnotationKind {
                 (&captures[1], "")
             };
             match base {
-                "allow" => match value.parse() {
-                    Ok(value) => Ok(AnnotationKind::Allow(value)),
-                    Err(e) => Err("missing value for 'allow' annotation".to_string()),
+                // Parameters are only reported as unchecked data, their
+                // severity can not be changed
+                "warn" | "deny" if value == "unchecked_params" => Err(format!(
+                    "'unchecked_params' can only be allowed, not used with '{}'",
+                    base
+                )),
+                "allow" | "warn" | "deny" => match value.parse() {
+                    Ok(value) => Ok(match base {
+                        "allow" => AnnotationKind::Allow(value),
+                        "warn" => AnnotationKind::Warn(value),
+                        _ => AnnotationKind::Deny(value),
+                    }),
+                    Err(e) => Err(format!("missing value for '{}' annotation", base)),
                 },
                 "filter" => {
                     if value == "*" {
@@ -50,10 +63,17 @@ impl std::str::FromStr for AnnotationKind {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum WarningKind {
     UncheckedData,
     UncheckedParams,
+    Unused,
+    Cost,
+    Sip,
+    UncheckedResponse,
+    Arithmetic,
+    Lint,
 }
 
 impl std::str::FromStr for WarningKind {
@@ -63,6 +83,12 @@ impl std::str::FromStr for WarningKind {
         match s {
             "unchecked_data" => Ok(WarningKind::UncheckedData),
             "unchecked_params" => Ok(WarningKind::UncheckedParams),
+            "unused" => Ok(WarningKind::Unused),
+            "cost" => Ok(WarningKind::Cost),
+            "sip" => Ok(WarningKind::Sip),
+            "unchecked_response" => Ok(WarningKind::UncheckedResponse),
+            "arithmetic" => Ok(WarningKind::Arithmetic),
+            "lint" => Ok(WarningKind::Lint),
             _ => Err(format!("'{}' is not a valid warning identifier", s)),
         }
     }
@@ -102,6 +128,49 @@ mod tests {
         };
     }
 
+    #[test]
+    fn parse_allow_unused() {
+        match "allow(unused)".parse::<AnnotationKind>() {
+            Ok(AnnotationKind::Allow(WarningKind::Unused)) => (),
+            _ => panic!("failed to parse annotation kind correectly"),
+        };
+    }
+
+    #[test]
+    fn parse_deny_unused() {
+        match "deny(unused)".parse::<AnnotationKind>() {
+            Ok(AnnotationKind::Deny(WarningKind::Unused)) => (),
+            _ => panic!("failed to parse annotation kind correectly"),
+        };
+    }
+
+    #[test]
+    fn parse_deny_unchecked_params() {
+        match "deny(unchecked_params)".parse::<AnnotationKind>() {
+            Err(e) => assert_eq!(
+                e,
+                "'unchecked_params' can only be allowed, not used with 'deny'"
+            ),
+            _ => panic!("failed to return error for 'deny(unchecked_params)'"),
+        };
+    }
+
+    #[test]
+    fn parse_warn_cost() {
+        match "warn(cost)".parse::<AnnotationKind>() {
+            Ok(AnnotationKind::Warn(WarningKind::Cost)) => (),
+            _ => panic!("failed to parse annotation kind correectly"),
+        };
+    }
+
+    #[test]
+    fn parse_deny_empty() {
+        match "deny".parse::<AnnotationKind>() {
+            Err(_) => (),
+            _ => panic!("failed to return error for 'deny' with no value"),
+        };
+    }
+
     #[test]
     fn parse_annotation_kind_error() {
         match "invalid_string".parse::<AnnotationKind>() {
@@ -2,13 +2,13 @@ use crate::analysis::annotation::{Annotation, AnnotationKind, WarningKind};
 use crate::analysis::ast_visitor::{traverse, ASTVisitor, TypedVar};
 use crate::analysis::{self, AnalysisPass, AnalysisResult};
 use clarity::vm::analysis::analysis_db::AnalysisDatabase;
-use clarity::vm::analysis::types::ContractAnalysis;
+use clarity::vm::analysis::types::{ContractAnalysis, TypeMap};
 use clarity::vm::diagnostic::{DiagnosableError, Diagnostic, Level};
 use clarity::vm::functions::define::DefineFunctions;
 use clarity::vm::functions::NativeFunctions;
 use clarity::vm::representations::SymbolicExpressionType::*;
 use clarity::vm::representations::{Span, TraitDefinition};
-use clarity::vm::types::{TraitIdentifier, Value};
+use clarity::vm::types::{QualifiedContractIdentifier, TraitIdentifier, TypeSignature, Value};
 use clarity::vm::{ClarityName, ClarityVersion, SymbolicExpression};
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
@@ -107,6 +107,19 @@ struct FunctionInfo {
     filtered_params: Vec<bool>,
 }
 
+// The taint analysis is shared by several passes, which report the untrusted
+// inputs used in different places.
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    // Untrusted inputs used to update the state or move assets
+    Check,
+    // Untrusted inputs used with the privileges of the contract, inside of an
+    // `as-contract` node
+    AsContract,
+    // Untrusted inputs used in arithmetic operations which can abort
+    Arithmetic,
+}
+
 pub struct CheckChecker<'a, 'b> {
     db: &'a mut AnalysisDatabase<'b>,
     settings: Settings,
@@ -121,8 +134,13 @@ pub struct CheckChecker<'a, 'b> {
     // For each user-defined function, record which parameters are allowed
     // to be unchecked (tainted)
     user_funcs: HashMap<&'a ClarityName, FunctionInfo>,
-    // True if currently traversing within an `as-contract` node
-    in_as_contract: bool,
+    // The innermost `as-contract` node currently being traversed, if any
+    as_contract: Option<&'a SymbolicExpression>,
+    mode: Mode,
+    type_map: Option<&'a TypeMap>,
+    // Pairs of expressions `(greater, lesser)` compared by the assertions of
+    // the current function, guarding the arithmetic operations which follow
+    arithmetic_guards: Vec<(&'a SymbolicExpression, &'a SymbolicExpression)>,
 }
 
 impl<'a, 'b> CheckChecker<'a, 'b> {
@@ -130,6 +148,7 @@ impl<'a, 'b> CheckChecker<'a, 'b> {
         db: &'a mut AnalysisDatabase<'b>,
         annotations: &'a Vec<Annotation>,
         settings: Settings,
+        mode: Mode,
     ) -> CheckChecker<'a, 'b> {
         Self {
             db,
@@ -141,11 +160,16 @@ impl<'a, 'b> CheckChecker<'a, 'b> {
             active_annotation: None,
             public_funcs: HashSet::new(),
             user_funcs: HashMap::new(),
-            in_as_contract: false,
+            as_contract: None,
+            mode,
+            type_map: None,
+            arithmetic_guards: vec![],
         }
     }
 
     fn run(mut self, contract_analysis: &'a ContractAnalysis) -> AnalysisResult {
+        self.type_map = contract_analysis.type_map.as_ref();
+
         // First traverse the entire AST
         traverse(&mut self, &contract_analysis.expressions);
 
@@ -199,12 +223,96 @@ impl<'a, 'b> CheckChecker<'a, 'b> {
 
     // If this expression is tainted, add a diagnostic
     fn taint_check(&mut self, expr: &'a SymbolicExpression) {
+        if self.mode != Mode::Check {
+            return;
+        }
         if self.tainted_nodes.contains_key(&Node::Expr(expr.id)) {
             self.diagnostics
                 .insert(expr.id, self.generate_diagnostics(expr));
         }
     }
 
+    // If this expression is tainted and used with the privileges of the
+    // contract, add a diagnostic
+    fn as_contract_check(&mut self, expr: &'a SymbolicExpression) {
+        if self.mode != Mode::AsContract || self.as_contract.is_none() {
+            return;
+        }
+        if self.tainted_nodes.contains_key(&Node::Expr(expr.id)) {
+            self.diagnostics
+                .insert(expr.id, self.generate_diagnostics(expr));
+        }
+    }
+
+    // If an operand of this arithmetic operation is tainted, add a diagnostic
+    fn arithmetic_check(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        operands: &'a [SymbolicExpression],
+        message: &str,
+    ) {
+        if self.mode != Mode::Arithmetic {
+            return;
+        }
+        let mut sources = HashSet::new();
+        for operand in operands {
+            if let Some(tainted) = self.tainted_nodes.get(&Node::Expr(operand.id)) {
+                sources.extend(tainted.sources.clone());
+            }
+        }
+        if sources.is_empty() {
+            return;
+        }
+        let mut diagnostics = vec![Diagnostic {
+            level: Level::Warning,
+            message: message.to_string(),
+            spans: vec![expr.span.clone()],
+            suggestion: None,
+        }];
+        diagnostics.append(&mut self.source_notes(&sources));
+        self.diagnostics.insert(expr.id, diagnostics);
+    }
+
+    // Record the comparison asserted by `cond`, if any, as a guard for the
+    // arithmetic operations which follow
+    fn add_arithmetic_guard(&mut self, cond: &'a SymbolicExpression) {
+        let (function_name, args) = match cond.match_list().and_then(|list| list.split_first()) {
+            Some((function_name, args)) if args.len() == 2 => (function_name, args),
+            _ => return,
+        };
+        let guard = match function_name.match_atom().map(|name| name.as_str()) {
+            Some(">=") | Some(">") => (&args[0], &args[1]),
+            Some("<=") | Some("<") => (&args[1], &args[0]),
+            _ => return,
+        };
+        self.arithmetic_guards.push(guard);
+    }
+
+    // A subtraction `(- a b)` is guarded by an assertion that `a` is greater
+    // than `b`, and a multiplication by assertions bounding each of its
+    // untrusted operands with a trusted value.
+    fn is_guarded(&self, func: NativeFunctions, operands: &[SymbolicExpression]) -> bool {
+        let is_tainted =
+            |expr: &SymbolicExpression| self.tainted_nodes.contains_key(&Node::Expr(expr.id));
+        match func {
+            NativeFunctions::Subtract if operands.len() == 2 => {
+                self.arithmetic_guards.iter().any(|(greater, lesser)| {
+                    is_same_expression(greater, &operands[0])
+                        && is_same_expression(lesser, &operands[1])
+                })
+            }
+            NativeFunctions::Multiply => operands
+                .iter()
+                .filter(|operand| is_tainted(*operand))
+                .all(|operand| {
+                    self.arithmetic_guards.iter().any(|(greater, lesser)| {
+                        is_same_expression(lesser, operand) && !is_tainted(*greater)
+                    })
+                }),
+            _ => false,
+        }
+    }
+
     fn filter_source(&mut self, source_node: &Node<'a>, rollback: bool) {
         if let Some(source) = self.taint_sources.remove(source_node) {
             self.tainted_nodes.remove(&source_node);
@@ -263,12 +371,15 @@ impl<'a, 'b> CheckChecker<'a, 'b> {
         }
     }
 
-    // Check if the expression is annotated with `allow(unchecked_data)`
+    // Check if the expression is annotated with `allow(unchecked_data)`, or
+    // with `allow(arithmetic)` for the arithmetic checks
     fn allow_unchecked_data(&self) -> bool {
         if let Some(idx) = self.active_annotation {
             let annotation = &self.annotations[idx];
-            return match annotation.kind {
-                AnnotationKind::Allow(WarningKind::UncheckedData) => true,
+            return match (self.mode, &annotation.kind) {
+                (Mode::Arithmetic, AnnotationKind::Allow(WarningKind::Arithmetic)) => true,
+                (Mode::Arithmetic, _) => false,
+                (_, AnnotationKind::Allow(WarningKind::UncheckedData)) => true,
                 _ => false,
             };
         }
@@ -306,32 +417,55 @@ impl<'a, 'b> CheckChecker<'a, 'b> {
 
     fn generate_diagnostics(&self, expr: &SymbolicExpression) -> Vec<Diagnostic> {
         let mut diagnostics: Vec<Diagnostic> = Vec::new();
+        let message = if self.mode == Mode::AsContract {
+            "use of potentially unchecked data inside as-contract"
+        } else {
+            "use of potentially unchecked data"
+        };
         let diagnostic = Diagnostic {
             level: Level::Warning,
-            message: "use of potentially unchecked data".to_string(),
+            message: message.to_string(),
             spans: vec![expr.span.clone()],
             suggestion: None,
         };
         diagnostics.push(diagnostic);
 
+        if let (Mode::AsContract, Some(as_contract)) = (self.mode, self.as_contract) {
+            // Point at the `as-contract` keyword, rather than the entire expression
+            let span = match as_contract.match_list().and_then(|list| list.get(0)) {
+                Some(keyword) => keyword.span.clone(),
+                None => as_contract.span.clone(),
+            };
+            diagnostics.push(Diagnostic {
+                level: Level::Note,
+                message: "executed with the privileges of the contract here".to_string(),
+                spans: vec![span],
+                suggestion: None,
+            });
+        }
+
         let tainted = &self.tainted_nodes[&Node::Expr(expr.id)];
-        // Add a note for each source, ordered by span
+        diagnostics.append(&mut self.source_notes(&tainted.sources));
+        diagnostics
+    }
+
+    // A note for each source, ordered by span
+    fn source_notes(&self, sources: &HashSet<Node<'a>>) -> Vec<Diagnostic> {
         let mut source_spans = vec![];
-        for source in &tainted.sources {
+        for source in sources {
             let span = self.taint_sources[source].span.clone();
             let pos = source_spans.binary_search(&span).unwrap_or_else(|e| e);
             source_spans.insert(pos, span);
         }
-        for span in source_spans {
-            let diagnostic = Diagnostic {
+        source_spans
+            .into_iter()
+            .map(|span| Diagnostic {
                 level: Level::Note,
                 message: "source of untrusted input here".to_string(),
                 spans: vec![span],
                 suggestion: None,
-            };
-            diagnostics.push(diagnostic);
-        }
-        diagnostics
+            })
+            .collect()
     }
 }
 
@@ -367,6 +501,7 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
 
         self.taint_sources.clear();
         self.tainted_nodes.clear();
+        self.arithmetic_guards.clear();
 
         // Upon entering a public function, all parameters are tainted
         if let Some(params) = parameters {
@@ -397,6 +532,7 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
     ) -> bool {
         self.taint_sources.clear();
         self.tainted_nodes.clear();
+        self.arithmetic_guards.clear();
         let mut info = FunctionInfo {
             unchecked_params: vec![],
             filtered_params: vec![],
@@ -446,6 +582,17 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
         else_expr: &'a SymbolicExpression,
     ) -> bool {
         self.traverse_expr(cond);
+        if self.mode == Mode::Arithmetic {
+            // The comparison only guards the arithmetic operations of the
+            // `then` branch
+            let guards = self.arithmetic_guards.len();
+            self.add_arithmetic_guard(cond);
+            self.traverse_expr(then_expr);
+            self.arithmetic_guards.truncate(guards);
+            self.traverse_expr(else_expr);
+            self.arithmetic_guards.truncate(guards);
+            return true;
+        }
         self.filter_taint(cond, false);
 
         self.traverse_expr(then_expr);
@@ -453,6 +600,41 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
         true
     }
 
+    fn traverse_match_option(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        input: &'a SymbolicExpression,
+        some_name: &'a ClarityName,
+        some_branch: &'a SymbolicExpression,
+        none_branch: &'a SymbolicExpression,
+    ) -> bool {
+        // The assertions of a branch only guard the arithmetic operations of
+        // this branch
+        let guards = self.arithmetic_guards.len();
+        let res = self.traverse_expr(input) && self.traverse_expr(some_branch);
+        self.arithmetic_guards.truncate(guards);
+        let res = res && self.traverse_expr(none_branch);
+        self.arithmetic_guards.truncate(guards);
+        res && self.visit_match_option(expr, input, some_name, some_branch, none_branch)
+    }
+
+    fn traverse_match_response(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        input: &'a SymbolicExpression,
+        ok_name: &'a ClarityName,
+        ok_branch: &'a SymbolicExpression,
+        err_name: &'a ClarityName,
+        err_branch: &'a SymbolicExpression,
+    ) -> bool {
+        let guards = self.arithmetic_guards.len();
+        let res = self.traverse_expr(input) && self.traverse_expr(ok_branch);
+        self.arithmetic_guards.truncate(guards);
+        let res = res && self.traverse_expr(err_branch);
+        self.arithmetic_guards.truncate(guards);
+        res && self.visit_match_response(expr, input, ok_name, ok_branch, err_name, err_branch)
+    }
+
     fn traverse_lazy_logical(
         &mut self,
         expr: &'a SymbolicExpression,
@@ -461,7 +643,9 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
     ) -> bool {
         for operand in operands {
             self.traverse_expr(operand);
-            self.filter_taint(operand, false);
+            if self.mode != Mode::Arithmetic {
+                self.filter_taint(operand, false);
+            }
         }
         true
     }
@@ -472,6 +656,9 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
         bindings: &HashMap<&'a ClarityName, &'a SymbolicExpression>,
         body: &'a [SymbolicExpression],
     ) -> bool {
+        // The assertions in the body of the let only guard the arithmetic
+        // operations which follow them in this body
+        let guards = self.arithmetic_guards.len();
         for (name, val) in bindings {
             if !self.traverse_expr(val) {
                 return false;
@@ -505,6 +692,7 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
             self.taint_sources.remove(&node);
             self.tainted_nodes.remove(&node);
         }
+        self.arithmetic_guards.truncate(guards);
         true
     }
 
@@ -513,11 +701,13 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
         expr: &'a SymbolicExpression,
         statements: &'a [SymbolicExpression],
     ) -> bool {
+        let guards = self.arithmetic_guards.len();
         for stmt in statements {
             if !self.traverse_expr(stmt) {
                 return false;
             }
         }
+        self.arithmetic_guards.truncate(guards);
 
         // the value of the begin is determined by the last expression
         if let Some(tainted) = &self.tainted_nodes.get(&Node::Expr(expr.id)) {
@@ -533,9 +723,9 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
         expr: &'a SymbolicExpression,
         inner: &'a SymbolicExpression,
     ) -> bool {
-        self.in_as_contract = true;
+        let outer = self.as_contract.replace(expr);
         let res = self.traverse_expr(inner) && self.visit_as_contract(expr, inner);
-        self.in_as_contract = false;
+        self.as_contract = outer;
         res
     }
 
@@ -545,7 +735,48 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
         cond: &'a SymbolicExpression,
         thrown: &'a SymbolicExpression,
     ) -> bool {
-        self.filter_taint(cond, true);
+        // Arithmetic operations are only guarded by comparisons between their
+        // operands, asserted before the operation: an assertion placed after
+        // it does not prevent it from aborting.
+        if self.mode == Mode::Arithmetic {
+            self.add_arithmetic_guard(cond);
+        } else {
+            self.filter_taint(cond, true);
+        }
+        true
+    }
+
+    fn visit_arithmetic(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        func: NativeFunctions,
+        operands: &'a [SymbolicExpression],
+    ) -> bool {
+        if self.is_guarded(func, operands) {
+            return true;
+        }
+        match func {
+            NativeFunctions::Subtract => {
+                let is_uint = operands.first().map_or(false, |operand| {
+                    self.type_map
+                        .and_then(|type_map| type_map.get_type(operand))
+                        == Some(&TypeSignature::UIntType)
+                });
+                if is_uint {
+                    self.arithmetic_check(
+                        expr,
+                        operands,
+                        "subtraction of potentially unchecked data may underflow",
+                    );
+                }
+            }
+            NativeFunctions::Multiply => self.arithmetic_check(
+                expr,
+                operands,
+                "multiplication of potentially unchecked data may overflow",
+            ),
+            _ => (),
+        }
         true
     }
 
@@ -601,11 +832,12 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
     ) -> bool {
         // Input from the sender can be used un-checked to interact with the
         // sender's assets. The sender is protected by post-conditions.
-        if match_tx_sender(sender) && !self.in_as_contract {
+        if match_tx_sender(sender) && self.as_contract.is_none() {
             return true;
         }
         self.taint_check(amount);
         self.taint_check(sender);
+        self.as_contract_check(amount);
         true
     }
 
@@ -619,12 +851,14 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
     ) -> bool {
         // Input from the sender can be used un-checked to interact with the
         // sender's assets. The sender is protected by post-conditions.
-        if match_tx_sender(sender) && !self.in_as_contract {
+        if match_tx_sender(sender) && self.as_contract.is_none() {
             return true;
         }
         self.taint_check(amount);
         self.taint_check(sender);
         self.taint_check(recipient);
+        self.as_contract_check(amount);
+        self.as_contract_check(recipient);
         true
     }
 
@@ -637,11 +871,12 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
     ) -> bool {
         // Input from the sender can be used un-checked to interact with the
         // sender's assets. The sender is protected by post-conditions.
-        if match_tx_sender(sender) && !self.in_as_contract {
+        if match_tx_sender(sender) && self.as_contract.is_none() {
             return true;
         }
         self.taint_check(amount);
         self.taint_check(sender);
+        self.as_contract_check(amount);
         true
     }
 
@@ -655,12 +890,14 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
     ) -> bool {
         // Input from the sender can be used un-checked to interact with the
         // sender's assets. The sender is protected by post-conditions.
-        if match_tx_sender(sender) && !self.in_as_contract {
+        if match_tx_sender(sender) && self.as_contract.is_none() {
             return true;
         }
         self.taint_check(amount);
         self.taint_check(sender);
         self.taint_check(recipient);
+        self.as_contract_check(amount);
+        self.as_contract_check(recipient);
         true
     }
 
@@ -685,11 +922,12 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
     ) -> bool {
         // Input from the sender can be used un-checked to interact with the
         // sender's assets. The sender is protected by post-conditions.
-        if match_tx_sender(sender) && !self.in_as_contract {
+        if match_tx_sender(sender) && self.as_contract.is_none() {
             return true;
         }
         self.taint_check(identifier);
         self.taint_check(sender);
+        self.as_contract_check(identifier);
         true
     }
 
@@ -703,12 +941,14 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
     ) -> bool {
         // Input from the sender can be used un-checked to interact with the
         // sender's assets. The sender is protected by post-conditions.
-        if match_tx_sender(sender) && !self.in_as_contract {
+        if match_tx_sender(sender) && self.as_contract.is_none() {
             return true;
         }
         self.taint_check(identifier);
         self.taint_check(sender);
         self.taint_check(recipient);
+        self.as_contract_check(identifier);
+        self.as_contract_check(recipient);
         true
     }
 
@@ -786,6 +1026,23 @@ impl<'a> ASTVisitor<'a> for CheckChecker<'a, '_> {
         args: &'a [SymbolicExpression],
     ) -> bool {
         self.taint_check(trait_ref);
+        self.as_contract_check(trait_ref);
+        for arg in args {
+            self.as_contract_check(arg);
+        }
+        true
+    }
+
+    fn visit_static_contract_call(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        contract_identifier: &'a QualifiedContractIdentifier,
+        function_name: &'a ClarityName,
+        args: &'a [SymbolicExpression],
+    ) -> bool {
+        for arg in args {
+            self.as_contract_check(arg);
+        }
         true
     }
 
@@ -863,11 +1120,74 @@ impl AnalysisPass for CheckChecker<'_, '_> {
         annotations: &Vec<Annotation>,
         settings: &analysis::Settings,
     ) -> AnalysisResult {
-        let checker = CheckChecker::new(analysis_db, annotations, settings.check_checker);
+        let checker = CheckChecker::new(
+            analysis_db,
+            annotations,
+            settings.check_checker,
+            Mode::Check,
+        );
+        checker.run(contract_analysis)
+    }
+}
+
+/// Reuses the taint analysis of the check-checker to report the untrusted
+/// inputs used inside of an `as-contract` expression, where transfers and
+/// contract calls are executed with the privileges of the contract.
+pub struct AsContractChecker;
+
+impl AnalysisPass for AsContractChecker {
+    fn run_pass(
+        contract_analysis: &mut ContractAnalysis,
+        analysis_db: &mut AnalysisDatabase,
+        annotations: &Vec<Annotation>,
+        settings: &analysis::Settings,
+    ) -> AnalysisResult {
+        let checker = CheckChecker::new(
+            analysis_db,
+            annotations,
+            settings.check_checker,
+            Mode::AsContract,
+        );
         checker.run(contract_analysis)
     }
 }
 
+/// Reuses the taint analysis of the check-checker to report the subtractions
+/// of uints which may underflow and the multiplications which may overflow,
+/// when an operand comes from an untrusted input that no assertion checked
+/// before the operation.
+pub struct ArithmeticChecker;
+
+impl AnalysisPass for ArithmeticChecker {
+    fn run_pass(
+        contract_analysis: &mut ContractAnalysis,
+        analysis_db: &mut AnalysisDatabase,
+        annotations: &Vec<Annotation>,
+        settings: &analysis::Settings,
+    ) -> AnalysisResult {
+        let checker = CheckChecker::new(
+            analysis_db,
+            annotations,
+            settings.check_checker,
+            Mode::Arithmetic,
+        );
+        checker.run(contract_analysis)
+    }
+}
+
+// Check if two expressions are the same, regardless of their location
+fn is_same_expression(a: &SymbolicExpression, b: &SymbolicExpression) -> bool {
+    match (&a.expr, &b.expr) {
+        (List(a), List(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|(a, b)| is_same_expression(a, b))
+        }
+        (a, b) => a == b,
+    }
+}
+
 fn match_tx_sender(expr: &SymbolicExpression) -> bool {
     if let Some(name) = expr.match_atom() {
         if name.as_str() == "tx-sender" {
@@ -3816,4 +4136,441 @@ mod tests {
             _ => panic!("Expected successful interpretation"),
         };
     }
+
+    #[test]
+    fn as_contract_tainted_recipient() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::AsContractChecker];
+        let mut session = Session::new(settings);
+        let snippet = "
+(define-public (withdraw (recipient principal))
+    (as-contract (stx-transfer? u100 tx-sender recipient))
+)
+"
+        .to_string();
+        match session.formatted_interpretation(
+            snippet,
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((output, _)) => {
+                assert_eq!(output.len(), 9);
+                assert_eq!(
+                    output[0],
+                    format!(
+                        "checker:3:48: {}: use of potentially unchecked data inside as-contract",
+                        yellow!("warning")
+                    )
+                );
+                assert_eq!(
+                    output[1],
+                    "    (as-contract (stx-transfer? u100 tx-sender recipient))"
+                );
+                assert_eq!(
+                    output[2],
+                    "                                               ^~~~~~~~~"
+                );
+                assert_eq!(
+                    output[3],
+                    format!(
+                        "checker:3:6: {}: executed with the privileges of the contract here",
+                        blue!("note")
+                    )
+                );
+                assert_eq!(
+                    output[4],
+                    "    (as-contract (stx-transfer? u100 tx-sender recipient))"
+                );
+                assert_eq!(output[5], "     ^~~~~~~~~~~");
+                assert_eq!(
+                    output[6],
+                    format!(
+                        "checker:2:27: {}: source of untrusted input here",
+                        blue!("note")
+                    )
+                );
+                assert_eq!(output[7], "(define-public (withdraw (recipient principal))");
+                assert_eq!(output[8], "                          ^~~~~~~~~");
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
+
+    #[test]
+    fn as_contract_tainted_trait() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::AsContractChecker];
+        let mut session = Session::new(settings);
+        let snippet = "
+(define-trait token-trait ((transfer (uint principal principal) (response bool uint))))
+(define-public (pay (token <token-trait>) (amount uint))
+    (begin
+        (asserts! (< amount u1000) (err u1))
+        (as-contract (contract-call? token transfer amount tx-sender tx-sender))
+    )
+)
+"
+        .to_string();
+        match session.formatted_interpretation(
+            snippet,
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((output, result)) => {
+                assert_eq!(result.diagnostics.len(), 3);
+                assert_eq!(
+                    output[0],
+                    format!(
+                        "checker:6:38: {}: use of potentially unchecked data inside as-contract",
+                        yellow!("warning")
+                    )
+                );
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
+
+    #[test]
+    fn as_contract_checked() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::AsContractChecker];
+        let mut session = Session::new(settings);
+        let snippet = "
+(define-data-var owner principal tx-sender)
+(define-public (withdraw (amount uint) (recipient principal))
+    (begin
+        (asserts! (is-eq recipient (var-get owner)) (err u1))
+        (asserts! (< amount u1000) (err u2))
+        (as-contract (stx-transfer? amount tx-sender recipient))
+    )
+)
+"
+        .to_string();
+        match session.formatted_interpretation(
+            snippet,
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((_, result)) => {
+                assert_eq!(result.diagnostics.len(), 0);
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
+
+    #[test]
+    fn as_contract_not_used() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::AsContractChecker];
+        let mut session = Session::new(settings);
+        let snippet = "
+(define-public (deposit (amount uint) (recipient principal))
+    (stx-transfer? amount tx-sender recipient)
+)
+"
+        .to_string();
+        match session.formatted_interpretation(
+            snippet,
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((_, result)) => {
+                assert_eq!(result.diagnostics.len(), 0);
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
+
+    #[test]
+    fn arithmetic_underflow() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::ArithmeticChecker];
+        let mut session = Session::new(settings);
+        let snippet = "
+(define-map balances principal uint)
+(define-public (withdraw (amount uint))
+    (let ((balance (default-to u0 (map-get? balances tx-sender))))
+        (map-set balances tx-sender (- balance amount))
+        (ok true)
+    )
+)
+"
+        .to_string();
+        match session.formatted_interpretation(
+            snippet,
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((output, _)) => {
+                assert_eq!(output.len(), 6);
+                assert_eq!(
+                    output[0],
+                    format!(
+                        "checker:5:37: {}: subtraction of potentially unchecked data may underflow",
+                        yellow!("warning")
+                    )
+                );
+                assert_eq!(
+                    output[1],
+                    "        (map-set balances tx-sender (- balance amount))"
+                );
+                assert_eq!(
+                    output[2],
+                    "                                    ^~~~~~~~~~~~~~~~~~"
+                );
+                assert_eq!(
+                    output[3],
+                    format!(
+                        "checker:3:27: {}: source of untrusted input here",
+                        blue!("note")
+                    )
+                );
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
+
+    #[test]
+    fn arithmetic_guarded() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::ArithmeticChecker];
+        let mut session = Session::new(settings);
+        let snippet = "
+(define-map balances principal uint)
+(define-public (withdraw (amount uint))
+    (let ((balance (default-to u0 (map-get? balances tx-sender))))
+        (asserts! (<= amount balance) (err u1))
+        (map-set balances tx-sender (- balance amount))
+        (ok true)
+    )
+)
+"
+        .to_string();
+        match session.formatted_interpretation(
+            snippet,
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((_, result)) => {
+                assert_eq!(result.diagnostics.len(), 0);
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
+
+    #[test]
+    fn arithmetic_unrelated_guard() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::ArithmeticChecker];
+        let mut session = Session::new(settings);
+        let snippet = "
+(define-map balances principal uint)
+(define-public (withdraw (amount uint))
+    (let ((balance (default-to u0 (map-get? balances tx-sender))))
+        (asserts! (> amount u0) (err u1))
+        (map-set balances tx-sender (- balance amount))
+        (ok true)
+    )
+)
+"
+        .to_string();
+        match session.formatted_interpretation(
+            snippet,
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((output, result)) => {
+                assert_eq!(result.diagnostics.len(), 2);
+                assert_eq!(
+                    output[0],
+                    format!(
+                        "checker:6:37: {}: subtraction of potentially unchecked data may underflow",
+                        yellow!("warning")
+                    )
+                );
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
+
+    #[test]
+    fn arithmetic_guard_after() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::ArithmeticChecker];
+        let mut session = Session::new(settings);
+        let snippet = "
+(define-map balances principal uint)
+(define-public (withdraw (amount uint))
+    (let ((balance (default-to u0 (map-get? balances tx-sender))))
+        (map-set balances tx-sender (- balance amount))
+        (asserts! (<= amount balance) (err u1))
+        (ok true)
+    )
+)
+"
+        .to_string();
+        match session.formatted_interpretation(
+            snippet,
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((_, result)) => {
+                assert_eq!(result.diagnostics.len(), 2);
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
+
+    #[test]
+    fn arithmetic_guard_in_match_arm() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::ArithmeticChecker];
+        let mut session = Session::new(settings);
+        let snippet = "
+(define-map balances principal uint)
+(define-public (withdraw (amount uint))
+    (let ((balance (default-to u0 (map-get? balances tx-sender))))
+        (match (map-get? balances contract-caller)
+            limit (begin
+                (asserts! (<= amount balance) (err u1))
+                (map-set balances tx-sender (- balance amount))
+            )
+            true
+        )
+        (map-set balances tx-sender (- balance amount))
+        (ok true)
+    )
+)
+"
+        .to_string();
+        match session.formatted_interpretation(
+            snippet,
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((output, result)) => {
+                // The assertion only guards the subtraction of its own arm
+                assert_eq!(result.diagnostics.len(), 2);
+                assert_eq!(
+                    output[0],
+                    format!(
+                        "checker:12:37: {}: subtraction of potentially unchecked data may underflow",
+                        yellow!("warning")
+                    )
+                );
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
+
+    #[test]
+    fn arithmetic_overflow() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::ArithmeticChecker];
+        let mut session = Session::new(settings);
+        let snippet = "
+(define-data-var total uint u0)
+(define-public (buy (quantity uint) (price uint))
+    (begin
+        (var-set total (* quantity price))
+        (ok true)
+    )
+)
+"
+        .to_string();
+        match session.formatted_interpretation(
+            snippet,
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((output, result)) => {
+                assert_eq!(result.diagnostics.len(), 3);
+                assert_eq!(
+                    output[0],
+                    format!(
+                        "checker:5:24: {}: multiplication of potentially unchecked data may overflow",
+                        yellow!("warning")
+                    )
+                );
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
+
+    #[test]
+    fn arithmetic_overflow_guarded() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::ArithmeticChecker];
+        let mut session = Session::new(settings);
+        let snippet = "
+(define-data-var total uint u0)
+(define-public (buy (quantity uint) (price uint))
+    (begin
+        (asserts! (<= quantity u100) (err u1))
+        (asserts! (< price u1000000) (err u2))
+        (var-set total (* quantity price))
+        (ok true)
+    )
+)
+"
+        .to_string();
+        match session.formatted_interpretation(
+            snippet,
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((_, result)) => {
+                assert_eq!(result.diagnostics.len(), 0);
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
+
+    #[test]
+    fn allow_arithmetic() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::ArithmeticChecker];
+        let mut session = Session::new(settings);
+        let snippet = "
+(define-public (withdraw (amount uint))
+    ;; #[allow(arithmetic)]
+    (ok (- u100 amount))
+)
+"
+        .to_string();
+        match session.formatted_interpretation(
+            snippet,
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((_, result)) => {
+                assert_eq!(result.diagnostics.len(), 0);
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
 }
@@ -0,0 +1,852 @@
+use crate::analysis::annotation::{Annotation, AnnotationKind, WarningKind};
+use crate::analysis::{self, AnalysisPass, AnalysisResult};
+use crate::repl::interpreter::BLOCK_LIMIT_MAINNET;
+use clarity::vm::analysis::analysis_db::AnalysisDatabase;
+use clarity::vm::analysis::types::ContractAnalysis;
+use clarity::vm::costs::ExecutionCost;
+use clarity::vm::diagnostic::{Diagnostic, Level};
+use clarity::vm::functions::define::DefineFunctions;
+use clarity::vm::functions::NativeFunctions;
+use clarity::vm::representations::SymbolicExpressionType::*;
+use clarity::vm::types::{SequenceSubtype, StringSubtype, TypeSignature};
+use clarity::vm::{ClarityName, SymbolicExpression};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Settings {
+    // Percentage of the block limit above which a function is reported
+    threshold: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct SettingsFile {
+    // Percentage of the block limit above which a function is reported
+    threshold: Option<u64>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self { threshold: 50 }
+    }
+}
+
+impl From<SettingsFile> for Settings {
+    fn from(from_file: SettingsFile) -> Self {
+        Settings {
+            threshold: from_file.threshold.unwrap_or(50),
+        }
+    }
+}
+
+/// Estimate the worst case execution cost of each public function of a contract.
+///
+/// The estimation is based on an approximation of the cost functions of the
+/// `costs-2` boot contract. The maximum length of the sequences, and the maximum
+/// size of the values read or written, are taken from the type signatures, and
+/// iterations over sequences (`map`, `fold` and `filter`) are accounted for as
+/// if the sequences were full. The cost of the functions called in other
+/// contracts, with `contract-call?`, is not included.
+pub fn estimate_costs(
+    contract_analysis: &ContractAnalysis,
+) -> BTreeMap<ClarityName, ExecutionCost> {
+    let mut estimator = CostEstimator::new(contract_analysis);
+    let mut costs = BTreeMap::new();
+    for (name, _) in estimator.public_functions() {
+        let cost = estimator.function_cost(name);
+        costs.insert(name.clone(), cost);
+    }
+    costs
+}
+
+struct UserFunction<'a> {
+    params: &'a [SymbolicExpression],
+    body: &'a SymbolicExpression,
+    is_public: bool,
+    name_expr: &'a SymbolicExpression,
+}
+
+pub struct CostEstimator<'a> {
+    contract_analysis: &'a ContractAnalysis,
+    functions: HashMap<&'a ClarityName, UserFunction<'a>>,
+    // Worst case cost of the user-defined functions already estimated
+    function_costs: HashMap<&'a ClarityName, ExecutionCost>,
+    // Functions being estimated, to guard against (invalid) recursive calls
+    in_progress: HashSet<&'a ClarityName>,
+}
+
+impl<'a> CostEstimator<'a> {
+    fn new(contract_analysis: &'a ContractAnalysis) -> CostEstimator<'a> {
+        let mut functions = HashMap::new();
+        for expr in contract_analysis.expressions.iter() {
+            if let Some((name, function)) = match_function_definition(expr) {
+                functions.insert(name, function);
+            }
+        }
+        Self {
+            contract_analysis,
+            functions,
+            function_costs: HashMap::new(),
+            in_progress: HashSet::new(),
+        }
+    }
+
+    // Public functions, in the order of their definitions
+    fn public_functions(&self) -> Vec<(&'a ClarityName, &'a SymbolicExpression)> {
+        let mut functions: Vec<_> = self
+            .functions
+            .iter()
+            .filter(|(_, function)| function.is_public)
+            .map(|(name, function)| (*name, function.name_expr))
+            .collect();
+        functions.sort_by(|a, b| a.1.span.cmp(&b.1.span));
+        functions
+    }
+
+    fn run(mut self, settings: Settings, annotations: &[Annotation]) -> AnalysisResult {
+        let mut diagnostics = vec![];
+        for (name, name_expr) in self.public_functions() {
+            // Functions annotated with `allow(cost)` are not reported
+            let allowed = annotations.iter().any(|annotation| {
+                annotation.span.start_line == name_expr.span.start_line - 1
+                    && matches!(annotation.kind, AnnotationKind::Allow(WarningKind::Cost))
+            });
+            if allowed {
+                continue;
+            }
+            let cost = self.function_cost(name);
+            let (dimension, share) = largest_share(&cost);
+            if share > settings.threshold {
+                diagnostics.push(Diagnostic {
+                    level: Level::Warning,
+                    message: format!(
+                        "worst case {} of '{}' is {}% of the block limit (threshold: {}%)",
+                        dimension, name, share, settings.threshold
+                    ),
+                    spans: vec![name_expr.span.clone()],
+                    suggestion: None,
+                });
+            }
+            // The estimate of each function is reported, along with the warning
+            // when it exceeds the threshold
+            diagnostics.push(Diagnostic {
+                level: Level::Note,
+                message: format!(
+                    "worst case cost of '{}': runtime {}, read count {}, read length {}, write count {}, write length {}",
+                    name,
+                    cost.runtime,
+                    cost.read_count,
+                    cost.read_length,
+                    cost.write_count,
+                    cost.write_length
+                ),
+                spans: vec![name_expr.span.clone()],
+                suggestion: None,
+            });
+        }
+        Ok(diagnostics)
+    }
+
+    fn function_cost(&mut self, name: &'a ClarityName) -> ExecutionCost {
+        if let Some(cost) = self.function_costs.get(name) {
+            return cost.clone();
+        }
+        let (params, body) = match self.functions.get(name) {
+            Some(function) => (function.params, function.body),
+            None => return ExecutionCost::zero(),
+        };
+        if !self.in_progress.insert(name) {
+            return ExecutionCost::zero();
+        }
+
+        // Applying the function, and checking the types of its arguments
+        let mut cost = runtime(linear(params.len() as u64, 26, 5));
+        for param in params.iter() {
+            let size = param
+                .match_list()
+                .and_then(|pair| pair.get(1))
+                .and_then(|type_expr| TypeSignature::parse_type_repr(type_expr, &mut ()).ok())
+                .map_or(0, |type_signature| type_signature.size() as u64);
+            add(&mut cost, &runtime(linear(size, 2, 9)));
+        }
+        add(&mut cost, &self.expr_cost(body));
+
+        self.in_progress.remove(name);
+        self.function_costs.insert(name, cost.clone());
+        cost
+    }
+
+    fn expr_cost(&mut self, expr: &'a SymbolicExpression) -> ExecutionCost {
+        match &expr.expr {
+            List(list) => self.list_cost(expr, list),
+            // Looking up a variable
+            Atom(_) => runtime(linear(self.size_of(expr), 2, 1)),
+            _ => ExecutionCost::zero(),
+        }
+    }
+
+    fn args_cost(&mut self, args: &'a [SymbolicExpression]) -> ExecutionCost {
+        let mut cost = ExecutionCost::zero();
+        for arg in args.iter() {
+            add(&mut cost, &self.expr_cost(arg));
+        }
+        cost
+    }
+
+    fn list_cost(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        list: &'a [SymbolicExpression],
+    ) -> ExecutionCost {
+        let (function_name, args) = match list.split_first() {
+            Some((function_name, args)) => match function_name.match_atom() {
+                Some(function_name) => (function_name, args),
+                None => return ExecutionCost::zero(),
+            },
+            None => return ExecutionCost::zero(),
+        };
+        if DefineFunctions::lookup_by_name(function_name).is_some() {
+            return ExecutionCost::zero();
+        }
+        match NativeFunctions::lookup_by_name_at_version(
+            function_name,
+            &self.contract_analysis.clarity_version,
+        ) {
+            Some(native_function) => self.native_cost(expr, native_function, args),
+            None => {
+                let mut cost = self.args_cost(args);
+                add(&mut cost, &self.function_cost(function_name));
+                cost
+            }
+        }
+    }
+
+    // Cost of iterating `function` over sequences of `len` elements
+    fn iteration_cost(
+        &mut self,
+        function: Option<&'a SymbolicExpression>,
+        len: u64,
+    ) -> ExecutionCost {
+        let function_name = match function.and_then(|function| function.match_atom()) {
+            Some(function_name) => function_name,
+            None => return ExecutionCost::zero(),
+        };
+        let mut cost = if self.functions.contains_key(function_name) {
+            self.function_cost(function_name)
+        } else {
+            // A native function, such as `+`, applied to each element
+            runtime(linear(2, 13, 125))
+        };
+        multiply(&mut cost, len);
+        cost
+    }
+
+    fn native_cost(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        function: NativeFunctions,
+        args: &'a [SymbolicExpression],
+    ) -> ExecutionCost {
+        use clarity::vm::functions::NativeFunctions::*;
+        let n = args.len() as u64;
+        match function {
+            // Only one of the branches is evaluated
+            If => {
+                let mut cost = runtime(168);
+                add(
+                    &mut cost,
+                    &self.args_cost(args.get(0..1).unwrap_or_default()),
+                );
+                let then_cost = self.args_cost(args.get(1..2).unwrap_or_default());
+                let else_cost = self.args_cost(args.get(2..3).unwrap_or_default());
+                add(&mut cost, &max(&then_cost, &else_cost));
+                cost
+            }
+            Match => {
+                let mut cost = runtime(264);
+                add(
+                    &mut cost,
+                    &self.args_cost(args.get(0..1).unwrap_or_default()),
+                );
+                // `(match opt some-name some-branch none-branch)` or
+                // `(match res ok-name ok-branch err-name err-branch)`
+                let (first, second) = match args.len() {
+                    4 => (args.get(2..3), args.get(3..4)),
+                    _ => (args.get(2..3), args.get(4..5)),
+                };
+                let first_cost = self.args_cost(first.unwrap_or_default());
+                let second_cost = self.args_cost(second.unwrap_or_default());
+                add(&mut cost, &max(&first_cost, &second_cost));
+                cost
+            }
+            Let => {
+                let bindings = args
+                    .get(0)
+                    .and_then(|bindings| bindings.match_list())
+                    .unwrap_or_default();
+                let mut cost = runtime(linear(bindings.len() as u64, 117, 178));
+                for binding in bindings.iter() {
+                    if let Some(value) = binding.match_list().and_then(|pair| pair.get(1)) {
+                        add(&mut cost, &self.expr_cost(value));
+                    }
+                }
+                add(
+                    &mut cost,
+                    &self.args_cost(args.get(1..).unwrap_or_default()),
+                );
+                cost
+            }
+            Map => {
+                let sequences = args.get(1..).unwrap_or_default();
+                let len = sequences
+                    .iter()
+                    .map(|sequence| self.max_len(sequence))
+                    .max()
+                    .unwrap_or(0);
+                let mut cost = runtime(linear(sequences.len() as u64, 1198, 3067));
+                add(&mut cost, &self.args_cost(sequences));
+                add(&mut cost, &self.iteration_cost(args.get(0), len));
+                cost
+            }
+            Fold | Filter => {
+                let len = args.get(1).map_or(0, |sequence| self.max_len(sequence));
+                let mut cost = runtime(if function == Fold { 460 } else { 407 });
+                add(
+                    &mut cost,
+                    &self.args_cost(args.get(1..).unwrap_or_default()),
+                );
+                add(&mut cost, &self.iteration_cost(args.get(0), len));
+                cost
+            }
+            FetchVar | SetVar => {
+                let size = args
+                    .get(0)
+                    .and_then(|name| name.match_atom())
+                    .and_then(|name| self.contract_analysis.persisted_variable_types.get(name))
+                    .map_or(0, |data_type| data_type.size() as u64);
+                let mut cost = self.args_cost(args.get(1..).unwrap_or_default());
+                let var_cost = if function == FetchVar {
+                    ExecutionCost {
+                        runtime: linear(size, 1, 468),
+                        read_count: 1,
+                        read_length: size,
+                        write_count: 0,
+                        write_length: 0,
+                    }
+                } else {
+                    ExecutionCost {
+                        runtime: linear(size, 5, 655),
+                        read_count: 0,
+                        read_length: 0,
+                        write_count: 1,
+                        write_length: size,
+                    }
+                };
+                add(&mut cost, &var_cost);
+                cost
+            }
+            FetchEntry | SetEntry | InsertEntry | DeleteEntry => {
+                let size = args
+                    .get(0)
+                    .and_then(|name| name.match_atom())
+                    .and_then(|name| self.contract_analysis.map_types.get(name))
+                    .map_or(0, |(key_type, value_type)| {
+                        (key_type.size() + value_type.size()) as u64
+                    });
+                let mut cost = self.args_cost(args.get(1..).unwrap_or_default());
+                let entry_cost = if function == FetchEntry {
+                    ExecutionCost {
+                        runtime: linear(size, 1, 1314),
+                        read_count: 1,
+                        read_length: size,
+                        write_count: 0,
+                        write_length: 0,
+                    }
+                } else {
+                    ExecutionCost {
+                        runtime: linear(size, 4, 1899),
+                        read_count: 1,
+                        read_length: 0,
+                        write_count: 1,
+                        write_length: size,
+                    }
+                };
+                add(&mut cost, &entry_cost);
+                cost
+            }
+            _ => {
+                let mut cost = self.args_cost(args);
+                add(&mut cost, &self.simple_native_cost(expr, function, args, n));
+                cost
+            }
+        }
+    }
+
+    // Cost of the evaluation of a native function, excluding the evaluation
+    // of its arguments
+    fn simple_native_cost(
+        &self,
+        expr: &'a SymbolicExpression,
+        function: NativeFunctions,
+        args: &'a [SymbolicExpression],
+        n: u64,
+    ) -> ExecutionCost {
+        use clarity::vm::functions::NativeFunctions::*;
+        let args_size: u64 = args.iter().map(|arg| self.size_of(arg)).sum();
+        match function {
+            Add | Subtract => runtime(linear(n, 11, 125)),
+            Multiply | Divide => runtime(linear(n, 13, 125)),
+            Modulo | Power | Sqrti | Log2 => runtime(167),
+            CmpLess | CmpLeq | CmpGreater | CmpGeq => runtime(170),
+            Equals => runtime(linear(args_size, 7, 128)),
+            And | Or => runtime(linear(n, 3, 120)),
+            Not | ToInt | ToUInt | BitwiseXOR => runtime(135),
+            Begin => runtime(151),
+            Asserts => runtime(128),
+            ConsError | ConsOkay | ConsSome => runtime(199),
+            UnwrapRet | Unwrap | UnwrapErrRet | UnwrapErr | TryRet => runtime(302),
+            IsOkay | IsNone | IsErr | IsSome | DefaultTo => runtime(268),
+            Len => runtime(429),
+            ElementAt => runtime(498),
+            IndexOf => runtime(linear(args_size, 1, 211)),
+            Append => runtime(linear(args_size, 73, 285)),
+            Concat => runtime(linear(args_size, 37, 220)),
+            AsMaxLen => runtime(475),
+            ListCons => runtime(linear(n, 14, 164)),
+            TupleCons => runtime(nlogn(n, 10, 1876)),
+            TupleGet => runtime(nlogn(self.size_of(expr), 4, 1736)),
+            TupleMerge => runtime(linear(args_size, 4, 408)),
+            Hash160 | Sha256 | Sha512 | Sha512Trunc256 | Keccak256 => {
+                runtime(linear(args_size, 1, 188))
+            }
+            Secp256k1Recover => runtime(8655),
+            Secp256k1Verify => runtime(8349),
+            Print => runtime(linear(args_size, 15, 1458)),
+            ContractCall => ExecutionCost {
+                runtime: 134,
+                read_count: 1,
+                read_length: 0,
+                write_count: 0,
+                write_length: 0,
+            },
+            AsContract => runtime(138),
+            ContractOf => runtime(13400),
+            PrincipalOf => runtime(984),
+            AtBlock | GetBlockInfo | GetBurnBlockInfo => ExecutionCost {
+                runtime: 6321,
+                read_count: 1,
+                read_length: 1,
+                write_count: 0,
+                write_length: 0,
+            },
+            StxTransfer | StxTransferMemo | StxBurn => ExecutionCost {
+                runtime: 4640,
+                read_count: 1,
+                read_length: 1,
+                write_count: 1,
+                write_length: 1,
+            },
+            GetStxBalance | StxGetAccount => ExecutionCost {
+                runtime: 4294,
+                read_count: 1,
+                read_length: 1,
+                write_count: 0,
+                write_length: 0,
+            },
+            TransferToken | BurnToken | MintToken => ExecutionCost {
+                runtime: 1479,
+                read_count: 2,
+                read_length: 1,
+                write_count: 2,
+                write_length: 1,
+            },
+            GetTokenBalance | GetTokenSupply => ExecutionCost {
+                runtime: 479,
+                read_count: 1,
+                read_length: 1,
+                write_count: 0,
+                write_length: 0,
+            },
+            TransferAsset | BurnAsset | MintAsset => ExecutionCost {
+                runtime: linear(args_size, 9, 795),
+                read_count: 1,
+                read_length: args_size,
+                write_count: 1,
+                write_length: args_size,
+            },
+            GetAssetOwner => ExecutionCost {
+                runtime: linear(args_size, 9, 795),
+                read_count: 1,
+                read_length: args_size,
+                write_count: 0,
+                write_length: 0,
+            },
+            ToConsensusBuff => runtime(linear(args_size, 1, 233)),
+            FromConsensusBuff => runtime(nlogn(args_size, 3, 185)),
+            _ => runtime(linear(args_size, 1, 200)),
+        }
+    }
+
+    fn size_of(&self, expr: &SymbolicExpression) -> u64 {
+        self.type_of(expr)
+            .map_or(0, |type_signature| type_signature.size() as u64)
+    }
+
+    // Maximum length of a sequence, from its type signature
+    fn max_len(&self, expr: &SymbolicExpression) -> u64 {
+        let len = match self.type_of(expr) {
+            Some(TypeSignature::SequenceType(sequence_type)) => match sequence_type {
+                SequenceSubtype::ListType(list_type) => list_type.get_max_len(),
+                SequenceSubtype::BufferType(len) => u32::from(len),
+                SequenceSubtype::StringType(StringSubtype::ASCII(len)) => u32::from(len),
+                SequenceSubtype::StringType(StringSubtype::UTF8(len)) => u32::from(len),
+            },
+            _ => 0,
+        };
+        len as u64
+    }
+
+    fn type_of(&self, expr: &SymbolicExpression) -> Option<&'a TypeSignature> {
+        self.contract_analysis
+            .type_map
+            .as_ref()
+            .and_then(|type_map| type_map.get_type(expr))
+    }
+}
+
+impl AnalysisPass for CostEstimator<'_> {
+    fn run_pass(
+        contract_analysis: &mut ContractAnalysis,
+        analysis_db: &mut AnalysisDatabase,
+        annotations: &Vec<Annotation>,
+        settings: &analysis::Settings,
+    ) -> AnalysisResult {
+        let estimator = CostEstimator::new(contract_analysis);
+        estimator.run(settings.cost_estimator, annotations)
+    }
+}
+
+fn match_function_definition(expr: &SymbolicExpression) -> Option<(&ClarityName, UserFunction)> {
+    let (define, args) = expr.match_list()?.split_first()?;
+    let is_public = match DefineFunctions::lookup_by_name(define.match_atom()?)? {
+        DefineFunctions::PublicFunction => true,
+        DefineFunctions::PrivateFunction | DefineFunctions::ReadOnlyFunction => false,
+        _ => return None,
+    };
+    let (name_expr, params) = args.get(0)?.match_list()?.split_first()?;
+    Some((
+        name_expr.match_atom()?,
+        UserFunction {
+            params,
+            body: args.get(1)?,
+            is_public,
+            name_expr,
+        },
+    ))
+}
+
+// The dimension of the cost using the largest share of the block limit, with
+// this share, as a percentage
+fn largest_share(cost: &ExecutionCost) -> (&'static str, u64) {
+    let limit = BLOCK_LIMIT_MAINNET;
+    let shares = [
+        ("runtime", cost.runtime, limit.runtime),
+        ("read count", cost.read_count, limit.read_count),
+        ("read length", cost.read_length, limit.read_length),
+        ("write count", cost.write_count, limit.write_count),
+        ("write length", cost.write_length, limit.write_length),
+    ];
+    shares
+        .iter()
+        .map(|(dimension, actual, limit)| {
+            (
+                *dimension,
+                actual.saturating_mul(100).checked_div(*limit).unwrap_or(0),
+            )
+        })
+        .fold(("runtime", 0), |largest, share| {
+            if share.1 > largest.1 {
+                share
+            } else {
+                largest
+            }
+        })
+}
+
+fn linear(n: u64, a: u64, b: u64) -> u64 {
+    a.saturating_mul(n).saturating_add(b)
+}
+
+fn nlogn(n: u64, a: u64, b: u64) -> u64 {
+    let log2 = 64 - n.leading_zeros() as u64;
+    a.saturating_mul(n).saturating_mul(log2).saturating_add(b)
+}
+
+fn runtime(runtime: u64) -> ExecutionCost {
+    ExecutionCost {
+        runtime,
+        read_count: 0,
+        read_length: 0,
+        write_count: 0,
+        write_length: 0,
+    }
+}
+
+fn add(cost: &mut ExecutionCost, other: &ExecutionCost) {
+    cost.runtime = cost.runtime.saturating_add(other.runtime);
+    cost.read_count = cost.read_count.saturating_add(other.read_count);
+    cost.read_length = cost.read_length.saturating_add(other.read_length);
+    cost.write_count = cost.write_count.saturating_add(other.write_count);
+    cost.write_length = cost.write_length.saturating_add(other.write_length);
+}
+
+fn multiply(cost: &mut ExecutionCost, times: u64) {
+    cost.runtime = cost.runtime.saturating_mul(times);
+    cost.read_count = cost.read_count.saturating_mul(times);
+    cost.read_length = cost.read_length.saturating_mul(times);
+    cost.write_count = cost.write_count.saturating_mul(times);
+    cost.write_length = cost.write_length.saturating_mul(times);
+}
+
+// Dimension-wise maximum of two costs
+fn max(first: &ExecutionCost, second: &ExecutionCost) -> ExecutionCost {
+    ExecutionCost {
+        runtime: first.runtime.max(second.runtime),
+        read_count: first.read_count.max(second.read_count),
+        read_length: first.read_length.max(second.read_length),
+        write_count: first.write_count.max(second.write_count),
+        write_length: first.write_length.max(second.write_length),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::Pass;
+    use crate::repl::session::Session;
+    use crate::repl::SessionSettings;
+
+    const CREDIT_CONTRACT: &str = "
+(define-map balances uint uint)
+(define-private (credit (id uint) (total uint))
+    (begin
+        (map-set balances id u1)
+        (+ total u1)
+    )
+)
+(define-public (credit-all (ids (list 20000 uint)))
+    (ok (fold credit ids u0))
+)
+(define-public (credit-one (id uint))
+    (ok (credit id u0))
+)
+";
+
+    fn costs_of(snippet: &str) -> BTreeMap<ClarityName, ExecutionCost> {
+        let mut session = Session::new(SessionSettings::default());
+        match session.formatted_interpretation(
+            snippet.to_string(),
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((_, result)) => match result.result {
+                clarity::vm::EvaluationResult::Contract(contract_result) => {
+                    estimate_costs(&contract_result.contract.analysis)
+                }
+                _ => panic!("Expected contract result"),
+            },
+            _ => panic!("Expected successful interpretation"),
+        }
+    }
+
+    #[test]
+    fn fold_over_bounded_list() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::CostEstimator];
+        let mut session = Session::new(settings);
+        match session.formatted_interpretation(
+            CREDIT_CONTRACT.to_string(),
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((output, _)) => {
+                // 20000 `map-set` exceed the block limits of 15000 reads and 15000 writes
+                assert_eq!(output.len(), 9);
+                assert_eq!(
+                    output[0],
+                    format!(
+                        "checker:9:17: {}: worst case read count of 'credit-all' is 133% of the block limit (threshold: 50%)",
+                        yellow!("warning")
+                    )
+                );
+                // The estimate of each public function is reported
+                assert!(output[3].starts_with("checker:9:17: "));
+                assert!(output[3].contains("worst case cost of 'credit-all': runtime"));
+                assert!(output[3].contains(
+                    "read count 20000, read length 0, write count 20000, write length 640000"
+                ));
+                assert!(output[6].starts_with("checker:12:17: "));
+                assert!(output[6].contains("worst case cost of 'credit-one': runtime"));
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
+
+    #[test]
+    fn threshold_setting() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::CostEstimator];
+        settings.repl_settings.analysis.cost_estimator = Settings { threshold: 150 };
+        let mut session = Session::new(settings.clone());
+        match session.formatted_interpretation(
+            CREDIT_CONTRACT.to_string(),
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((output, _)) => {
+                // Only the estimates are reported, 133% being below the threshold
+                assert_eq!(output.len(), 6);
+                assert!(!output.iter().any(|line| line.contains("block limit")));
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+
+        settings.repl_settings.analysis.cost_estimator = Settings { threshold: 100 };
+        let mut session = Session::new(settings);
+        match session.formatted_interpretation(
+            CREDIT_CONTRACT.to_string(),
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((output, _)) => {
+                assert_eq!(output.len(), 9);
+                assert_eq!(
+                    output[0],
+                    format!(
+                        "checker:9:17: {}: worst case read count of 'credit-all' is 133% of the block limit (threshold: 100%)",
+                        yellow!("warning")
+                    )
+                );
+            }
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
+
+    #[test]
+    fn map_set_cost() {
+        let settings = SessionSettings::default();
+        let mut session = Session::new(settings);
+        let snippet = "
+(define-map balances uint uint)
+(define-public (credit (id uint))
+    (ok (map-set balances id u1))
+)
+"
+        .to_string();
+        match session.formatted_interpretation(
+            snippet,
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((_, result)) => match result.result {
+                clarity::vm::EvaluationResult::Contract(contract_result) => {
+                    let costs = estimate_costs(&contract_result.contract.analysis);
+                    let cost = &costs[&ClarityName::from("credit")];
+                    assert_eq!(cost.write_count, 1);
+                    assert_eq!(cost.read_count, 1);
+                    assert!(cost.runtime > 0);
+                }
+                _ => panic!("Expected contract result"),
+            },
+            _ => panic!("Expected successful interpretation"),
+        };
+    }
+
+    #[test]
+    fn branches_take_maximum() {
+        let costs = costs_of(
+            "
+(define-data-var a uint u0)
+(define-data-var b uint u0)
+(define-public (set-if (flag bool))
+    (ok (if flag
+        (begin (var-set a u1) (var-set b u1))
+        (var-set a u2)
+    ))
+)
+(define-public (set-match (value (optional uint)))
+    (ok (match value
+        v (var-set a v)
+        (begin (var-set a u0) (var-set b u0) (var-set a u1))
+    ))
+)
+",
+        );
+        // Only the most expensive branch is accounted for
+        assert_eq!(costs[&ClarityName::from("set-if")].write_count, 2);
+        assert_eq!(costs[&ClarityName::from("set-if")].write_length, 32);
+        assert_eq!(costs[&ClarityName::from("set-match")].write_count, 3);
+        assert_eq!(costs[&ClarityName::from("set-match")].write_length, 48);
+    }
+
+    #[test]
+    fn map_operations_cost() {
+        let costs = costs_of(
+            "
+(define-map balances uint uint)
+(define-public (move (from uint) (to uint))
+    (let ((amount (default-to u0 (map-get? balances from))))
+        (map-delete balances from)
+        (ok (map-set balances to amount))
+    )
+)
+",
+        );
+        // Each entry is 32 bytes, a `uint` key and a `uint` value
+        let cost = &costs[&ClarityName::from("move")];
+        assert_eq!(cost.read_count, 3);
+        assert_eq!(cost.read_length, 32);
+        assert_eq!(cost.write_count, 2);
+        assert_eq!(cost.write_length, 64);
+    }
+
+    #[test]
+    fn iterations_over_lists() {
+        let costs = costs_of(
+            "
+(define-map seen uint bool)
+(define-private (mark (id uint))
+    (map-set seen id true)
+)
+(define-private (count-marked (id uint) (total uint))
+    (begin
+        (map-set seen id true)
+        (+ total u1)
+    )
+)
+(define-public (map-ids (ids (list 10 uint)))
+    (ok (map mark ids))
+)
+(define-public (fold-ids (ids (list 5 uint)))
+    (ok (fold count-marked ids u0))
+)
+(define-public (filter-ids (ids (list 7 uint)))
+    (ok (filter mark ids))
+)
+",
+        );
+        // The function is applied to each element of the longest possible list
+        assert_eq!(costs[&ClarityName::from("map-ids")].write_count, 10);
+        assert_eq!(costs[&ClarityName::from("fold-ids")].write_count, 5);
+        assert_eq!(costs[&ClarityName::from("filter-ids")].write_count, 7);
+    }
+}
@@ -0,0 +1,505 @@
+use crate::analysis::ast_visitor::{traverse, ASTVisitor, TypedVar};
+use clarity::vm::ast::ContractAST;
+use clarity::vm::representations::{SymbolicExpression, SymbolicExpressionType, TraitDefinition};
+use clarity::vm::types::{QualifiedContractIdentifier, TraitIdentifier};
+use clarity::vm::ClarityName;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Contract,
+    Function,
+    Trait,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    /// Call of a function defined in the same contract
+    Call,
+    /// `contract-call?` on a contract known statically
+    ContractCall,
+    /// `contract-call?` on a trait reference, dispatched at runtime
+    DynamicContractCall,
+    UseTrait,
+    ImplTrait,
+}
+
+impl EdgeKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            EdgeKind::Call => "call",
+            EdgeKind::ContractCall => "contract-call?",
+            EdgeKind::DynamicContractCall => "dynamic contract-call?",
+            EdgeKind::UseTrait => "use-trait",
+            EdgeKind::ImplTrait => "impl-trait",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Node {
+    pub id: String,
+    pub kind: NodeKind,
+    pub label: String,
+    /// Contract defining the function or the trait
+    pub contract: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub kind: EdgeKind,
+}
+
+/// Graph of the dependencies between contracts, or of the calls between
+/// functions.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+/// Build the graph of the dependencies between the contracts (`contract-call?`,
+/// `use-trait` and `impl-trait`), or, with `functions`, the graph of the calls
+/// between their functions. The calls on trait references are linked to the
+/// trait, since the contract called is only known at runtime.
+pub fn build_graph(
+    contract_asts: &HashMap<QualifiedContractIdentifier, ContractAST>,
+    functions: bool,
+) -> Graph {
+    let mut builder = GraphBuilder {
+        functions,
+        nodes: BTreeMap::new(),
+        edges: BTreeSet::new(),
+        contract_id: None,
+        function: None,
+        params: None,
+    };
+    for (contract_id, ast) in contract_asts.iter() {
+        builder.contract_id = Some(contract_id);
+        if !functions {
+            builder.add_contract_node(contract_id);
+        }
+        traverse(&mut builder, &ast.expressions);
+    }
+    Graph {
+        nodes: builder.nodes.into_values().collect(),
+        edges: builder.edges.into_iter().collect(),
+    }
+}
+
+impl Graph {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("unable to serialize graph")
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut lines = vec!["digraph {".to_string(), "    rankdir=LR;".to_string()];
+        for (contract, nodes) in self.nodes_by_contract() {
+            let indent = match contract {
+                Some(contract) => {
+                    lines.push(format!("    subgraph \"cluster_{}\" {{", contract));
+                    lines.push(format!("        label=\"{}\";", contract_name(contract)));
+                    "        "
+                }
+                None => "    ",
+            };
+            for node in nodes {
+                let shape = match node.kind {
+                    NodeKind::Contract => "box",
+                    NodeKind::Function => "ellipse",
+                    NodeKind::Trait => "hexagon",
+                };
+                lines.push(format!(
+                    "{}\"{}\" [label=\"{}\", shape={}];",
+                    indent, node.id, node.label, shape
+                ));
+            }
+            if contract.is_some() {
+                lines.push("    }".to_string());
+            }
+        }
+        for edge in self.edges.iter() {
+            lines.push(format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"];",
+                edge.from,
+                edge.to,
+                edge.kind.label()
+            ));
+        }
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+
+    pub fn to_mermaid(&self) -> String {
+        // Mermaid identifiers can not contain the characters of the contract
+        // identifiers, so the nodes are numbered.
+        let ids: HashMap<&str, String> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.id.as_str(), format!("n{}", i)))
+            .collect();
+        let mut lines = vec!["flowchart LR".to_string()];
+        for (i, (contract, nodes)) in self.nodes_by_contract().into_iter().enumerate() {
+            let indent = match contract {
+                Some(contract) => {
+                    lines.push(format!(
+                        "    subgraph c{} [\"{}\"]",
+                        i,
+                        contract_name(contract)
+                    ));
+                    "        "
+                }
+                None => "    ",
+            };
+            for node in nodes {
+                let id = &ids[node.id.as_str()];
+                lines.push(match node.kind {
+                    NodeKind::Contract => format!("{}{}[\"{}\"]", indent, id, node.label),
+                    NodeKind::Function => format!("{}{}(\"{}\")", indent, id, node.label),
+                    NodeKind::Trait => format!("{}{}{{{{\"{}\"}}}}", indent, id, node.label),
+                });
+            }
+            if contract.is_some() {
+                lines.push("    end".to_string());
+            }
+        }
+        for edge in self.edges.iter() {
+            lines.push(format!(
+                "    {} -->|{}| {}",
+                ids[edge.from.as_str()],
+                edge.kind.label(),
+                ids[edge.to.as_str()]
+            ));
+        }
+        lines.join("\n")
+    }
+
+    // The function nodes are grouped by contract, the other nodes are not
+    fn nodes_by_contract(&self) -> BTreeMap<Option<&str>, Vec<&Node>> {
+        let mut groups: BTreeMap<Option<&str>, Vec<&Node>> = BTreeMap::new();
+        for node in self.nodes.iter() {
+            let contract = match node.kind {
+                NodeKind::Function => node.contract.as_deref(),
+                _ => None,
+            };
+            groups.entry(contract).or_default().push(node);
+        }
+        groups
+    }
+}
+
+fn contract_name(contract_id: &str) -> &str {
+    contract_id.rsplit('.').next().unwrap_or(contract_id)
+}
+
+struct GraphBuilder<'a> {
+    functions: bool,
+    nodes: BTreeMap<String, Node>,
+    edges: BTreeSet<Edge>,
+    contract_id: Option<&'a QualifiedContractIdentifier>,
+    // Function being traversed, and its parameters
+    function: Option<&'a ClarityName>,
+    params: Option<Vec<TypedVar<'a>>>,
+}
+
+impl<'a> GraphBuilder<'a> {
+    fn add_contract_node(&mut self, contract_id: &QualifiedContractIdentifier) -> String {
+        let id = contract_id.to_string();
+        self.nodes.entry(id.clone()).or_insert_with(|| Node {
+            id: id.clone(),
+            kind: NodeKind::Contract,
+            label: contract_id.name.to_string(),
+            contract: None,
+        });
+        id
+    }
+
+    fn add_function_node(
+        &mut self,
+        contract_id: &QualifiedContractIdentifier,
+        name: &ClarityName,
+    ) -> String {
+        let id = format!("{}::{}", contract_id, name);
+        self.nodes.entry(id.clone()).or_insert_with(|| Node {
+            id: id.clone(),
+            kind: NodeKind::Function,
+            label: name.to_string(),
+            contract: Some(contract_id.to_string()),
+        });
+        id
+    }
+
+    fn add_trait_node(&mut self, trait_id: &TraitIdentifier) -> String {
+        let id = format!("{}.{}", trait_id.contract_identifier, trait_id.name);
+        self.nodes.entry(id.clone()).or_insert_with(|| Node {
+            id: id.clone(),
+            kind: NodeKind::Trait,
+            label: format!("{}.{}", trait_id.contract_identifier.name, trait_id.name),
+            contract: Some(trait_id.contract_identifier.to_string()),
+        });
+        id
+    }
+
+    // Add an edge from the current contract, or from the current function
+    // when building the graph of the functions.
+    fn add_edge(&mut self, to: String, kind: EdgeKind) {
+        let contract_id = match self.contract_id {
+            Some(contract_id) => contract_id,
+            None => return,
+        };
+        let from = if self.functions {
+            match self.function {
+                Some(function) => self.add_function_node(contract_id, function),
+                None => return,
+            }
+        } else {
+            contract_id.to_string()
+        };
+        self.edges.insert(Edge { from, to, kind });
+    }
+
+    fn get_param_trait(&self, name: &ClarityName) -> Option<&'a TraitIdentifier> {
+        let param = self
+            .params
+            .as_ref()?
+            .iter()
+            .find(|param| param.name == name)?;
+        match &param.type_expr.expr {
+            SymbolicExpressionType::TraitReference(_, TraitDefinition::Defined(identifier))
+            | SymbolicExpressionType::TraitReference(_, TraitDefinition::Imported(identifier)) => {
+                Some(identifier)
+            }
+            _ => None,
+        }
+    }
+
+    fn traverse_function(
+        &mut self,
+        name: &'a ClarityName,
+        parameters: Option<Vec<TypedVar<'a>>>,
+        body: &'a SymbolicExpression,
+    ) -> bool {
+        if self.functions {
+            if let Some(contract_id) = self.contract_id {
+                self.add_function_node(contract_id, name);
+            }
+        }
+        self.function = Some(name);
+        self.params = parameters;
+        let res = self.traverse_expr(body);
+        self.function = None;
+        self.params = None;
+        res
+    }
+}
+
+impl<'a> ASTVisitor<'a> for GraphBuilder<'a> {
+    fn traverse_define_private(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        parameters: Option<Vec<TypedVar<'a>>>,
+        body: &'a SymbolicExpression,
+    ) -> bool {
+        self.traverse_function(name, parameters, body)
+    }
+
+    fn traverse_define_read_only(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        parameters: Option<Vec<TypedVar<'a>>>,
+        body: &'a SymbolicExpression,
+    ) -> bool {
+        self.traverse_function(name, parameters, body)
+    }
+
+    fn traverse_define_public(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        parameters: Option<Vec<TypedVar<'a>>>,
+        body: &'a SymbolicExpression,
+    ) -> bool {
+        self.traverse_function(name, parameters, body)
+    }
+
+    fn visit_call_user_defined(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        args: &'a [SymbolicExpression],
+    ) -> bool {
+        if self.functions {
+            if let Some(contract_id) = self.contract_id {
+                let to = self.add_function_node(contract_id, name);
+                self.add_edge(to, EdgeKind::Call);
+            }
+        }
+        true
+    }
+
+    fn visit_static_contract_call(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        contract_identifier: &'a QualifiedContractIdentifier,
+        function_name: &'a ClarityName,
+        args: &'a [SymbolicExpression],
+    ) -> bool {
+        let to = if self.functions {
+            self.add_function_node(contract_identifier, function_name)
+        } else {
+            self.add_contract_node(contract_identifier)
+        };
+        self.add_edge(to, EdgeKind::ContractCall);
+        true
+    }
+
+    fn visit_dynamic_contract_call(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        trait_ref: &'a SymbolicExpression,
+        function_name: &'a ClarityName,
+        args: &'a [SymbolicExpression],
+    ) -> bool {
+        let trait_id = trait_ref
+            .match_atom()
+            .and_then(|name| self.get_param_trait(name));
+        if let Some(trait_id) = trait_id {
+            let to = self.add_trait_node(trait_id);
+            self.add_edge(to, EdgeKind::DynamicContractCall);
+        }
+        true
+    }
+
+    fn visit_use_trait(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        trait_identifier: &TraitIdentifier,
+    ) -> bool {
+        if !self.functions {
+            let to = self.add_trait_node(trait_identifier);
+            self.add_edge(to, EdgeKind::UseTrait);
+        }
+        true
+    }
+
+    fn visit_impl_trait(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        trait_identifier: &TraitIdentifier,
+    ) -> bool {
+        if !self.functions {
+            let to = self.add_trait_node(trait_identifier);
+            self.add_edge(to, EdgeKind::ImplTrait);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repl::session::Session;
+    use crate::repl::SessionSettings;
+
+    fn build_asts(contracts: &[(&str, &str)]) -> HashMap<QualifiedContractIdentifier, ContractAST> {
+        let session = Session::new(SessionSettings::default());
+        let mut asts = HashMap::new();
+        for (name, snippet) in contracts {
+            let contract_id = QualifiedContractIdentifier::parse(&format!(
+                "S1G2081040G2081040G2081040G208105NK8PE5.{}",
+                name
+            ))
+            .unwrap();
+            let (ast, _, success) = session
+                .interpreter
+                .build_ast(contract_id.clone(), snippet.to_string());
+            assert!(success);
+            asts.insert(contract_id, ast);
+        }
+        asts
+    }
+
+    fn contracts() -> Vec<(&'static str, &'static str)> {
+        vec![
+            (
+                "token-trait",
+                "(define-trait token-trait ((transfer (uint principal) (response bool uint))))",
+            ),
+            (
+                "counter",
+                "(define-data-var count uint u0)
+(define-public (increment) (ok (var-set count (+ (var-get count) u1))))",
+            ),
+            (
+                "router",
+                "(use-trait token-trait .token-trait.token-trait)
+(define-private (bump) (contract-call? .counter increment))
+(define-public (route (token <token-trait>) (amount uint))
+    (begin
+        (try! (bump))
+        (contract-call? token transfer amount tx-sender)
+    )
+)",
+            ),
+        ]
+    }
+
+    #[test]
+    fn contracts_graph() {
+        let graph = build_graph(&build_asts(&contracts()), false);
+        let edges: Vec<(&str, &str, EdgeKind)> = graph
+            .edges
+            .iter()
+            .map(|edge| (contract_name(&edge.from), edge.to.as_str(), edge.kind))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                (
+                    "router",
+                    "S1G2081040G2081040G2081040G208105NK8PE5.counter",
+                    EdgeKind::ContractCall
+                ),
+                (
+                    "router",
+                    "S1G2081040G2081040G2081040G208105NK8PE5.token-trait.token-trait",
+                    EdgeKind::DynamicContractCall
+                ),
+                (
+                    "router",
+                    "S1G2081040G2081040G2081040G208105NK8PE5.token-trait.token-trait",
+                    EdgeKind::UseTrait
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn functions_graph_to_mermaid() {
+        let asts = build_asts(&contracts()[1..]);
+        let graph = build_graph(&asts, true);
+        assert_eq!(
+            graph.to_mermaid(),
+            "flowchart LR
+    n3{{\"token-trait.token-trait\"}}
+    subgraph c1 [\"counter\"]
+        n0(\"increment\")
+    end
+    subgraph c2 [\"router\"]
+        n1(\"bump\")
+        n2(\"route\")
+    end
+    n1 -->|contract-call?| n0
+    n2 -->|call| n1
+    n2 -->|dynamic contract-call?| n3"
+        );
+    }
+}
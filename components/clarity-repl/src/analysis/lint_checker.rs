@@ -0,0 +1,391 @@
+use crate::analysis::annotation::{Annotation, AnnotationKind, WarningKind};
+use crate::analysis::{self, AnalysisPass, AnalysisResult, Severity};
+use clarity::vm::analysis::analysis_db::AnalysisDatabase;
+use clarity::vm::analysis::types::ContractAnalysis;
+use clarity::vm::diagnostic::{Diagnostic, Level};
+use clarity::vm::representations::Span;
+use clarity::vm::representations::SymbolicExpressionType::*;
+use clarity::vm::types::{PrincipalData, Value};
+use clarity::vm::SymbolicExpression;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+/// A project-specific rule, reporting each expression matching its pattern.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    pub name: String,
+    pub pattern: Pattern,
+    pub message: String,
+    #[serde(default = "default_severity")]
+    pub severity: Severity,
+    /// Contracts in which the rule does not apply
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+fn default_severity() -> Severity {
+    Severity::Warn
+}
+
+/// An s-expression matched against the AST of the contracts, in which `_`
+/// matches any expression and `...` any number of expressions of a list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Pattern {
+    Any,
+    Rest,
+    Atom(String),
+    List(Vec<Pattern>),
+}
+
+impl Pattern {
+    pub fn matches(&self, expr: &SymbolicExpression) -> bool {
+        match (self, &expr.expr) {
+            (Pattern::Any, _) | (Pattern::Rest, _) => true,
+            (Pattern::Atom(name), Atom(atom)) => atom.as_str() == name,
+            // `.contract` matches a contract of any deployer
+            (
+                Pattern::Atom(name),
+                AtomValue(Value::Principal(PrincipalData::Contract(contract)))
+                | LiteralValue(Value::Principal(PrincipalData::Contract(contract))),
+            ) if name.starts_with('.') => contract.name.as_str() == &name[1..],
+            (Pattern::Atom(name), AtomValue(value) | LiteralValue(value)) => {
+                value.to_string() == *name
+            }
+            (Pattern::List(patterns), List(exprs)) => matches_list(patterns, exprs),
+            _ => false,
+        }
+    }
+}
+
+fn matches_list(patterns: &[Pattern], exprs: &[SymbolicExpression]) -> bool {
+    match patterns.split_first() {
+        None => exprs.is_empty(),
+        Some((Pattern::Rest, patterns)) => {
+            (0..=exprs.len()).any(|skipped| matches_list(patterns, &exprs[skipped..]))
+        }
+        Some((pattern, patterns)) => match exprs.split_first() {
+            Some((expr, exprs)) => pattern.matches(expr) && matches_list(patterns, exprs),
+            None => false,
+        },
+    }
+}
+
+impl std::str::FromStr for Pattern {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s);
+        let mut tokens = tokens.iter();
+        let pattern = parse_pattern(&mut tokens)?;
+        match tokens.next() {
+            Some(token) => Err(format!(
+                "unexpected '{}' after the end of the pattern",
+                token
+            )),
+            None => Ok(pattern),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' | ')' => tokens.push(c.to_string()),
+            c if c.is_whitespace() => (),
+            '"' => {
+                let mut token = c.to_string();
+                for c in chars.by_ref() {
+                    token.push(c);
+                    if c == '"' {
+                        break;
+                    }
+                }
+                tokens.push(token);
+            }
+            c => {
+                let mut token = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                tokens.push(token);
+            }
+        }
+    }
+    tokens
+}
+
+fn parse_pattern(tokens: &mut std::slice::Iter<String>) -> Result<Pattern, String> {
+    match tokens.next().map(|token| token.as_str()) {
+        None => Err("unexpected end of the pattern".to_string()),
+        Some("(") => {
+            let mut patterns = vec![];
+            loop {
+                match tokens.as_slice().first().map(|token| token.as_str()) {
+                    Some(")") => {
+                        tokens.next();
+                        return Ok(Pattern::List(patterns));
+                    }
+                    Some(_) => patterns.push(parse_pattern(tokens)?),
+                    None => return Err("missing ')' in the pattern".to_string()),
+                }
+            }
+        }
+        Some(")") => Err("unexpected ')' in the pattern".to_string()),
+        Some("_") => Ok(Pattern::Any),
+        Some("...") => Ok(Pattern::Rest),
+        Some(atom) => Ok(Pattern::Atom(atom.to_string())),
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(pattern: Pattern) -> Self {
+        pattern.to_string()
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pattern::Any => write!(f, "_"),
+            Pattern::Rest => write!(f, "..."),
+            Pattern::Atom(name) => write!(f, "{}", name),
+            Pattern::List(patterns) => {
+                let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+                write!(f, "({})", patterns.join(" "))
+            }
+        }
+    }
+}
+
+pub struct LintChecker<'a> {
+    rules: Vec<&'a Rule>,
+    annotations: &'a Vec<Annotation>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> LintChecker<'a> {
+    fn new(
+        contract_analysis: &ContractAnalysis,
+        annotations: &'a Vec<Annotation>,
+        rules: &'a [Rule],
+    ) -> LintChecker<'a> {
+        let contract_name = contract_analysis.contract_identifier.name.as_str();
+        Self {
+            rules: rules
+                .iter()
+                .filter(|rule| rule.severity != Severity::Off)
+                .filter(|rule| !rule.exclude.iter().any(|name| name == contract_name))
+                .collect(),
+            annotations,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn run(mut self, expressions: &[SymbolicExpression]) -> AnalysisResult {
+        if !self.rules.is_empty() {
+            self.check(expressions, false);
+        }
+        Ok(self.diagnostics)
+    }
+
+    fn check(&mut self, expressions: &[SymbolicExpression], allowed: bool) {
+        for expr in expressions {
+            let allowed = allowed || self.is_allowed(&expr.span);
+            if !allowed {
+                for rule in self.rules.iter() {
+                    if rule.pattern.matches(expr) {
+                        self.diagnostics.push(Diagnostic {
+                            level: match rule.severity {
+                                Severity::Error => Level::Error,
+                                _ => Level::Warning,
+                            },
+                            message: format!("lint({}): {}", rule.name, rule.message),
+                            spans: vec![expr.span.clone()],
+                            suggestion: None,
+                        });
+                    }
+                }
+            }
+            if let Some(list) = expr.match_list() {
+                self.check(list, allowed);
+            }
+        }
+    }
+
+    // Check if the expression at `span` is annotated with `allow(lint)`
+    fn is_allowed(&self, span: &Span) -> bool {
+        for annotation in self.annotations.iter() {
+            if annotation.span.start_line == (span.start_line - 1) {
+                return matches!(annotation.kind, AnnotationKind::Allow(WarningKind::Lint));
+            } else if annotation.span.start_line >= span.start_line {
+                // The annotations are ordered by span, so if we have passed
+                // the target line, return.
+                return false;
+            }
+        }
+        false
+    }
+}
+
+impl AnalysisPass for LintChecker<'_> {
+    fn run_pass(
+        contract_analysis: &mut ContractAnalysis,
+        analysis_db: &mut AnalysisDatabase,
+        annotations: &Vec<Annotation>,
+        settings: &analysis::Settings,
+    ) -> AnalysisResult {
+        let checker = LintChecker::new(contract_analysis, annotations, &settings.lints);
+        checker.run(&contract_analysis.expressions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repl::session::Session;
+    use crate::repl::SessionSettings;
+
+    fn burn_rule() -> Rule {
+        Rule {
+            name: "no-burn".to_string(),
+            pattern: "(stx-burn? _ ...)".parse().unwrap(),
+            message: "only the treasury can burn STX".to_string(),
+            severity: Severity::Error,
+            exclude: vec!["treasury".to_string()],
+        }
+    }
+
+    fn interpret(snippet: &str, name: &str, rules: Vec<Rule>) -> Result<Vec<String>, Vec<String>> {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.add_lint_rules(rules);
+        let mut session = Session::new(settings);
+        session
+            .formatted_interpretation(
+                snippet.to_string(),
+                Some(name.to_string()),
+                false,
+                None,
+                None,
+            )
+            .map(|(output, _)| output)
+    }
+
+    #[test]
+    fn parse_pattern() {
+        let pattern: Pattern = "(contract-call? .token  transfer _ ...)".parse().unwrap();
+        assert_eq!(
+            pattern,
+            Pattern::List(vec![
+                Pattern::Atom("contract-call?".to_string()),
+                Pattern::Atom(".token".to_string()),
+                Pattern::Atom("transfer".to_string()),
+                Pattern::Any,
+                Pattern::Rest,
+            ])
+        );
+        assert_eq!(
+            pattern.to_string(),
+            "(contract-call? .token transfer _ ...)"
+        );
+        assert!("(stx-burn? _".parse::<Pattern>().is_err());
+        assert!("(stx-burn?) _".parse::<Pattern>().is_err());
+    }
+
+    #[test]
+    fn forbidden_call() {
+        let snippet = "
+(define-public (burn (amount uint))
+    (stx-burn? amount tx-sender)
+)
+";
+        match interpret(snippet, "checker", vec![burn_rule()]) {
+            Err(output) => {
+                assert_eq!(
+                    output[0],
+                    format!(
+                        "checker:3:5: {}: lint(no-burn): only the treasury can burn STX",
+                        red!("error")
+                    )
+                );
+                assert_eq!(output[1], "    (stx-burn? amount tx-sender)");
+                assert_eq!(output[2], "    ^~~~~~~~~~~~~~~~~~~~~~~~~~~~");
+            }
+            _ => panic!("Expected failed interpretation"),
+        }
+    }
+
+    #[test]
+    fn excluded_contract() {
+        let snippet = "
+(define-public (burn (amount uint))
+    (stx-burn? amount tx-sender)
+)
+";
+        match interpret(snippet, "treasury", vec![burn_rule()]) {
+            Ok(output) => assert!(output.is_empty()),
+            _ => panic!("Expected successful interpretation"),
+        }
+    }
+
+    #[test]
+    fn literal_values() {
+        let rule = Rule {
+            name: "no-magic-fee".to_string(),
+            pattern: "(* _ u3)".parse().unwrap(),
+            message: "use the FEE constant".to_string(),
+            severity: Severity::Warn,
+            exclude: vec![],
+        };
+        let snippet = "
+(define-read-only (get-fee (amount uint))
+    (* amount u3)
+)
+(define-read-only (get-double (amount uint))
+    (* amount u2)
+)
+";
+        match interpret(snippet, "checker", vec![rule]) {
+            Ok(output) => {
+                assert_eq!(output.len(), 3);
+                assert_eq!(
+                    output[0],
+                    format!(
+                        "checker:3:5: {}: lint(no-magic-fee): use the FEE constant",
+                        yellow!("warning")
+                    )
+                );
+            }
+            _ => panic!("Expected successful interpretation"),
+        }
+    }
+
+    #[test]
+    fn allow_lint() {
+        let snippet = "
+;; #[allow(lint)]
+(define-public (burn (amount uint))
+    (stx-burn? amount tx-sender)
+)
+";
+        match interpret(snippet, "checker", vec![burn_rule()]) {
+            Ok(output) => assert!(output.is_empty()),
+            _ => panic!("Expected successful interpretation"),
+        }
+    }
+}
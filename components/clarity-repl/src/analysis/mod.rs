@@ -3,33 +3,71 @@ pub mod ast_dependency_detector;
 pub mod ast_visitor;
 pub mod call_checker;
 pub mod check_checker;
+pub mod cost_estimator;
 pub mod coverage;
+pub mod graph;
+pub mod lint_checker;
+pub mod response_checker;
+pub mod sip_checker;
+pub mod unused_checker;
 
 use serde::de::Deserialize;
 use serde::Serialize;
 
-use crate::analysis::annotation::Annotation;
+use crate::analysis::annotation::{Annotation, AnnotationKind, WarningKind};
 use clarity::vm::analysis::analysis_db::AnalysisDatabase;
 use clarity::vm::analysis::types::ContractAnalysis;
-use clarity::vm::diagnostic::Diagnostic;
+use clarity::vm::diagnostic::{Diagnostic, Level};
+use clarity::vm::representations::Span;
+use clarity::vm::SymbolicExpression;
+use std::collections::BTreeMap;
 
 use self::ast_dependency_detector::ASTDependencyDetector;
 use self::call_checker::CallChecker;
-use self::check_checker::CheckChecker;
+use self::check_checker::{ArithmeticChecker, AsContractChecker, CheckChecker};
+use self::cost_estimator::CostEstimator;
+use self::lint_checker::LintChecker;
+use self::response_checker::ResponseChecker;
+use self::sip_checker::SipChecker;
+use self::unused_checker::UnusedChecker;
 
 pub type AnalysisResult = Result<Vec<Diagnostic>, Vec<Diagnostic>>;
 
+/// Diagnostic reported by an analysis pass, along with the name of the pass
+/// (`call_checker`, which always runs, `check_checker`, `unused_checker`, ...).
+pub type AnalysisDiagnostic = (&'static str, Diagnostic);
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Pass {
     All,
     CheckChecker,
+    UnusedChecker,
+    AsContractChecker,
+    CostEstimator,
+    SipChecker,
+    ResponseChecker,
+    ArithmeticChecker,
+}
+
+/// Severity of the diagnostics reported for a kind of warning, set with
+/// the `rules` of the settings or with `warn(...)` and `deny(...)` annotations.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Off,
+    Warn,
+    Error,
 }
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct Settings {
     passes: Vec<Pass>,
+    rules: BTreeMap<WarningKind, Severity>,
     check_checker: check_checker::Settings,
+    cost_estimator: cost_estimator::Settings,
+    sip_checker: sip_checker::Settings,
+    lints: Vec<lint_checker::Rule>,
 }
 
 impl Settings {
@@ -48,6 +86,16 @@ impl Settings {
             };
         }
     }
+
+    pub fn set_rule(&mut self, kind: WarningKind, severity: Severity) {
+        self.rules.insert(kind, severity);
+    }
+
+    /// Add custom lint rules, such as the ones loaded from the `settings/lints`
+    /// directory of a project.
+    pub fn add_lint_rules(&mut self, rules: Vec<lint_checker::Rule>) {
+        self.lints.extend(rules);
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -62,11 +110,43 @@ pub enum OneOrList<T> {
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct SettingsFile {
     passes: Option<OneOrList<Pass>>,
+    #[serde(default, deserialize_with = "deserialize_rules")]
+    rules: Option<BTreeMap<WarningKind, Severity>>,
     check_checker: Option<check_checker::SettingsFile>,
+    cost_estimator: Option<cost_estimator::SettingsFile>,
+    sip_checker: Option<sip_checker::SettingsFile>,
+    lints: Option<Vec<lint_checker::Rule>>,
+}
+
+// No diagnostic is reported as `unchecked_params`, which can only be allowed
+// with an annotation, so a rule setting its severity is rejected
+fn deserialize_rules<'de, D>(
+    deserializer: D,
+) -> Result<Option<BTreeMap<WarningKind, Severity>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let rules = Option::<BTreeMap<WarningKind, Severity>>::deserialize(deserializer)?;
+    if let Some(ref rules) = rules {
+        if rules.contains_key(&WarningKind::UncheckedParams) {
+            return Err(serde::de::Error::custom(
+                "the severity of 'unchecked_params' can not be set, it can only be allowed",
+            ));
+        }
+    }
+    Ok(rules)
 }
 
 // Each new pass should be included in this list
-static ALL_PASSES: [Pass; 1] = [Pass::CheckChecker];
+static ALL_PASSES: [Pass; 7] = [
+    Pass::CheckChecker,
+    Pass::UnusedChecker,
+    Pass::AsContractChecker,
+    Pass::CostEstimator,
+    Pass::SipChecker,
+    Pass::ResponseChecker,
+    Pass::ArithmeticChecker,
+];
 
 impl From<SettingsFile> for Settings {
     fn from(from_file: SettingsFile) -> Self {
@@ -94,12 +174,141 @@ impl From<SettingsFile> for Settings {
         } else {
             check_checker::Settings::default()
         };
+        let cost_estimator_settings = match from_file.cost_estimator {
+            Some(cost_estimator_settings) => {
+                cost_estimator::Settings::from(cost_estimator_settings)
+            }
+            None => cost_estimator::Settings::default(),
+        };
+        let sip_checker_settings = match from_file.sip_checker {
+            Some(sip_checker_settings) => sip_checker::Settings::from(sip_checker_settings),
+            None => sip_checker::Settings::default(),
+        };
 
         Self {
             passes,
+            rules: from_file.rules.unwrap_or_default(),
             check_checker: checker_settings,
+            cost_estimator: cost_estimator_settings,
+            sip_checker: sip_checker_settings,
+            lints: from_file.lints.unwrap_or_default(),
+        }
+    }
+}
+
+// Kind of warning reported by an analysis, which can be configured with rules.
+// `unchecked_params` is not one of them, see `deserialize_rules`.
+fn get_warning_kind(analysis_name: &str) -> Option<WarningKind> {
+    match analysis_name {
+        "check_checker" | "as_contract_checker" => Some(WarningKind::UncheckedData),
+        "unused_checker" => Some(WarningKind::Unused),
+        "cost_estimator" => Some(WarningKind::Cost),
+        "sip_checker" => Some(WarningKind::Sip),
+        "response_checker" => Some(WarningKind::UncheckedResponse),
+        "arithmetic_checker" => Some(WarningKind::Arithmetic),
+        "lint_checker" => Some(WarningKind::Lint),
+        _ => None,
+    }
+}
+
+/// Range of code covered by a `warn(...)` or `deny(...)` annotation
+struct AnnotationScope {
+    kind: WarningKind,
+    severity: Severity,
+    span: Span,
+}
+
+impl AnnotationScope {
+    fn contains(&self, span: &Span) -> bool {
+        (self.span.start_line, self.span.start_column) <= (span.start_line, span.start_column)
+            && (span.start_line, span.start_column) <= (self.span.end_line, self.span.end_column)
+    }
+}
+
+// An annotation applies to the expression which follows it. When this
+// expression is a function definition, it covers the whole function.
+fn get_annotation_scopes(
+    expressions: &[SymbolicExpression],
+    annotations: &[Annotation],
+) -> Vec<AnnotationScope> {
+    let mut scopes = vec![];
+    for annotation in annotations.iter() {
+        let (kind, severity) = match annotation.kind {
+            AnnotationKind::Warn(kind) => (kind, Severity::Warn),
+            AnnotationKind::Deny(kind) => (kind, Severity::Error),
+            _ => continue,
+        };
+        if let Some(expr) = find_next_expression(expressions, annotation.span.start_line) {
+            scopes.push(AnnotationScope {
+                kind,
+                severity,
+                span: expr.span.clone(),
+            });
         }
     }
+    scopes
+}
+
+// First expression starting after `line`, in the order of the source
+fn find_next_expression(
+    expressions: &[SymbolicExpression],
+    line: u32,
+) -> Option<&SymbolicExpression> {
+    for expr in expressions.iter() {
+        if expr.span.start_line > line {
+            return Some(expr);
+        }
+        if expr.span.end_line >= line {
+            if let Some(next) = expr
+                .match_list()
+                .and_then(|list| find_next_expression(list, line))
+            {
+                return Some(next);
+            }
+        }
+    }
+    None
+}
+
+/// Set the level of the diagnostics reported by the passes according to the
+/// rules of the settings, overridden by the innermost `warn(...)` or
+/// `deny(...)` annotation covering them. The diagnostics of the rules which
+/// are turned off are removed, along with their notes.
+fn apply_rules(
+    expressions: &[SymbolicExpression],
+    annotations: &[Annotation],
+    rules: &BTreeMap<WarningKind, Severity>,
+    diagnostics: Vec<AnalysisDiagnostic>,
+) -> Vec<AnalysisDiagnostic> {
+    let scopes = get_annotation_scopes(expressions, annotations);
+    let mut result = Vec::with_capacity(diagnostics.len());
+    let mut severity = None;
+    let mut last_name = None;
+    for (name, mut diagnostic) in diagnostics.into_iter() {
+        let is_note = matches!(diagnostic.level, Level::Note);
+        // A note follows the diagnostic it explains, unless a pass reports it
+        // on its own, as the estimates of the cost-estimator
+        if !is_note || last_name != Some(name) {
+            last_name = Some(name);
+            severity = get_warning_kind(name).and_then(|kind| {
+                let span = diagnostic.spans.first();
+                scopes
+                    .iter()
+                    .filter(|scope| scope.kind == kind && span.map_or(false, |s| scope.contains(s)))
+                    .max_by(|a, b| a.span.cmp(&b.span))
+                    .map(|scope| scope.severity)
+                    .or_else(|| rules.get(&kind).copied())
+            });
+        }
+        match severity {
+            Some(Severity::Off) => continue,
+            Some(Severity::Warn) if !is_note => diagnostic.level = Level::Warning,
+            Some(Severity::Error) if !is_note => diagnostic.level = Level::Error,
+            _ => (),
+        }
+        result.push((name, diagnostic));
+    }
+    result
 }
 
 pub trait AnalysisPass {
@@ -116,34 +325,215 @@ pub fn run_analysis(
     analysis_db: &mut AnalysisDatabase,
     annotations: &Vec<Annotation>,
     settings: &Settings,
-) -> AnalysisResult {
-    let mut errors: Vec<Diagnostic> = Vec::new();
-    let mut passes: Vec<
+) -> Result<Vec<AnalysisDiagnostic>, Vec<AnalysisDiagnostic>> {
+    let mut errors: Vec<AnalysisDiagnostic> = Vec::new();
+    let mut passes: Vec<(
+        &'static str,
         fn(
             &mut ContractAnalysis,
             &mut AnalysisDatabase,
             &Vec<Annotation>,
             settings: &Settings,
         ) -> AnalysisResult,
-    > = vec![CallChecker::run_pass];
+    )> = vec![("call_checker", CallChecker::run_pass)];
     for pass in &settings.passes {
         match pass {
-            Pass::CheckChecker => passes.push(CheckChecker::run_pass),
+            Pass::CheckChecker => passes.push(("check_checker", CheckChecker::run_pass)),
+            Pass::UnusedChecker => passes.push(("unused_checker", UnusedChecker::run_pass)),
+            Pass::AsContractChecker => {
+                passes.push(("as_contract_checker", AsContractChecker::run_pass))
+            }
+            Pass::CostEstimator => passes.push(("cost_estimator", CostEstimator::run_pass)),
+            Pass::SipChecker => passes.push(("sip_checker", SipChecker::run_pass)),
+            Pass::ResponseChecker => passes.push(("response_checker", ResponseChecker::run_pass)),
+            Pass::ArithmeticChecker => {
+                passes.push(("arithmetic_checker", ArithmeticChecker::run_pass))
+            }
             Pass::All => panic!("unexpected All in list of passes"),
         }
     }
+    // The custom lint rules are checked as soon as some are defined
+    if !settings.lints.is_empty() {
+        passes.push(("lint_checker", LintChecker::run_pass));
+    }
 
-    analysis_db.execute(|db| {
-        for pass in passes {
+    let diagnostics = analysis_db.execute(|db| {
+        for (name, pass) in passes {
             // Collect warnings and continue, or if there is an error, return.
+            // Each diagnostic is tagged with the name of the pass reporting it.
             match pass(contract_analysis, db, annotations, &settings) {
-                Ok(mut w) => errors.append(&mut w),
-                Err(mut e) => {
-                    errors.append(&mut e);
+                Ok(w) => errors.extend(w.into_iter().map(|d| (name, d))),
+                Err(e) => {
+                    errors.extend(e.into_iter().map(|d| (name, d)));
                     return Err(errors);
                 }
             }
         }
         Ok(errors)
-    })
+    })?;
+
+    // Diagnostics raised to errors by the rules are returned along with the
+    // warnings, and the interpreter rejects the contract.
+    Ok(apply_rules(
+        &contract_analysis.expressions,
+        annotations,
+        &settings.rules,
+        diagnostics,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repl::session::Session;
+    use crate::repl::SessionSettings;
+
+    fn interpret(snippet: &str, settings: Settings) -> Result<Vec<String>, Vec<String>> {
+        let mut session_settings = SessionSettings::default();
+        session_settings.repl_settings.analysis = settings;
+        let mut session = Session::new(session_settings);
+        session
+            .formatted_interpretation(
+                snippet.to_string(),
+                Some("checker".to_string()),
+                false,
+                None,
+                None,
+            )
+            .map(|(output, _)| output)
+    }
+
+    fn unused_checker_settings() -> Settings {
+        let mut settings = Settings::default();
+        settings.passes = vec![Pass::UnusedChecker];
+        settings
+    }
+
+    #[test]
+    fn rule_error() {
+        let mut settings = unused_checker_settings();
+        settings.set_rule(WarningKind::Unused, Severity::Error);
+        let snippet = "
+(define-constant ERR_UNUSED (err u402))
+";
+        match interpret(snippet, settings) {
+            Err(output) => {
+                assert_eq!(
+                    output[0],
+                    format!(
+                        "checker:2:18: {}: constant 'ERR_UNUSED' is never used",
+                        red!("error")
+                    )
+                );
+            }
+            _ => panic!("Expected failed interpretation"),
+        }
+    }
+
+    #[test]
+    fn rule_unchecked_params_rejected() {
+        let settings = serde_json::from_str::<SettingsFile>(r#"{"rules": {"unused": "error"}}"#);
+        assert!(settings.is_ok());
+        let settings =
+            serde_json::from_str::<SettingsFile>(r#"{"rules": {"unchecked_params": "error"}}"#);
+        assert!(settings.is_err());
+    }
+
+    #[test]
+    fn rule_off() {
+        let mut settings = unused_checker_settings();
+        settings.set_rule(WarningKind::Unused, Severity::Off);
+        let snippet = "
+(define-constant ERR_UNUSED (err u402))
+";
+        match interpret(snippet, settings) {
+            Ok(output) => assert!(output.is_empty()),
+            _ => panic!("Expected successful interpretation"),
+        }
+    }
+
+    #[test]
+    fn deny_function() {
+        let snippet = "
+(define-constant ERR_UNUSED (err u402))
+;; #[deny(unused)]
+(define-read-only (get-total (amount uint) (fee uint))
+    (ok amount)
+)
+";
+        match interpret(snippet, unused_checker_settings()) {
+            Err(output) => {
+                assert_eq!(output.len(), 6);
+                assert_eq!(
+                    output[0],
+                    format!(
+                        "checker:2:18: {}: constant 'ERR_UNUSED' is never used",
+                        yellow!("warning")
+                    )
+                );
+                assert_eq!(
+                    output[3],
+                    format!(
+                        "checker:4:45: {}: parameter 'fee' is never used",
+                        red!("error")
+                    )
+                );
+            }
+            _ => panic!("Expected failed interpretation"),
+        }
+    }
+
+    #[test]
+    fn deny_expression() {
+        let snippet = "
+(define-read-only (get-total (amount uint) (fee uint))
+    ;; #[deny(unused)]
+    (let ((unused u2))
+        (ok amount)
+    )
+)
+";
+        match interpret(snippet, unused_checker_settings()) {
+            Err(output) => {
+                assert_eq!(output.len(), 6);
+                assert_eq!(
+                    output[0],
+                    format!(
+                        "checker:2:45: {}: parameter 'fee' is never used",
+                        yellow!("warning")
+                    )
+                );
+                assert_eq!(
+                    output[3],
+                    format!(
+                        "checker:4:12: {}: let-binding 'unused' is never used",
+                        red!("error")
+                    )
+                );
+            }
+            _ => panic!("Expected failed interpretation"),
+        }
+    }
+
+    #[test]
+    fn warn_overrides_rule() {
+        let mut settings = unused_checker_settings();
+        settings.set_rule(WarningKind::Unused, Severity::Error);
+        let snippet = "
+;; #[warn(unused)]
+(define-constant ERR_UNUSED (err u402))
+";
+        match interpret(snippet, settings) {
+            Ok(output) => {
+                assert_eq!(
+                    output[0],
+                    format!(
+                        "checker:3:18: {}: constant 'ERR_UNUSED' is never used",
+                        yellow!("warning")
+                    )
+                );
+            }
+            _ => panic!("Expected successful interpretation"),
+        }
+    }
 }
@@ -0,0 +1,430 @@
+use crate::analysis::annotation::{Annotation, AnnotationKind, WarningKind};
+use crate::analysis::ast_visitor::{traverse, ASTVisitor};
+use crate::analysis::{self, AnalysisPass, AnalysisResult};
+use clarity::vm::analysis::analysis_db::AnalysisDatabase;
+use clarity::vm::analysis::types::ContractAnalysis;
+use clarity::vm::diagnostic::{Diagnostic, Level};
+use clarity::vm::representations::Span;
+use clarity::vm::representations::SymbolicExpressionType::*;
+use clarity::vm::types::{SequenceSubtype, TypeSignature};
+use clarity::vm::{ClarityName, SymbolicExpression};
+use std::collections::{HashMap, HashSet};
+
+pub struct ResponseChecker<'a> {
+    contract_analysis: &'a ContractAnalysis,
+    annotations: &'a Vec<Annotation>,
+    // True while traversing an expression annotated with `allow(unchecked_response)`
+    allow_unchecked_response: bool,
+    // Let-bindings holding the result of a `contract-call?`
+    call_results: HashSet<&'a ClarityName>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> ResponseChecker<'a> {
+    fn new(
+        contract_analysis: &'a ContractAnalysis,
+        annotations: &'a Vec<Annotation>,
+    ) -> ResponseChecker<'a> {
+        Self {
+            contract_analysis,
+            annotations,
+            allow_unchecked_response: false,
+            call_results: HashSet::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn run(mut self) -> AnalysisResult {
+        traverse(&mut self, &self.contract_analysis.expressions);
+        let mut diagnostics = self.diagnostics;
+        diagnostics.sort_by(|a, b| a.spans[0].cmp(&b.spans[0]));
+        Ok(diagnostics)
+    }
+
+    // Check if the expression at `span` is annotated with `allow(unchecked_response)`
+    fn is_allowed(&self, span: &Span) -> bool {
+        if self.allow_unchecked_response {
+            return true;
+        }
+        for annotation in self.annotations.iter() {
+            if annotation.span.start_line == (span.start_line - 1) {
+                return matches!(
+                    annotation.kind,
+                    AnnotationKind::Allow(WarningKind::UncheckedResponse)
+                );
+            } else if annotation.span.start_line >= span.start_line {
+                // The annotations are ordered by span, so if we have passed
+                // the target line, return.
+                return false;
+            }
+        }
+        false
+    }
+
+    fn add_diagnostic(&mut self, message: String, span: &Span) {
+        if self.is_allowed(span) {
+            return;
+        }
+        self.diagnostics.push(Diagnostic {
+            level: Level::Warning,
+            message,
+            spans: vec![span.clone()],
+            suggestion: None,
+        });
+    }
+
+    fn is_call_result(&self, expr: &SymbolicExpression) -> bool {
+        match &expr.expr {
+            Atom(name) => self.call_results.contains(name),
+            _ => is_call(expr, "contract-call?"),
+        }
+    }
+
+    // The value of each statement but the last is discarded, which silently
+    // ignores the responses it may contain.
+    fn check_statements(&mut self, statements: &'a [SymbolicExpression]) {
+        let discarded = match statements.split_last() {
+            Some((_, discarded)) => discarded,
+            None => return,
+        };
+        for statement in discarded {
+            if let Some(func) = ["is-ok", "is-err"]
+                .iter()
+                .find(|func| is_call(statement, func))
+            {
+                self.add_diagnostic(format!("result of {} is discarded", func), &statement.span);
+            } else if self
+                .type_of(statement)
+                .map_or(false, |statement_type| contains_response(statement_type))
+            {
+                self.add_diagnostic(
+                    "value containing responses is discarded".to_string(),
+                    &statement.span,
+                );
+            }
+        }
+    }
+
+    fn check_panic(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        func: &str,
+        input: &SymbolicExpression,
+    ) {
+        if self.is_call_result(input) {
+            self.add_diagnostic(
+                format!("{} discards the error of contract-call?", func),
+                &expr.span,
+            );
+        }
+    }
+
+    fn type_of(&self, expr: &SymbolicExpression) -> Option<&'a TypeSignature> {
+        self.contract_analysis
+            .type_map
+            .as_ref()
+            .and_then(|type_map| type_map.get_type(expr))
+    }
+}
+
+impl<'a> ASTVisitor<'a> for ResponseChecker<'a> {
+    fn traverse_expr(&mut self, expr: &'a SymbolicExpression) -> bool {
+        let allow_unchecked_response = self.allow_unchecked_response;
+        self.allow_unchecked_response = self.is_allowed(&expr.span);
+        let result = match &expr.expr {
+            AtomValue(value) => self.visit_atom_value(expr, value),
+            Atom(name) => self.visit_atom(expr, name),
+            List(exprs) => self.traverse_list(expr, &exprs),
+            LiteralValue(value) => self.visit_literal_value(expr, value),
+            Field(field) => self.visit_field(expr, field),
+            TraitReference(name, trait_def) => self.visit_trait_reference(expr, name, trait_def),
+        };
+        self.allow_unchecked_response = allow_unchecked_response;
+        result
+    }
+
+    fn traverse_let(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        bindings: &HashMap<&'a ClarityName, &'a SymbolicExpression>,
+        body: &'a [SymbolicExpression],
+    ) -> bool {
+        let mut call_results = Vec::new();
+        for (name, val) in bindings {
+            if !self.traverse_expr(val) {
+                return false;
+            }
+            if is_call(val, "contract-call?") {
+                call_results.push(*name);
+            }
+        }
+        // Uses of these bindings in the body are checked like the calls themselves
+        for name in call_results.iter() {
+            self.call_results.insert(*name);
+        }
+        for expr in body {
+            if !self.traverse_expr(expr) {
+                return false;
+            }
+        }
+        for name in call_results.iter() {
+            self.call_results.remove(*name);
+        }
+        self.visit_let(expr, bindings, body)
+    }
+
+    fn visit_let(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        bindings: &HashMap<&'a ClarityName, &'a SymbolicExpression>,
+        body: &'a [SymbolicExpression],
+    ) -> bool {
+        self.check_statements(body);
+        true
+    }
+
+    fn visit_begin(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        statements: &'a [SymbolicExpression],
+    ) -> bool {
+        self.check_statements(statements);
+        true
+    }
+
+    fn visit_unwrap_panic(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        input: &'a SymbolicExpression,
+    ) -> bool {
+        self.check_panic(expr, "unwrap-panic", input);
+        true
+    }
+
+    fn visit_unwrap_err_panic(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        input: &'a SymbolicExpression,
+    ) -> bool {
+        self.check_panic(expr, "unwrap-err-panic", input);
+        true
+    }
+
+    fn visit_unwrap(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        input: &'a SymbolicExpression,
+        throws: &'a SymbolicExpression,
+    ) -> bool {
+        let is_response = matches!(self.type_of(input), Some(TypeSignature::ResponseType(_)));
+        if is_response && is_call(throws, "ok") {
+            self.add_diagnostic(
+                "error is turned into an ok response".to_string(),
+                &throws.span,
+            );
+        }
+        true
+    }
+
+    fn visit_match_response(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        input: &'a SymbolicExpression,
+        ok_name: &'a ClarityName,
+        ok_branch: &'a SymbolicExpression,
+        err_name: &'a ClarityName,
+        err_branch: &'a SymbolicExpression,
+    ) -> bool {
+        if is_call(err_branch, "ok") {
+            self.add_diagnostic(
+                "error is turned into an ok response".to_string(),
+                &err_branch.span,
+            );
+        }
+        true
+    }
+}
+
+impl AnalysisPass for ResponseChecker<'_> {
+    fn run_pass(
+        contract_analysis: &mut ContractAnalysis,
+        analysis_db: &mut AnalysisDatabase,
+        annotations: &Vec<Annotation>,
+        settings: &analysis::Settings,
+    ) -> AnalysisResult {
+        let checker = ResponseChecker::new(contract_analysis, annotations);
+        checker.run()
+    }
+}
+
+// Check if `expr` is a call to the native function `func`
+fn is_call(expr: &SymbolicExpression, func: &str) -> bool {
+    expr.match_list()
+        .and_then(|list| list.first())
+        .and_then(|name| name.match_atom())
+        .map_or(false, |name| name.as_str() == func)
+}
+
+fn contains_response(value_type: &TypeSignature) -> bool {
+    match value_type {
+        TypeSignature::ResponseType(_) => true,
+        TypeSignature::OptionalType(inner) => contains_response(inner),
+        TypeSignature::SequenceType(SequenceSubtype::ListType(list)) => {
+            contains_response(list.get_list_item_type())
+        }
+        TypeSignature::TupleType(tuple) => tuple.get_type_map().values().any(contains_response),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::Pass;
+    use crate::repl::session::Session;
+    use crate::repl::SessionSettings;
+
+    fn interpret(snippet: &str) -> Vec<String> {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::ResponseChecker];
+        let mut session = Session::new(settings);
+        let callee = "(define-public (withdraw) (ok u1))";
+        session
+            .formatted_interpretation(
+                callee.to_string(),
+                Some("callee".to_string()),
+                false,
+                None,
+                None,
+            )
+            .expect("Expected successful interpretation");
+        match session.formatted_interpretation(
+            snippet.to_string(),
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((output, _)) => output,
+            _ => panic!("Expected successful interpretation"),
+        }
+    }
+
+    #[test]
+    fn unwrap_panic_contract_call() {
+        let output = interpret(
+            "
+(define-public (claim)
+    (ok (unwrap-panic (contract-call? .callee withdraw)))
+)
+",
+        );
+        assert_eq!(output.len(), 3);
+        assert_eq!(
+            output[0],
+            format!(
+                "checker:3:9: {}: unwrap-panic discards the error of contract-call?",
+                yellow!("warning")
+            )
+        );
+        assert_eq!(
+            output[1],
+            "    (ok (unwrap-panic (contract-call? .callee withdraw)))"
+        );
+        assert_eq!(
+            output[2],
+            "        ^~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~~"
+        );
+    }
+
+    #[test]
+    fn unwrap_panic_let_binding() {
+        let output = interpret(
+            "
+(define-public (claim)
+    (let ((result (contract-call? .callee withdraw)))
+        (ok (unwrap-panic result))
+    )
+)
+",
+        );
+        assert_eq!(output.len(), 3);
+        assert_eq!(
+            output[0],
+            format!(
+                "checker:4:13: {}: unwrap-panic discards the error of contract-call?",
+                yellow!("warning")
+            )
+        );
+    }
+
+    #[test]
+    fn discarded_responses() {
+        let output = interpret(
+            "
+(define-private (send (recipient principal)) (stx-transfer? u1 tx-sender recipient))
+(define-public (airdrop (recipients (list 10 principal)))
+    (begin
+        (is-ok (contract-call? .callee withdraw))
+        (map send recipients)
+        (ok true)
+    )
+)
+",
+        );
+        assert_eq!(output.len(), 6);
+        assert_eq!(
+            output[0],
+            format!(
+                "checker:5:9: {}: result of is-ok is discarded",
+                yellow!("warning")
+            )
+        );
+        assert_eq!(
+            output[3],
+            format!(
+                "checker:6:9: {}: value containing responses is discarded",
+                yellow!("warning")
+            )
+        );
+        assert_eq!(output[5], "        ^~~~~~~~~~~~~~~~~~~~~");
+    }
+
+    #[test]
+    fn match_error_into_ok() {
+        let output = interpret(
+            "
+(define-public (safe-withdraw)
+    (match (contract-call? .callee withdraw)
+        amount (ok amount)
+        error (ok u0)
+    )
+)
+",
+        );
+        assert_eq!(output.len(), 3);
+        assert_eq!(
+            output[0],
+            format!(
+                "checker:5:15: {}: error is turned into an ok response",
+                yellow!("warning")
+            )
+        );
+        assert_eq!(output[2], "              ^~~~~~");
+    }
+
+    #[test]
+    fn allow_unchecked_response() {
+        let output = interpret(
+            "
+(define-public (safe-withdraw)
+    ;; #[allow(unchecked_response)]
+    (match (contract-call? .callee withdraw)
+        amount (ok amount)
+        error (ok u0)
+    )
+)
+",
+        );
+        assert_eq!(output.len(), 0);
+    }
+}
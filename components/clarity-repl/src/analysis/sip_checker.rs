@@ -0,0 +1,578 @@
+use crate::analysis::annotation::{Annotation, AnnotationKind, WarningKind};
+use crate::analysis::ast_visitor::{traverse, ASTVisitor, TypedVar};
+use crate::analysis::{self, AnalysisPass, AnalysisResult};
+use clarity::vm::analysis::analysis_db::AnalysisDatabase;
+use clarity::vm::analysis::types::ContractAnalysis;
+use clarity::vm::diagnostic::{Diagnostic, Level};
+use clarity::vm::functions::NativeFunctions;
+use clarity::vm::representations::Span;
+use clarity::vm::{ClarityName, SymbolicExpression};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Settings {
+    // Contracts checked against SIP-009, even if they do not implement the trait
+    sip009: Vec<String>,
+    // Contracts checked against SIP-010, even if they do not implement the trait
+    sip010: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SettingsFile {
+    sip009: Option<Vec<String>>,
+    sip010: Option<Vec<String>>,
+}
+
+impl From<SettingsFile> for Settings {
+    fn from(from_file: SettingsFile) -> Self {
+        Settings {
+            sip009: from_file.sip009.unwrap_or_default(),
+            sip010: from_file.sip010.unwrap_or_default(),
+        }
+    }
+}
+
+// Requirements of a token standard which are not enforced by the type
+// signatures of its trait.
+struct Standard {
+    name: &'static str,
+    trait_name: &'static str,
+    functions: &'static [&'static str],
+    // Getters, which are not expected to modify the state
+    read_only: &'static [&'static str],
+    // Position of the `sender` and `memo` parameters of `transfer`
+    sender: usize,
+    memo: Option<usize>,
+    // Whether the token is defined with `define-fungible-token`, or with
+    // `define-non-fungible-token`
+    fungible: bool,
+    // Native function expected in each function, when the token is defined
+    // in the contract
+    token_functions: &'static [(&'static str, &'static str)],
+}
+
+static SIP009: Standard = Standard {
+    name: "SIP-009",
+    trait_name: "nft-trait",
+    functions: &[
+        "get-last-token-id",
+        "get-token-uri",
+        "get-owner",
+        "transfer",
+    ],
+    read_only: &["get-last-token-id", "get-token-uri", "get-owner"],
+    sender: 1,
+    memo: None,
+    fungible: false,
+    token_functions: &[
+        ("get-owner", "nft-get-owner?"),
+        ("transfer", "nft-transfer?"),
+    ],
+};
+
+static SIP010: Standard = Standard {
+    name: "SIP-010",
+    trait_name: "sip-010-trait",
+    functions: &[
+        "transfer",
+        "get-name",
+        "get-symbol",
+        "get-decimals",
+        "get-balance",
+        "get-total-supply",
+        "get-token-uri",
+    ],
+    read_only: &[
+        "get-name",
+        "get-symbol",
+        "get-decimals",
+        "get-balance",
+        "get-total-supply",
+        "get-token-uri",
+    ],
+    sender: 1,
+    memo: Some(3),
+    fungible: true,
+    token_functions: &[
+        ("transfer", "ft-transfer?"),
+        ("get-balance", "ft-get-balance"),
+        ("get-total-supply", "ft-get-supply"),
+    ],
+};
+
+// What a function body does, as far as the standards are concerned
+#[derive(Default)]
+struct Facts<'a> {
+    // Symbols compared with `tx-sender` or `contract-caller` using `is-eq`
+    compared_to_caller: HashSet<&'a ClarityName>,
+    referenced: HashSet<&'a ClarityName>,
+    printed: bool,
+    natives: HashSet<&'static str>,
+}
+
+struct Function<'a> {
+    read_only: bool,
+    params: Vec<&'a ClarityName>,
+    span: Span,
+    allowed: bool,
+    facts: Facts<'a>,
+}
+
+pub struct SipChecker<'a> {
+    annotations: &'a Vec<Annotation>,
+    functions: HashMap<&'a ClarityName, Function<'a>>,
+    fungible_tokens: Vec<&'a ClarityName>,
+    non_fungible_tokens: Vec<&'a ClarityName>,
+    // Facts about the function being traversed
+    facts: Facts<'a>,
+}
+
+impl<'a> SipChecker<'a> {
+    fn new(annotations: &'a Vec<Annotation>) -> SipChecker<'a> {
+        Self {
+            annotations,
+            functions: HashMap::new(),
+            fungible_tokens: vec![],
+            non_fungible_tokens: vec![],
+            facts: Facts::default(),
+        }
+    }
+
+    fn run(
+        mut self,
+        contract_analysis: &'a ContractAnalysis,
+        settings: &Settings,
+    ) -> AnalysisResult {
+        traverse(&mut self, &contract_analysis.expressions);
+
+        let contract_name = contract_analysis.contract_identifier.name.as_str();
+        let mut diagnostics = vec![];
+        for (standard, requested) in [(&SIP009, &settings.sip009), (&SIP010, &settings.sip010)] {
+            let implemented = contract_analysis
+                .implemented_traits
+                .iter()
+                .any(|trait_id| trait_id.name.as_str() == standard.trait_name);
+            if implemented || requested.iter().any(|name| name == contract_name) {
+                self.check_standard(standard, &mut diagnostics);
+            }
+        }
+        diagnostics.sort_by(|a, b| a.spans.first().cmp(&b.spans.first()));
+        Ok(diagnostics)
+    }
+
+    fn check_standard(&self, standard: &Standard, diagnostics: &mut Vec<Diagnostic>) {
+        let defines_token = if standard.fungible {
+            !self.fungible_tokens.is_empty()
+        } else {
+            !self.non_fungible_tokens.is_empty()
+        };
+
+        for name in standard.functions.iter() {
+            let function = match self.functions.get(&ClarityName::from(*name)) {
+                Some(function) => function,
+                None => {
+                    diagnostics.push(Diagnostic {
+                        level: Level::Warning,
+                        message: format!("{}: missing function '{}'", standard.name, name),
+                        spans: vec![],
+                        suggestion: None,
+                    });
+                    continue;
+                }
+            };
+            if function.allowed {
+                continue;
+            }
+            let mut warn = |message: String, suggestion: Option<String>| {
+                diagnostics.push(Diagnostic {
+                    level: Level::Warning,
+                    message: format!("{}: {}", standard.name, message),
+                    spans: vec![function.span.clone()],
+                    suggestion,
+                })
+            };
+
+            if standard.read_only.contains(name) && !function.read_only {
+                warn(
+                    format!("'{}' should be defined with define-read-only", name),
+                    None,
+                );
+            }
+
+            if *name == "transfer" {
+                if let Some(sender) = function.params.get(standard.sender) {
+                    if !function.facts.compared_to_caller.contains(sender) {
+                        warn(
+                            format!("'transfer' does not check that '{}' is tx-sender", sender),
+                            Some(format!(
+                                "assert that the sender is the caller, e.g. (asserts! (is-eq tx-sender {}) (err u4))",
+                                sender
+                            )),
+                        );
+                    }
+                }
+                if let Some(memo) = standard.memo.and_then(|i| function.params.get(i)) {
+                    if !function.facts.printed || !function.facts.referenced.contains(memo) {
+                        warn(
+                            "the memo of 'transfer' is never printed".to_string(),
+                            Some(format!(
+                                "print the memo, e.g. (match {} to-print (print to-print) 0x)",
+                                memo
+                            )),
+                        );
+                    }
+                }
+            }
+
+            if defines_token {
+                for (_, native) in standard
+                    .token_functions
+                    .iter()
+                    .filter(|(function_name, _)| function_name == name)
+                {
+                    if !function.facts.natives.contains(native) {
+                        warn(format!("'{}' does not use {}", name, native), None);
+                    }
+                }
+            }
+        }
+    }
+
+    // Check if the function at `span` is annotated with `allow(sip)`
+    fn is_allowed(&self, span: &Span) -> bool {
+        self.annotations.iter().any(|annotation| {
+            annotation.span.start_line == span.start_line - 1
+                && matches!(annotation.kind, AnnotationKind::Allow(WarningKind::Sip))
+        })
+    }
+
+    fn add_function(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        parameters: Option<Vec<TypedVar<'a>>>,
+        read_only: bool,
+    ) {
+        // Span of the name, in `(define-public (name ...) ...)`
+        let span = expr
+            .match_list()
+            .and_then(|list| list.get(1))
+            .and_then(|signature| signature.match_list())
+            .and_then(|signature| signature.get(0))
+            .map_or_else(|| expr.span.clone(), |name| name.span.clone());
+        let function = Function {
+            read_only,
+            params: parameters
+                .unwrap_or_default()
+                .iter()
+                .map(|param| param.name)
+                .collect(),
+            allowed: self.is_allowed(&span),
+            span,
+            facts: std::mem::take(&mut self.facts),
+        };
+        self.functions.insert(name, function);
+    }
+}
+
+impl<'a> ASTVisitor<'a> for SipChecker<'a> {
+    fn traverse_define_private(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        parameters: Option<Vec<TypedVar<'a>>>,
+        body: &'a SymbolicExpression,
+    ) -> bool {
+        self.facts = Facts::default();
+        self.traverse_expr(body);
+        self.add_function(expr, name, parameters, false);
+        true
+    }
+
+    fn traverse_define_read_only(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        parameters: Option<Vec<TypedVar<'a>>>,
+        body: &'a SymbolicExpression,
+    ) -> bool {
+        self.facts = Facts::default();
+        self.traverse_expr(body);
+        self.add_function(expr, name, parameters, true);
+        true
+    }
+
+    fn traverse_define_public(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        parameters: Option<Vec<TypedVar<'a>>>,
+        body: &'a SymbolicExpression,
+    ) -> bool {
+        self.facts = Facts::default();
+        self.traverse_expr(body);
+        self.add_function(expr, name, parameters, false);
+        true
+    }
+
+    fn visit_define_ft(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        supply: Option<&'a SymbolicExpression>,
+    ) -> bool {
+        self.fungible_tokens.push(name);
+        true
+    }
+
+    fn visit_define_nft(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        nft_type: &'a SymbolicExpression,
+    ) -> bool {
+        self.non_fungible_tokens.push(name);
+        true
+    }
+
+    fn visit_atom(&mut self, expr: &'a SymbolicExpression, atom: &'a ClarityName) -> bool {
+        self.facts.referenced.insert(atom);
+        true
+    }
+
+    fn visit_comparison(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        func: NativeFunctions,
+        operands: &'a [SymbolicExpression],
+    ) -> bool {
+        if !matches!(func, NativeFunctions::Equals) {
+            return true;
+        }
+        let atoms: Vec<&'a ClarityName> = operands
+            .iter()
+            .filter_map(|operand| operand.match_atom())
+            .collect();
+        let is_caller = |atom: &&ClarityName| {
+            atom.as_str() == "tx-sender" || atom.as_str() == "contract-caller"
+        };
+        if atoms.iter().any(is_caller) {
+            for atom in atoms.into_iter().filter(|atom| !is_caller(atom)) {
+                self.facts.compared_to_caller.insert(atom);
+            }
+        }
+        true
+    }
+
+    fn visit_print(&mut self, expr: &'a SymbolicExpression, value: &'a SymbolicExpression) -> bool {
+        self.facts.printed = true;
+        true
+    }
+
+    fn visit_ft_transfer(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        token: &'a ClarityName,
+        amount: &'a SymbolicExpression,
+        sender: &'a SymbolicExpression,
+        recipient: &'a SymbolicExpression,
+    ) -> bool {
+        self.facts.natives.insert("ft-transfer?");
+        true
+    }
+
+    fn visit_ft_get_balance(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        token: &'a ClarityName,
+        owner: &'a SymbolicExpression,
+    ) -> bool {
+        self.facts.natives.insert("ft-get-balance");
+        true
+    }
+
+    fn visit_ft_get_supply(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        token: &'a ClarityName,
+    ) -> bool {
+        self.facts.natives.insert("ft-get-supply");
+        true
+    }
+
+    fn visit_nft_transfer(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        token: &'a ClarityName,
+        identifier: &'a SymbolicExpression,
+        sender: &'a SymbolicExpression,
+        recipient: &'a SymbolicExpression,
+    ) -> bool {
+        self.facts.natives.insert("nft-transfer?");
+        true
+    }
+
+    fn visit_nft_get_owner(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        token: &'a ClarityName,
+        identifier: &'a SymbolicExpression,
+    ) -> bool {
+        self.facts.natives.insert("nft-get-owner?");
+        true
+    }
+}
+
+impl AnalysisPass for SipChecker<'_> {
+    fn run_pass(
+        contract_analysis: &mut ContractAnalysis,
+        analysis_db: &mut AnalysisDatabase,
+        annotations: &Vec<Annotation>,
+        settings: &analysis::Settings,
+    ) -> AnalysisResult {
+        let checker = SipChecker::new(annotations);
+        checker.run(contract_analysis, &settings.sip_checker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::Pass;
+    use crate::repl::session::Session;
+    use crate::repl::SessionSettings;
+
+    fn session() -> Session {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::SipChecker];
+        settings.repl_settings.analysis.sip_checker.sip010 = vec!["checker".to_string()];
+        Session::new(settings)
+    }
+
+    fn interpret(session: &mut Session, snippet: &str, name: &str) -> Vec<String> {
+        match session.formatted_interpretation(
+            snippet.to_string(),
+            Some(name.to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((output, _)) => output,
+            _ => panic!("Expected successful interpretation"),
+        }
+    }
+
+    #[test]
+    fn sip010_requested_in_settings() {
+        let mut session = session();
+        let snippet = "
+(define-fungible-token token)
+(define-public (transfer (amount uint) (sender principal) (recipient principal) (memo (optional (buff 34))))
+    (ft-transfer? token amount sender recipient)
+)
+(define-read-only (get-name) (ok \"Token\"))
+(define-read-only (get-symbol) (ok \"TKN\"))
+(define-read-only (get-decimals) (ok u6))
+(define-public (get-balance (who principal)) (ok (ft-get-balance token who)))
+(define-read-only (get-total-supply) (ok u1000000))
+(define-read-only (get-token-uri) (ok (some u\"https://example.com\")))
+";
+        let output = interpret(&mut session, snippet, "checker");
+        assert_eq!(output.len(), 12);
+        assert_eq!(
+            output[0],
+            format!(
+                "checker:3:17: {}: SIP-010: 'transfer' does not check that 'sender' is tx-sender",
+                yellow!("warning")
+            )
+        );
+        assert_eq!(output[2], "                ^~~~~~~");
+        assert_eq!(
+            output[3],
+            format!(
+                "checker:3:17: {}: SIP-010: the memo of 'transfer' is never printed",
+                yellow!("warning")
+            )
+        );
+        assert_eq!(
+            output[6],
+            format!(
+                "checker:8:17: {}: SIP-010: 'get-balance' should be defined with define-read-only",
+                yellow!("warning")
+            )
+        );
+        assert_eq!(
+            output[9],
+            format!(
+                "checker:9:20: {}: SIP-010: 'get-total-supply' does not use ft-get-supply",
+                yellow!("warning")
+            )
+        );
+    }
+
+    #[test]
+    fn sip009_implemented_trait() {
+        let mut session = session();
+        let nft_trait = "
+(define-trait nft-trait
+    (
+        (get-last-token-id () (response uint uint))
+        (get-token-uri (uint) (response (optional (string-ascii 256)) uint))
+        (get-owner (uint) (response (optional principal) uint))
+        (transfer (uint principal principal) (response bool uint))
+    )
+)
+";
+        interpret(&mut session, nft_trait, "nft-trait");
+        let snippet = "
+(impl-trait .nft-trait.nft-trait)
+(define-non-fungible-token nft uint)
+(define-data-var last-id uint u0)
+(define-read-only (get-last-token-id) (ok (var-get last-id)))
+(define-read-only (get-token-uri (id uint)) (ok (some \"ipfs://token\")))
+(define-read-only (get-owner (id uint)) (ok (nft-get-owner? nft id)))
+(define-public (transfer (id uint) (sender principal) (recipient principal))
+    (begin
+        (asserts! (is-eq tx-sender sender) (err u403))
+        (nft-transfer? nft id sender recipient)
+    )
+)
+";
+        let output = interpret(&mut session, snippet, "nft");
+        assert_eq!(output.len(), 0);
+    }
+
+    #[test]
+    fn sip009_transfer_without_sender_check() {
+        let mut session = session();
+        let nft_trait = "
+(define-trait nft-trait
+    (
+        (get-last-token-id () (response uint uint))
+        (get-token-uri (uint) (response (optional (string-ascii 256)) uint))
+        (get-owner (uint) (response (optional principal) uint))
+        (transfer (uint principal principal) (response bool uint))
+    )
+)
+";
+        interpret(&mut session, nft_trait, "nft-trait");
+        let snippet = "
+(impl-trait .nft-trait.nft-trait)
+(define-non-fungible-token nft uint)
+(define-read-only (get-last-token-id) (ok u0))
+(define-read-only (get-token-uri (id uint)) (ok (some \"ipfs://token\")))
+(define-read-only (get-owner (id uint)) (ok (nft-get-owner? nft id)))
+(define-public (transfer (id uint) (sender principal) (recipient principal))
+    (nft-transfer? nft id sender recipient)
+)
+";
+        let output = interpret(&mut session, snippet, "nft");
+        assert_eq!(output.len(), 3);
+        assert_eq!(
+            output[0],
+            format!(
+                "nft:7:17: {}: SIP-009: 'transfer' does not check that 'sender' is tx-sender",
+                yellow!("warning")
+            )
+        );
+    }
+}
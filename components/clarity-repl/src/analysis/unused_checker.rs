@@ -0,0 +1,696 @@
+use crate::analysis::annotation::{Annotation, AnnotationKind, WarningKind};
+use crate::analysis::ast_visitor::{traverse, ASTVisitor, TypedVar};
+use crate::analysis::{self, AnalysisPass, AnalysisResult};
+use clarity::vm::analysis::analysis_db::AnalysisDatabase;
+use clarity::vm::analysis::types::ContractAnalysis;
+use clarity::vm::diagnostic::{Diagnostic, Level};
+use clarity::vm::representations::Span;
+use clarity::vm::representations::SymbolicExpressionType::*;
+use clarity::vm::types::Value;
+use clarity::vm::{ClarityName, SymbolicExpression};
+use std::collections::{HashMap, HashSet};
+
+// A local variable: a function parameter or a let-binding
+struct Local<'a> {
+    name: &'a ClarityName,
+    kind: &'static str,
+    span: Span,
+    used: bool,
+    allowed: bool,
+}
+
+// A top-level definition: a private function, constant, data-var or map
+struct Definition<'a> {
+    name: &'a ClarityName,
+    kind: &'static str,
+    span: Span,
+    allowed: bool,
+}
+
+pub struct UnusedChecker<'a> {
+    annotations: &'a Vec<Annotation>,
+    // True while traversing an expression annotated with `allow(unused)`
+    allow_unused: bool,
+    // Stack of the scopes of local variables, the innermost being last
+    scopes: Vec<Vec<Local<'a>>>,
+    definitions: Vec<Definition<'a>>,
+    // Names of the definitions referenced anywhere in the contract
+    references: HashSet<&'a ClarityName>,
+    diagnostics: Vec<Vec<Diagnostic>>,
+}
+
+impl<'a> UnusedChecker<'a> {
+    fn new(annotations: &'a Vec<Annotation>) -> UnusedChecker<'a> {
+        Self {
+            annotations,
+            allow_unused: false,
+            scopes: Vec::new(),
+            definitions: Vec::new(),
+            references: HashSet::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn run(mut self, contract_analysis: &'a ContractAnalysis) -> AnalysisResult {
+        traverse(&mut self, &contract_analysis.expressions);
+
+        // Definitions may be referenced before they are defined, so they can
+        // only be checked once the entire contract has been traversed.
+        for definition in self.definitions.iter() {
+            if !definition.allowed && !self.references.contains(definition.name) {
+                self.diagnostics.push(vec![unused_diagnostic(
+                    definition.kind,
+                    definition.name,
+                    &definition.span,
+                )]);
+            }
+        }
+
+        // Order the sets by the span of the warning (the first diagnostic)
+        let mut diagnostics = self.diagnostics;
+        diagnostics.sort_by(|a, b| a[0].spans[0].cmp(&b[0].spans[0]));
+        Ok(diagnostics.into_iter().flatten().collect())
+    }
+
+    // Check if the item at `span` is annotated with `allow(unused)`
+    fn is_allowed(&self, span: &Span) -> bool {
+        if self.allow_unused {
+            return true;
+        }
+        for annotation in self.annotations.iter() {
+            if annotation.span.start_line == (span.start_line - 1) {
+                return matches!(annotation.kind, AnnotationKind::Allow(WarningKind::Unused));
+            } else if annotation.span.start_line >= span.start_line {
+                // The annotations are ordered by span, so if we have passed
+                // the target line, return.
+                return false;
+            }
+        }
+        false
+    }
+
+    fn add_definition(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        kind: &'static str,
+    ) {
+        let span = name_span(expr);
+        let allowed = self.is_allowed(&span);
+        self.definitions.push(Definition {
+            name,
+            kind,
+            span,
+            allowed,
+        });
+    }
+
+    fn add_local(&mut self, name: &'a ClarityName, kind: &'static str, span: Span) {
+        let allowed = self.is_allowed(&span);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(Local {
+                name,
+                kind,
+                span,
+                used: false,
+                allowed,
+            });
+        }
+    }
+
+    fn pop_scope(&mut self) {
+        if let Some(scope) = self.scopes.pop() {
+            for local in scope {
+                if !local.used && !local.allowed {
+                    self.diagnostics.push(vec![unused_diagnostic(
+                        local.kind,
+                        local.name,
+                        &local.span,
+                    )]);
+                }
+            }
+        }
+    }
+
+    // Mark `name` as used, resolving it to the innermost local variable with
+    // this name, or to a definition if it is not a local variable.
+    fn reference(&mut self, name: &'a ClarityName) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(local) = scope.iter_mut().find(|local| local.name == name) {
+                local.used = true;
+                return;
+            }
+        }
+        self.references.insert(name);
+    }
+
+    fn traverse_function(
+        &mut self,
+        parameters: Option<Vec<TypedVar<'a>>>,
+        body: &'a SymbolicExpression,
+    ) -> bool {
+        self.scopes.push(Vec::new());
+        for param in parameters.unwrap_or_default() {
+            self.add_local(param.name, "parameter", param.decl_span);
+        }
+        let result = self.traverse_expr(body);
+        self.pop_scope();
+        result
+    }
+
+    // Traverse the bindings and the body of a `let` expression, in the scope
+    // pushed by the caller.
+    fn traverse_let_scope(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        body: &'a [SymbolicExpression],
+    ) -> bool {
+        // The bindings are evaluated in order, and each one can reference the
+        // bindings before it, so get them from the expression rather than the
+        // (unordered) map.
+        let pairs = expr
+            .match_list()
+            .and_then(|list| list.get(1))
+            .and_then(|bindings| bindings.match_list())
+            .unwrap_or_default();
+        for pair in pairs {
+            if let Some([name, value]) = pair.match_list() {
+                if !self.traverse_expr(value) {
+                    return false;
+                }
+                if let Some(name_atom) = name.match_atom() {
+                    self.add_local(name_atom, "let-binding", name.span.clone());
+                }
+            }
+        }
+        for stmt in body {
+            if !self.traverse_expr(stmt) {
+                return false;
+            }
+        }
+        self.check_unreachable(body);
+        true
+    }
+
+    // Report the statements following a statement which always exits early
+    // with an error, since they can never be executed.
+    fn check_unreachable(&mut self, statements: &'a [SymbolicExpression]) {
+        for (i, statement) in statements.iter().enumerate() {
+            if !is_unconditional_exit(statement) {
+                continue;
+            }
+            if let (Some(first), Some(last)) = (statements.get(i + 1), statements.last()) {
+                let span = Span {
+                    start_line: first.span.start_line,
+                    start_column: first.span.start_column,
+                    end_line: last.span.end_line,
+                    end_column: last.span.end_column,
+                };
+                if self.is_allowed(&span) {
+                    return;
+                }
+                self.diagnostics.push(vec![
+                    Diagnostic {
+                        level: Level::Warning,
+                        message: "unreachable code".to_string(),
+                        spans: vec![span],
+                        suggestion: None,
+                    },
+                    Diagnostic {
+                        level: Level::Note,
+                        message: "unconditional early return here".to_string(),
+                        spans: vec![statement.span.clone()],
+                        suggestion: None,
+                    },
+                ]);
+            }
+            return;
+        }
+    }
+}
+
+impl<'a> ASTVisitor<'a> for UnusedChecker<'a> {
+    fn traverse_expr(&mut self, expr: &'a SymbolicExpression) -> bool {
+        // An expression annotated with `allow(unused)` silences the warnings
+        // for everything declared inside of it.
+        let allow_unused = self.allow_unused;
+        self.allow_unused = self.is_allowed(&expr.span);
+        let result = match &expr.expr {
+            AtomValue(value) => self.visit_atom_value(expr, value),
+            Atom(name) => self.visit_atom(expr, name),
+            List(exprs) => self.traverse_list(expr, &exprs),
+            LiteralValue(value) => self.visit_literal_value(expr, value),
+            Field(field) => self.visit_field(expr, field),
+            TraitReference(name, trait_def) => self.visit_trait_reference(expr, name, trait_def),
+        };
+        self.allow_unused = allow_unused;
+        result
+    }
+
+    fn traverse_define_private(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        parameters: Option<Vec<TypedVar<'a>>>,
+        body: &'a SymbolicExpression,
+    ) -> bool {
+        self.add_definition(expr, name, "private function");
+        self.traverse_function(parameters, body)
+    }
+
+    fn traverse_define_read_only(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        parameters: Option<Vec<TypedVar<'a>>>,
+        body: &'a SymbolicExpression,
+    ) -> bool {
+        self.traverse_function(parameters, body)
+    }
+
+    fn traverse_define_public(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        parameters: Option<Vec<TypedVar<'a>>>,
+        body: &'a SymbolicExpression,
+    ) -> bool {
+        self.traverse_function(parameters, body)
+    }
+
+    fn visit_define_constant(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        value: &'a SymbolicExpression,
+    ) -> bool {
+        self.add_definition(expr, name, "constant");
+        true
+    }
+
+    fn visit_define_data_var(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        data_type: &'a SymbolicExpression,
+        initial: &'a SymbolicExpression,
+    ) -> bool {
+        self.add_definition(expr, name, "data-var");
+        true
+    }
+
+    fn visit_define_map(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        key_type: &'a SymbolicExpression,
+        value_type: &'a SymbolicExpression,
+    ) -> bool {
+        self.add_definition(expr, name, "map");
+        true
+    }
+
+    fn traverse_let(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        bindings: &HashMap<&'a ClarityName, &'a SymbolicExpression>,
+        body: &'a [SymbolicExpression],
+    ) -> bool {
+        self.scopes.push(Vec::new());
+        let result = self.traverse_let_scope(expr, body);
+        self.pop_scope();
+        result && self.visit_let(expr, bindings, body)
+    }
+
+    fn traverse_begin(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        statements: &'a [SymbolicExpression],
+    ) -> bool {
+        for stmt in statements {
+            if !self.traverse_expr(stmt) {
+                return false;
+            }
+        }
+        self.check_unreachable(statements);
+        self.visit_begin(expr, statements)
+    }
+
+    fn visit_atom(&mut self, expr: &'a SymbolicExpression, atom: &'a ClarityName) -> bool {
+        self.reference(atom);
+        true
+    }
+
+    fn visit_var_get(&mut self, expr: &'a SymbolicExpression, name: &'a ClarityName) -> bool {
+        self.references.insert(name);
+        true
+    }
+
+    fn visit_var_set(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        value: &'a SymbolicExpression,
+    ) -> bool {
+        self.references.insert(name);
+        true
+    }
+
+    fn visit_map_get(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        key: &HashMap<Option<&'a ClarityName>, &'a SymbolicExpression>,
+    ) -> bool {
+        self.references.insert(name);
+        true
+    }
+
+    fn visit_map_set(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        key: &HashMap<Option<&'a ClarityName>, &'a SymbolicExpression>,
+        value: &HashMap<Option<&'a ClarityName>, &'a SymbolicExpression>,
+    ) -> bool {
+        self.references.insert(name);
+        true
+    }
+
+    fn visit_map_insert(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        key: &HashMap<Option<&'a ClarityName>, &'a SymbolicExpression>,
+        value: &HashMap<Option<&'a ClarityName>, &'a SymbolicExpression>,
+    ) -> bool {
+        self.references.insert(name);
+        true
+    }
+
+    fn visit_map_delete(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        key: &HashMap<Option<&'a ClarityName>, &'a SymbolicExpression>,
+    ) -> bool {
+        self.references.insert(name);
+        true
+    }
+
+    fn visit_call_user_defined(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        name: &'a ClarityName,
+        args: &'a [SymbolicExpression],
+    ) -> bool {
+        self.references.insert(name);
+        true
+    }
+
+    fn visit_map(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        func: &'a ClarityName,
+        sequences: &'a [SymbolicExpression],
+    ) -> bool {
+        self.references.insert(func);
+        true
+    }
+
+    fn visit_fold(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        func: &'a ClarityName,
+        sequence: &'a SymbolicExpression,
+        initial: &'a SymbolicExpression,
+    ) -> bool {
+        self.references.insert(func);
+        true
+    }
+
+    fn visit_filter(
+        &mut self,
+        expr: &'a SymbolicExpression,
+        func: &'a ClarityName,
+        sequence: &'a SymbolicExpression,
+    ) -> bool {
+        self.references.insert(func);
+        true
+    }
+}
+
+impl AnalysisPass for UnusedChecker<'_> {
+    fn run_pass(
+        contract_analysis: &mut ContractAnalysis,
+        analysis_db: &mut AnalysisDatabase,
+        annotations: &Vec<Annotation>,
+        settings: &analysis::Settings,
+    ) -> AnalysisResult {
+        let checker = UnusedChecker::new(annotations);
+        checker.run(contract_analysis)
+    }
+}
+
+fn unused_diagnostic(kind: &str, name: &ClarityName, span: &Span) -> Diagnostic {
+    Diagnostic {
+        level: Level::Warning,
+        message: format!("{} '{}' is never used", kind, name),
+        spans: vec![span.clone()],
+        suggestion: None,
+    }
+}
+
+// Span of the name in a definition, e.g. `foo` in `(define-private (foo) ...)`
+// or in `(define-constant foo ...)`.
+fn name_span(expr: &SymbolicExpression) -> Span {
+    let name = expr.match_list().and_then(|list| list.get(1));
+    match name {
+        Some(name) => match name.match_list().and_then(|signature| signature.get(0)) {
+            Some(name) => name.span.clone(),
+            None => name.span.clone(),
+        },
+        None => expr.span.clone(),
+    }
+}
+
+fn match_function_call<'a>(
+    expr: &'a SymbolicExpression,
+    function_name: &str,
+) -> Option<&'a [SymbolicExpression]> {
+    let (name, args) = expr.match_list()?.split_first()?;
+    if name.match_atom()?.as_str() == function_name {
+        Some(args)
+    } else {
+        None
+    }
+}
+
+fn is_literal(expr: &SymbolicExpression, atom: &str, value: Value) -> bool {
+    match &expr.expr {
+        Atom(name) => name.as_str() == atom,
+        AtomValue(v) | LiteralValue(v) => *v == value,
+        _ => false,
+    }
+}
+
+// Check if the statement always exits early, returning an error, i.e.
+// `(asserts! false (err u1))`. Unwrapping a literal `(err u1)` or `none` would
+// exit early as well, but can't be type-checked, so it is not considered here.
+fn is_unconditional_exit(expr: &SymbolicExpression) -> bool {
+    match match_function_call(expr, "asserts!").and_then(|args| args.get(0)) {
+        Some(cond) => is_literal(cond, "false", Value::Bool(false)),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::Pass;
+    use crate::repl::session::Session;
+    use crate::repl::SessionSettings;
+
+    fn interpret(snippet: &str) -> Vec<String> {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.analysis.passes = vec![Pass::UnusedChecker];
+        let mut session = Session::new(settings);
+        match session.formatted_interpretation(
+            snippet.to_string(),
+            Some("checker".to_string()),
+            false,
+            None,
+            None,
+        ) {
+            Ok((output, _)) => output,
+            _ => panic!("Expected successful interpretation"),
+        }
+    }
+
+    #[test]
+    fn unused_private_function() {
+        let output = interpret(
+            "
+(define-private (helper)
+    (ok true)
+)
+",
+        );
+        assert_eq!(output.len(), 3);
+        assert_eq!(
+            output[0],
+            format!(
+                "checker:2:18: {}: private function 'helper' is never used",
+                yellow!("warning")
+            )
+        );
+        assert_eq!(output[1], "(define-private (helper)");
+        assert_eq!(output[2], "                 ^~~~~~");
+    }
+
+    #[test]
+    fn used_private_function() {
+        let output = interpret(
+            "
+(define-private (double (n uint))
+    (* n u2)
+)
+(define-private (add (n uint) (acc uint))
+    (+ n acc)
+)
+(define-read-only (compute (n uint))
+    (fold add (list n n) (double n))
+)
+",
+        );
+        assert_eq!(output.len(), 0);
+    }
+
+    #[test]
+    fn unused_constant() {
+        let output = interpret(
+            "
+(define-constant ERR_UNAUTHORIZED (err u401))
+(define-constant ERR_UNUSED (err u402))
+(define-read-only (check)
+    (if (is-eq tx-sender contract-caller) (ok true) ERR_UNAUTHORIZED)
+)
+",
+        );
+        assert_eq!(output.len(), 3);
+        assert_eq!(
+            output[0],
+            format!(
+                "checker:3:18: {}: constant 'ERR_UNUSED' is never used",
+                yellow!("warning")
+            )
+        );
+        assert_eq!(output[1], "(define-constant ERR_UNUSED (err u402))");
+        assert_eq!(output[2], "                 ^~~~~~~~~~");
+    }
+
+    #[test]
+    fn unused_data_var_and_map() {
+        let output = interpret(
+            "
+(define-data-var counter uint u0)
+(define-map balances principal uint)
+(define-map unused principal uint)
+(define-public (increment)
+    (begin
+        (var-set counter (+ (var-get counter) u1))
+        (ok (map-set balances tx-sender (var-get counter)))
+    )
+)
+",
+        );
+        assert_eq!(output.len(), 3);
+        assert_eq!(
+            output[0],
+            format!(
+                "checker:4:13: {}: map 'unused' is never used",
+                yellow!("warning")
+            )
+        );
+    }
+
+    #[test]
+    fn unused_let_binding_and_parameter() {
+        let output = interpret(
+            "
+(define-read-only (get-total (amount uint) (fee uint))
+    (let ((total (+ amount u1)) (unused u2))
+        total
+    )
+)
+",
+        );
+        assert_eq!(output.len(), 6);
+        assert_eq!(
+            output[0],
+            format!(
+                "checker:2:45: {}: parameter 'fee' is never used",
+                yellow!("warning")
+            )
+        );
+        assert_eq!(
+            output[3],
+            format!(
+                "checker:3:34: {}: let-binding 'unused' is never used",
+                yellow!("warning")
+            )
+        );
+    }
+
+    #[test]
+    fn unreachable_code() {
+        let output = interpret(
+            "
+(define-public (fail)
+    (begin
+        (asserts! false (err u1))
+        (ok true)
+    )
+)
+",
+        );
+        assert_eq!(output.len(), 6);
+        assert_eq!(
+            output[0],
+            format!("checker:5:9: {}: unreachable code", yellow!("warning"))
+        );
+        assert_eq!(output[1], "        (ok true)");
+        assert_eq!(output[2], "        ^~~~~~~~~");
+        assert_eq!(
+            output[3],
+            format!(
+                "checker:4:9: {}: unconditional early return here",
+                blue!("note")
+            )
+        );
+    }
+
+    #[test]
+    fn allow_unused() {
+        let output = interpret(
+            "
+;; #[allow(unused)]
+(define-constant ERR_UNUSED (err u402))
+;; #[allow(unused)]
+(define-read-only (get-total (amount uint) (fee uint))
+    (let ((total (+ amount u1)) (unused u2))
+        total
+    )
+)
+(define-read-only (get-fee (amount uint))
+    (let (
+        ;; #[allow(unused)]
+        (unused u2)
+    )
+        (/ amount u100)
+    )
+)
+",
+        );
+        assert_eq!(output.len(), 0);
+    }
+}
@@ -8,6 +8,7 @@ use clarity::vm::errors::{Error, RuntimeErrorType};
 
 use clarity::vm::representations::SymbolicExpression;
 use clarity::vm::types::QualifiedContractIdentifier;
+use clarity::vm::ClarityVersion;
 
 use clarity::vm::ast::definition_sorter::DefinitionSorter;
 use clarity::vm::ast::errors::ParseResult;
@@ -20,10 +21,14 @@ pub use clarity::vm::ast::types::ContractAST;
 
 use self::parser::error::ParserError;
 
+/// Build the AST of a contract using the error-recovering parser: unlike the parser
+/// used for deployments, it keeps going after an error, so that partial code (while
+/// being edited for instance) still produces an AST and a complete list of diagnostics.
 pub fn build_ast<T: CostTracker>(
     contract_identifier: &QualifiedContractIdentifier,
     source_code: &str,
     cost_track: &mut T,
+    clarity_version: ClarityVersion,
 ) -> (ContractAST, Vec<Diagnostic>, bool) {
     let cost_err = match runtime_cost(
         ClarityCostFunction::AstParse,
@@ -47,42 +52,42 @@ pub fn build_ast<T: CostTracker>(
         );
     }
     let mut contract_ast = ContractAST::new(contract_identifier.clone(), pre_expressions);
-    match StackDepthChecker::run_pass(&mut contract_ast) {
+    match StackDepthChecker::run_pass(&mut contract_ast, clarity_version) {
         Err(e) => {
             diagnostics.push(e.diagnostic);
             success = false;
         }
         _ => (),
     }
-    match ExpressionIdentifier::run_pre_expression_pass(&mut contract_ast) {
+    match ExpressionIdentifier::run_pre_expression_pass(&mut contract_ast, clarity_version) {
         Err(e) => {
             diagnostics.push(e.diagnostic);
             success = false;
         }
         _ => (),
     }
-    match DefinitionSorter::run_pass(&mut contract_ast, cost_track) {
+    match DefinitionSorter::run_pass(&mut contract_ast, cost_track, clarity_version) {
         Err(e) => {
             diagnostics.push(e.diagnostic);
             success = false;
         }
         _ => (),
     }
-    match TraitsResolver::run_pass(&mut contract_ast) {
+    match TraitsResolver::run_pass(&mut contract_ast, clarity_version) {
         Err(e) => {
             diagnostics.push(e.diagnostic);
             success = false;
         }
         _ => (),
     }
-    match SugarExpander::run_pass(&mut contract_ast) {
+    match SugarExpander::run_pass(&mut contract_ast, clarity_version) {
         Err(e) => {
             diagnostics.push(e.diagnostic);
             success = false;
         }
         _ => (),
     }
-    match ExpressionIdentifier::run_expression_pass(&mut contract_ast) {
+    match ExpressionIdentifier::run_expression_pass(&mut contract_ast, clarity_version) {
         Err(e) => {
             diagnostics.push(e.diagnostic);
             success = false;
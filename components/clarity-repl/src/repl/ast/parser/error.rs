@@ -1,7 +1,7 @@
-use crate::clarity::diagnostic::{DiagnosableError, Level};
-use crate::clarity::representations::Span;
 use crate::repl::ast::parser::lexer::error::LexerError;
 use crate::repl::ast::parser::lexer::token::Token;
+use clarity::vm::diagnostic::{DiagnosableError, Level};
+use clarity::vm::representations::Span;
 
 #[derive(Debug, PartialEq)]
 pub enum ParserError {
@@ -67,7 +67,7 @@ impl DiagnosableError for ParserError {
         None
     }
 
-    fn level(&self) -> crate::clarity::diagnostic::Level {
+    fn level(&self) -> clarity::vm::diagnostic::Level {
         use self::ParserError::*;
         match self {
             NoteToMatchThis(_) => Level::Note,
@@ -1,4 +1,4 @@
-use crate::clarity::{
+use clarity::vm::{
     diagnostic::{DiagnosableError, Level},
     representations::Span,
 };
@@ -66,7 +66,7 @@ impl DiagnosableError for LexerError {
         None
     }
 
-    fn level(&self) -> crate::clarity::diagnostic::Level {
+    fn level(&self) -> clarity::vm::diagnostic::Level {
         use self::LexerError::*;
         match self {
             NoteToMatchThis(_) => Level::Note,
@@ -1,7 +1,7 @@
 pub mod error;
 pub mod token;
 
-use crate::clarity::{
+use clarity::vm::{
     diagnostic::{DiagnosableError, Level},
     representations::Span,
 };
@@ -726,7 +726,7 @@ impl<'a> Lexer<'a> {
 
 #[cfg(test)]
 mod tests {
-    use crate::clarity::types::UTF8Data;
+    use clarity::vm::types::UTF8Data;
 
     use super::*;
 
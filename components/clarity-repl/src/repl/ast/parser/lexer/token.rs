@@ -1,7 +1,7 @@
 use std::fmt::Display;
 
 use super::error::LexerError;
-use crate::clarity::representations::Span;
+use clarity::vm::representations::Span;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Token {
@@ -5,16 +5,16 @@ use std::convert::TryFrom;
 
 use self::error::{ParserError, PlacedError};
 use self::lexer::error::LexerError;
-use crate::clarity::diagnostic::{DiagnosableError, Diagnostic, Level};
-use crate::clarity::representations::{
+use crate::repl::ast::parser::lexer::token::{PlacedToken, Token};
+use crate::repl::ast::parser::lexer::Lexer;
+use clarity::vm::diagnostic::{DiagnosableError, Diagnostic, Level};
+use clarity::vm::representations::{
     ClarityName, ContractName, PreSymbolicExpression, PreSymbolicExpressionType, Span,
 };
-use crate::clarity::types::{
+use clarity::vm::types::{
     CharType, PrincipalData, QualifiedContractIdentifier, SequenceData, StandardPrincipalData,
     TraitIdentifier, UTF8Data, Value,
 };
-use crate::repl::ast::parser::lexer::token::{PlacedToken, Token};
-use crate::repl::ast::parser::lexer::Lexer;
 
 pub struct Parser<'a> {
     lexer: Lexer<'a>,
@@ -668,7 +668,7 @@ pub fn parse(input: &str) -> (Vec<PreSymbolicExpression>, Vec<Diagnostic>, bool)
 
 #[cfg(test)]
 mod tests {
-    use crate::clarity::{
+    use clarity::vm::{
         diagnostic::Level,
         types::{
             ASCIIData, CharType, PrincipalData, SequenceData, StandardPrincipalData, UTF8Data,
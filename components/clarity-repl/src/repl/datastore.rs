@@ -13,15 +13,18 @@ use clarity::util::hash::Sha512Trunc256Sum;
 use clarity::vm::analysis::AnalysisDatabase;
 use clarity::vm::database::BurnStateDB;
 use clarity::vm::database::{ClarityBackingStore, ClarityDatabase, HeadersDB};
+use clarity::vm::database::{ClaritySerializable, STXBalance, StoreType};
 use clarity::vm::errors::{
     CheckErrors, IncomparableError, InterpreterError, InterpreterResult as Result, RuntimeErrorType,
 };
-use clarity::vm::types::QualifiedContractIdentifier;
 use clarity::vm::types::TupleData;
+use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
 use clarity::vm::EvalHook;
 use clarity::vm::StacksEpoch;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
 use std::hash::Hash;
+use std::sync::Arc;
 
 #[derive(Clone, Debug)]
 pub struct Datastore {
@@ -32,6 +35,55 @@ pub struct Datastore {
     current_chain_tip: StacksBlockId,
     chain_height: u32,
     height_at_chain_tip: HashMap<StacksBlockId, u32>,
+    remote: Option<Arc<dyn RemoteDatastore>>,
+    forked_contracts: BTreeSet<QualifiedContractIdentifier>,
+    // Sources of the contracts looked up on the remote chain, `None` for the
+    // contracts missing from it
+    remote_sources: HashMap<QualifiedContractIdentifier, Option<String>>,
+    // Height at which each contract was deployed, the metadata not being
+    // stored per block
+    contract_heights: HashMap<String, u32>,
+}
+
+/// State of a remote chain, read by a forked `Datastore` when a key is missing
+/// from its own store. The values are serialized as in the backing store.
+pub trait RemoteDatastore: fmt::Debug + Send + Sync {
+    fn get_data_var(
+        &self,
+        contract: &QualifiedContractIdentifier,
+        var_name: &str,
+    ) -> Option<String>;
+    fn get_map_entry(
+        &self,
+        contract: &QualifiedContractIdentifier,
+        map_name: &str,
+        key: &str,
+    ) -> Option<String>;
+    fn get_account(&self, principal: &PrincipalData) -> Option<RemoteAccount>;
+    fn get_contract_source(&self, contract: &QualifiedContractIdentifier) -> Option<String>;
+}
+
+#[derive(Clone, Debug)]
+pub struct RemoteAccount {
+    pub balance: u128,
+    pub nonce: u64,
+}
+
+/// Copy of the state of a `Datastore`, which can be serialized to save the
+/// chain of a session. The block ids are encoded in hex.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DatastoreSnapshot {
+    store: BTreeMap<String, BTreeMap<String, String>>,
+    block_id_lookup: BTreeMap<String, String>,
+    metadata: Vec<(String, String, String)>,
+    open_chain_tip: String,
+    current_chain_tip: String,
+    chain_height: u32,
+    height_at_chain_tip: BTreeMap<String, u32>,
+    #[serde(default)]
+    forked_contracts: Vec<String>,
+    #[serde(default)]
+    contract_heights: BTreeMap<String, u32>,
 }
 
 #[derive(Clone, Debug)]
@@ -150,6 +202,10 @@ impl Datastore {
             current_chain_tip: id,
             chain_height: 0,
             height_at_chain_tip: id_height_map,
+            remote: None,
+            forked_contracts: BTreeSet::new(),
+            remote_sources: HashMap::new(),
+            contract_heights: HashMap::new(),
         }
     }
 
@@ -174,6 +230,231 @@ impl Datastore {
         self.current_chain_tip = self.open_chain_tip;
         self.chain_height
     }
+
+    pub fn snapshot(&self) -> DatastoreSnapshot {
+        DatastoreSnapshot {
+            store: self
+                .store
+                .iter()
+                .map(|(id, map)| (id.to_hex(), map.clone().into_iter().collect()))
+                .collect(),
+            block_id_lookup: self
+                .block_id_lookup
+                .iter()
+                .map(|(id, lookup_id)| (id.to_hex(), lookup_id.to_hex()))
+                .collect(),
+            metadata: self
+                .metadata
+                .iter()
+                .map(|((contract, key), value)| (contract.clone(), key.clone(), value.clone()))
+                .collect(),
+            open_chain_tip: self.open_chain_tip.to_hex(),
+            current_chain_tip: self.current_chain_tip.to_hex(),
+            chain_height: self.chain_height,
+            height_at_chain_tip: self
+                .height_at_chain_tip
+                .iter()
+                .map(|(id, height)| (id.to_hex(), *height))
+                .collect(),
+            forked_contracts: self
+                .forked_contracts
+                .iter()
+                .map(|contract| contract.to_string())
+                .collect(),
+            contract_heights: self.contract_heights.clone().into_iter().collect(),
+        }
+    }
+
+    pub fn restore(snapshot: &DatastoreSnapshot) -> std::result::Result<Datastore, String> {
+        let mut store = HashMap::new();
+        for (id, map) in snapshot.store.iter() {
+            store.insert(parse_block_id(id)?, map.clone().into_iter().collect());
+        }
+        let mut block_id_lookup = HashMap::new();
+        for (id, lookup_id) in snapshot.block_id_lookup.iter() {
+            block_id_lookup.insert(parse_block_id(id)?, parse_block_id(lookup_id)?);
+        }
+        let mut height_at_chain_tip = HashMap::new();
+        for (id, height) in snapshot.height_at_chain_tip.iter() {
+            height_at_chain_tip.insert(parse_block_id(id)?, *height);
+        }
+        let mut forked_contracts = BTreeSet::new();
+        for contract in snapshot.forked_contracts.iter() {
+            forked_contracts.insert(
+                QualifiedContractIdentifier::parse(contract)
+                    .map_err(|e| format!("invalid contract {}: {:?}", contract, e))?,
+            );
+        }
+        Ok(Datastore {
+            store,
+            block_id_lookup,
+            metadata: snapshot
+                .metadata
+                .iter()
+                .map(|(contract, key, value)| ((contract.clone(), key.clone()), value.clone()))
+                .collect(),
+            open_chain_tip: parse_block_id(&snapshot.open_chain_tip)?,
+            current_chain_tip: parse_block_id(&snapshot.current_chain_tip)?,
+            chain_height: snapshot.chain_height,
+            height_at_chain_tip,
+            remote: None,
+            forked_contracts,
+            remote_sources: HashMap::new(),
+            contract_heights: snapshot.contract_heights.clone().into_iter().collect(),
+        })
+    }
+
+    /// Reset the chain to the end of the block at `height`, discarding the
+    /// blocks after it and the contracts deployed in them.
+    pub fn rewind(&mut self, height: u32) -> std::result::Result<(), String> {
+        if height > self.chain_height {
+            return Err(format!(
+                "unable to rewind to height {}, the chain tip is at height {}",
+                height, self.chain_height
+            ));
+        }
+        for discarded in (height + 1)..=self.chain_height {
+            let id = height_to_id(discarded);
+            self.store.remove(&id);
+            self.block_id_lookup.remove(&id);
+            self.height_at_chain_tip.remove(&id);
+        }
+        self.chain_height = height;
+        self.open_chain_tip = height_to_id(height);
+        self.current_chain_tip = self.open_chain_tip;
+
+        let discarded: BTreeSet<String> = self
+            .contract_heights
+            .iter()
+            .filter(|(_, deployed_at)| **deployed_at > height)
+            .map(|(contract, _)| contract.clone())
+            .collect();
+        self.contract_heights
+            .retain(|contract, _| !discarded.contains(contract));
+        self.metadata
+            .retain(|(contract, _), _| !discarded.contains(contract));
+        self.forked_contracts
+            .retain(|contract| !discarded.contains(&contract.to_string()));
+        Ok(())
+    }
+
+    /// Read the keys missing from the store from `remote`. Only the data of
+    /// the contracts marked with `fork_contract` and the STX accounts are read
+    /// from the remote chain.
+    pub fn set_remote(&mut self, remote: Arc<dyn RemoteDatastore>) {
+        self.remote = Some(remote);
+        self.remote_sources.clear();
+    }
+
+    pub fn get_remote(&self) -> Option<Arc<dyn RemoteDatastore>> {
+        self.remote.clone()
+    }
+
+    /// Source of `contract` on the remote chain. The lookups, including the
+    /// ones of the contracts missing from the remote chain, are cached.
+    pub fn get_remote_contract_source(
+        &mut self,
+        contract: &QualifiedContractIdentifier,
+    ) -> Option<String> {
+        let remote = self.remote.as_ref()?;
+        self.remote_sources
+            .entry(contract.clone())
+            .or_insert_with(|| remote.get_contract_source(contract))
+            .clone()
+    }
+
+    pub fn has_contract(&self, contract: &QualifiedContractIdentifier) -> bool {
+        let key = ClarityDatabase::make_metadata_key(StoreType::Contract, "contract");
+        self.metadata.contains_key(&(contract.to_string(), key))
+    }
+
+    pub fn get_contract_src(&self, contract: &QualifiedContractIdentifier) -> Option<String> {
+        let key = ClarityDatabase::make_metadata_key(StoreType::Contract, "contract-src");
+        self.metadata.get(&(contract.to_string(), key)).cloned()
+    }
+
+    /// Discard the data written by the initialization of `contract`, so that
+    /// its data variables and maps are read from the remote chain instead.
+    pub fn fork_contract(&mut self, contract: &QualifiedContractIdentifier) {
+        let prefixes = [
+            format!("vm::{}::{}::", contract, StoreType::DataMap as u8),
+            format!("vm::{}::{}::", contract, StoreType::Variable as u8),
+        ];
+        if let Some(lookup_id) = self.block_id_lookup.get(&self.open_chain_tip) {
+            if let Some(map) = self.store.get_mut(lookup_id) {
+                map.retain(|key, _| !prefixes.iter().any(|prefix| key.starts_with(prefix)));
+            }
+        }
+        self.forked_contracts.insert(contract.clone());
+    }
+
+    /// Serialized keys of the entries of a map at the current chain tip
+    pub fn get_map_keys(
+        &self,
+        contract: &QualifiedContractIdentifier,
+        map_name: &str,
+    ) -> Vec<String> {
+        let prefix = format!(
+            "vm::{}::{}::{}::",
+            contract,
+            StoreType::DataMap as u8,
+            map_name
+        );
+        let mut keys: Vec<String> = self
+            .block_id_lookup
+            .get(&self.current_chain_tip)
+            .and_then(|lookup_id| self.store.get(lookup_id))
+            .map(|map| {
+                map.keys()
+                    .filter_map(|key| key.strip_prefix(&prefix))
+                    .map(|key| key.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        keys.sort();
+        keys
+    }
+
+    fn get_remote_value(&self, key: &str) -> Option<String> {
+        let remote = self.remote.as_ref()?;
+        if let Some(key) = key.strip_prefix("vm::") {
+            let parts: Vec<&str> = key.split("::").collect();
+            let contract = QualifiedContractIdentifier::parse(parts[0]).ok()?;
+            if !self.forked_contracts.contains(&contract) {
+                return None;
+            }
+            let store_type: u8 = parts.get(1)?.parse().ok()?;
+            match parts[2..] {
+                [var_name] if store_type == StoreType::Variable as u8 => {
+                    remote.get_data_var(&contract, var_name)
+                }
+                [map_name, map_key] if store_type == StoreType::DataMap as u8 => {
+                    remote.get_map_entry(&contract, map_name, map_key)
+                }
+                _ => None,
+            }
+        } else if let Some(key) = key.strip_prefix("vm-account::") {
+            let (principal, store_type) = key.rsplit_once("::")?;
+            let principal = PrincipalData::parse(principal).ok()?;
+            let store_type: u8 = store_type.parse().ok()?;
+            // The locked STX of the remote accounts are not carried over
+            if store_type == StoreType::STXBalance as u8 {
+                let account = remote.get_account(&principal)?;
+                Some(STXBalance::initial(account.balance).serialize())
+            } else if store_type == StoreType::Nonce as u8 {
+                let account = remote.get_account(&principal)?;
+                Some(account.nonce.serialize())
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    }
+}
+
+fn parse_block_id(hex: &str) -> std::result::Result<StacksBlockId, String> {
+    StacksBlockId::from_hex(hex).map_err(|e| format!("invalid block id {}: {:?}", hex, e))
 }
 
 impl ClarityBackingStore for Datastore {
@@ -191,7 +472,10 @@ impl ClarityBackingStore for Datastore {
             .expect("Could not find current chain tip in block_id_lookup map");
 
         if let Some(map) = self.store.get(lookup_id) {
-            map.get(key).map(|v| v.clone())
+            match map.get(key) {
+                Some(value) => Some(value.clone()),
+                None => self.get_remote_value(key),
+            }
         } else {
             panic!("Block does not exist for current chain tip");
         }
@@ -241,6 +525,10 @@ impl ClarityBackingStore for Datastore {
     fn insert_metadata(&mut self, contract: &QualifiedContractIdentifier, key: &str, value: &str) {
         // let bhh = self.get_open_chain_tip();
         // self.get_side_store().insert_metadata(&bhh, &contract.to_string(), key, value)
+        if key == ClarityDatabase::make_metadata_key(StoreType::Contract, "contract") {
+            self.contract_heights
+                .insert(contract.to_string(), self.chain_height);
+        }
         self.metadata
             .insert((contract.to_string(), key.to_string()), value.to_string());
     }
@@ -346,6 +634,13 @@ impl BurnDatastore {
         self.open_chain_tip = height_to_id(self.chain_height);
         self.current_chain_tip = self.open_chain_tip;
     }
+
+    /// Rebuild the chain up to `height`. The blocks are derived from their
+    /// height, so this is all that needs to be saved to restore the chain.
+    pub fn set_chain_height(&mut self, height: u32) {
+        *self = BurnDatastore::new(self.constants.clone());
+        self.advance_chain_tip(height);
+    }
 }
 
 impl HeadersDB for BurnDatastore {
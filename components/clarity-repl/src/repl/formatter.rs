@@ -0,0 +1,579 @@
+use crate::repl::ast::parser;
+use crate::repl::ast::parser::lexer::token::{PlacedToken, Token};
+use crate::repl::ast::parser::lexer::Lexer;
+use clarity::vm::diagnostic::Diagnostic;
+use clarity::vm::representations::Span;
+
+#[derive(Debug, Clone)]
+pub struct FormatterSettings {
+    pub indentation: usize,
+    pub max_line_length: usize,
+}
+
+impl Default for FormatterSettings {
+    fn default() -> Self {
+        Self {
+            indentation: 2,
+            max_line_length: 80,
+        }
+    }
+}
+
+/// Pretty-print a contract. The source is checked with the parser first: a contract that
+/// can not be parsed is left untouched and the diagnostics of the parser are returned.
+pub fn format_source(
+    source: &str,
+    settings: &FormatterSettings,
+) -> Result<String, Vec<Diagnostic>> {
+    let (_, diagnostics, success) = parser::parse(source);
+    if !success {
+        return Err(diagnostics);
+    }
+
+    let mut lexer = Lexer::new(source);
+    let mut tokens = vec![];
+    loop {
+        let token = lexer.read_token();
+        if token.token == Token::Eof {
+            break;
+        }
+        tokens.push(token);
+    }
+
+    let mut builder = TreeBuilder {
+        source: SourceText::new(source),
+        tokens,
+        next_token: 0,
+    };
+    let nodes = builder.build_nodes();
+
+    let formatter = Formatter { settings };
+    Ok(formatter.format_top_level(&nodes))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    /// Identifiers and literals, kept as written in the source
+    Atom(String),
+    /// Comments, `trailing` when following some code on the same line
+    Comment {
+        text: String,
+        trailing: bool,
+    },
+    /// One or more empty lines, collapsed into one
+    BlankLine,
+    List(Vec<Node>),
+    Tuple(Vec<Node>),
+}
+
+struct SourceText {
+    chars: Vec<char>,
+    line_starts: Vec<usize>,
+}
+
+impl SourceText {
+    fn new(source: &str) -> SourceText {
+        let chars: Vec<char> = source.chars().collect();
+        let mut line_starts = vec![0];
+        for (i, ch) in chars.iter().enumerate() {
+            if *ch == '\n' {
+                line_starts.push(i + 1);
+            }
+        }
+        SourceText { chars, line_starts }
+    }
+
+    fn slice(&self, span: &Span) -> String {
+        let start = self.line_starts[span.start_line as usize - 1] + span.start_column as usize - 1;
+        let end = self.line_starts[span.end_line as usize - 1] + span.end_column as usize;
+        self.chars[start..end.min(self.chars.len())]
+            .iter()
+            .collect()
+    }
+}
+
+struct TreeBuilder {
+    source: SourceText,
+    tokens: Vec<PlacedToken>,
+    next_token: usize,
+}
+
+impl TreeBuilder {
+    // Collect the nodes until the closing token of the current list / tuple
+    // (or the end of the source at the top level).
+    fn build_nodes(&mut self) -> Vec<Node> {
+        let mut nodes = vec![];
+        let mut newlines = 0;
+        // Principals and trait references are made of several tokens (`'ST1.contract`)
+        let mut glue = false;
+
+        while self.next_token < self.tokens.len() {
+            let placed_token = self.tokens[self.next_token].clone();
+            self.next_token += 1;
+
+            match placed_token.token {
+                Token::Whitespace => {
+                    let count = self.source.slice(&placed_token.span).matches('\n').count();
+                    if count > 1 && !nodes.is_empty() {
+                        nodes.push(Node::BlankLine);
+                    }
+                    newlines += count;
+                    glue = false;
+                }
+                Token::Comment(text) => {
+                    let trailing = newlines == 0 && !nodes.is_empty();
+                    nodes.push(Node::Comment {
+                        text: text.trim_end().to_string(),
+                        trailing,
+                    });
+                    newlines = 0;
+                    glue = false;
+                }
+                Token::Lparen => {
+                    nodes.push(Node::List(self.build_nodes()));
+                    newlines = 0;
+                    glue = false;
+                }
+                Token::Lbrace => {
+                    nodes.push(Node::Tuple(self.build_nodes()));
+                    newlines = 0;
+                    glue = false;
+                }
+                Token::Rparen | Token::Rbrace => break,
+                // Commas are re-inserted when printing tuples
+                Token::Comma => glue = false,
+                Token::Colon => {
+                    match nodes.last_mut() {
+                        Some(Node::Atom(key)) => key.push(':'),
+                        _ => nodes.push(Node::Atom(":".to_string())),
+                    }
+                    glue = false;
+                }
+                _ => {
+                    let text = self.source.slice(&placed_token.span);
+                    match nodes.last_mut() {
+                        Some(Node::Atom(atom)) if glue => atom.push_str(&text),
+                        _ => nodes.push(Node::Atom(text)),
+                    }
+                    newlines = 0;
+                    glue = true;
+                }
+            }
+        }
+
+        while let Some(Node::BlankLine) = nodes.last() {
+            nodes.pop();
+        }
+        nodes
+    }
+}
+
+fn format_comment(text: &str) -> String {
+    if text.is_empty() || text.starts_with(';') {
+        format!(";;{}", text)
+    } else {
+        format!(";; {}", text)
+    }
+}
+
+fn is_code(node: &Node) -> bool {
+    match node {
+        Node::Atom(_) | Node::List(_) | Node::Tuple(_) => true,
+        Node::Comment { .. } | Node::BlankLine => false,
+    }
+}
+
+fn width(text: &str) -> usize {
+    text.chars().count()
+}
+
+struct Formatter<'a> {
+    settings: &'a FormatterSettings,
+}
+
+impl<'a> Formatter<'a> {
+    fn format_top_level(&self, nodes: &[Node]) -> String {
+        let mut output = String::new();
+        for node in nodes.iter() {
+            match node {
+                Node::BlankLine => output.push('\n'),
+                Node::Comment {
+                    text,
+                    trailing: true,
+                } => {
+                    output.pop();
+                    output.push(' ');
+                    output.push_str(&format_comment(text));
+                    output.push('\n');
+                }
+                node => {
+                    output.push_str(&self.format_node(node, 0));
+                    output.push('\n');
+                }
+            }
+        }
+        output
+    }
+
+    // Format a node starting at `column`. The lines following the first one are indented.
+    fn format_node(&self, node: &Node, column: usize) -> String {
+        if let Some(flat) = self.format_flat(node) {
+            if column + width(&flat) <= self.settings.max_line_length {
+                return flat;
+            }
+        }
+        match node {
+            Node::Atom(atom) => atom.clone(),
+            Node::Comment { text, .. } => format_comment(text),
+            Node::BlankLine => String::new(),
+            Node::List(children) => self.format_list(children, column),
+            Node::Tuple(children) => self.format_tuple(children, column),
+        }
+    }
+
+    // Format a node on a single line, when possible.
+    fn format_flat(&self, node: &Node) -> Option<String> {
+        match node {
+            Node::Atom(atom) if !atom.contains('\n') => Some(atom.clone()),
+            Node::List(children) => {
+                if let Some(Node::Atom(head)) = children.first() {
+                    if is_function_definition(head) {
+                        return None;
+                    }
+                }
+                let children = children
+                    .iter()
+                    .map(|child| self.format_flat(child))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(format!("({})", children.join(" ")))
+            }
+            Node::Tuple(children) => {
+                let mut entries = vec![];
+                for entry in get_tuple_entries(children).into_iter() {
+                    match entry {
+                        TupleEntry::KeyValue(key, Some(value)) => {
+                            entries.push(format!("{} {}", key, self.format_flat(value)?))
+                        }
+                        _ => return None,
+                    }
+                }
+                if entries.is_empty() {
+                    return Some("{}".to_string());
+                }
+                Some(format!("{{ {} }}", entries.join(", ")))
+            }
+            _ => None,
+        }
+    }
+
+    fn format_list(&self, children: &[Node], column: usize) -> String {
+        let mut output = String::from("(");
+
+        let (head, args) = match children.split_first() {
+            Some((Node::Atom(head), args)) => (head, args),
+            Some((first, rest)) => {
+                // Lists of lists, such as `let` bindings: the items are aligned
+                output.push_str(&self.format_node(first, column + 1));
+                self.push_lines(&mut output, rest, column + 1);
+                self.close(&mut output, children, ')', column);
+                return output;
+            }
+            None => return "()".to_string(),
+        };
+
+        output.push_str(head);
+        let args_column = column + 1 + width(head) + 1;
+        let body_column = column + self.settings.indentation;
+
+        // Arguments kept on the same line as the function name
+        let inline_args = match head.as_str() {
+            "begin" => 0,
+            _ => args.iter().take(1).take_while(|arg| is_code(arg)).count(),
+        };
+        for arg in args[..inline_args].iter() {
+            output.push(' ');
+            if is_function_definition(head) {
+                output.push_str(&self.format_signature(arg, args_column));
+            } else {
+                output.push_str(&self.format_node(arg, args_column));
+            }
+        }
+
+        let rest = &args[inline_args..];
+        match head.as_str() {
+            "match" if rest.iter().all(is_code) && (rest.len() == 3 || rest.len() == 4) => {
+                // (match opt some-binding some-branch none-branch)
+                // (match resp ok-binding ok-branch err-binding err-branch)
+                let mut branches = rest;
+                while !branches.is_empty() {
+                    output.push('\n');
+                    output.push_str(&" ".repeat(body_column));
+                    if branches.len() == 1 {
+                        output.push_str(&self.format_node(&branches[0], body_column));
+                        break;
+                    }
+                    let binding = self.format_node(&branches[0], body_column);
+                    let branch_column = body_column + width(&binding) + 1;
+                    let branch = &branches[1];
+                    match self.format_flat(branch) {
+                        Some(flat)
+                            if branch_column + width(&flat) <= self.settings.max_line_length =>
+                        {
+                            output.push_str(&format!("{} {}", binding, flat));
+                        }
+                        _ => {
+                            let branch_column = body_column + self.settings.indentation;
+                            output.push_str(&binding);
+                            output.push('\n');
+                            output.push_str(&" ".repeat(branch_column));
+                            output.push_str(&self.format_node(branch, branch_column));
+                        }
+                    }
+                    branches = &branches[2..];
+                }
+            }
+            _ => self.push_lines(&mut output, rest, body_column),
+        }
+
+        self.close(&mut output, children, ')', column);
+        output
+    }
+
+    // The parameters of a function are aligned with the first one
+    fn format_signature(&self, signature: &Node, column: usize) -> String {
+        let children = match signature {
+            Node::List(children) => children,
+            _ => return self.format_node(signature, column),
+        };
+        if let Some(flat) = self.format_flat(signature) {
+            if column + width(&flat) <= self.settings.max_line_length {
+                return flat;
+            }
+        }
+        let (name, params) = match children.split_first() {
+            Some((Node::Atom(name), params)) if params.first().map_or(false, is_code) => {
+                (name, params)
+            }
+            _ => return self.format_node(signature, column),
+        };
+        let params_column = column + 1 + width(name) + 1;
+        let mut output = format!("({} ", name);
+        output.push_str(&self.format_node(&params[0], params_column));
+        self.push_lines(&mut output, &params[1..], params_column);
+        self.close(&mut output, children, ')', column);
+        output
+    }
+
+    fn format_tuple(&self, children: &[Node], column: usize) -> String {
+        let entries_column = column + self.settings.indentation;
+        let entries = get_tuple_entries(children);
+        let mut remaining = entries
+            .iter()
+            .filter(|entry| matches!(entry, TupleEntry::KeyValue(..)))
+            .count();
+
+        let mut output = String::from("{");
+        for entry in entries.iter() {
+            match entry {
+                TupleEntry::KeyValue(key, value) => {
+                    output.push('\n');
+                    output.push_str(&" ".repeat(entries_column));
+                    output.push_str(key);
+                    if let Some(value) = value {
+                        let value_column = entries_column + width(key) + 1;
+                        output.push(' ');
+                        output.push_str(&self.format_node(value, value_column));
+                    }
+                    remaining -= 1;
+                    if remaining > 0 {
+                        output.push(',');
+                    }
+                }
+                TupleEntry::Other(node) => {
+                    self.push_lines(&mut output, std::slice::from_ref(*node), entries_column)
+                }
+            }
+        }
+        output.push('\n');
+        output.push_str(&" ".repeat(column));
+        output.push('}');
+        output
+    }
+
+    // Append each node on its own line, trailing comments excepted.
+    fn push_lines(&self, output: &mut String, nodes: &[Node], column: usize) {
+        for node in nodes.iter() {
+            match node {
+                Node::BlankLine => output.push('\n'),
+                Node::Comment {
+                    text,
+                    trailing: true,
+                } => {
+                    output.push(' ');
+                    output.push_str(&format_comment(text));
+                }
+                node => {
+                    output.push('\n');
+                    output.push_str(&" ".repeat(column));
+                    output.push_str(&self.format_node(node, column));
+                }
+            }
+        }
+    }
+
+    // Comments run until the end of the line: the closing delimiter can't follow them.
+    fn close(&self, output: &mut String, children: &[Node], delimiter: char, column: usize) {
+        if let Some(Node::Comment { .. }) = children.last() {
+            output.push('\n');
+            output.push_str(&" ".repeat(column));
+        }
+        output.push(delimiter);
+    }
+}
+
+fn is_function_definition(name: &str) -> bool {
+    match name {
+        "define-public" | "define-private" | "define-read-only" => true,
+        _ => false,
+    }
+}
+
+enum TupleEntry<'a> {
+    KeyValue(&'a str, Option<&'a Node>),
+    Other(&'a Node),
+}
+
+// Pair the keys (`key:`) of a tuple with their values, comments in between
+// a key and its value are moved after the value.
+fn get_tuple_entries(children: &[Node]) -> Vec<TupleEntry<'_>> {
+    let mut entries = vec![];
+    let mut pending = vec![];
+    let mut key = None;
+    for child in children.iter() {
+        match (child, key) {
+            (Node::Atom(atom), None) if atom.ends_with(':') => key = Some(atom.as_str()),
+            (node, Some(k)) if is_code(node) => {
+                entries.push(TupleEntry::KeyValue(k, Some(node)));
+                entries.extend(pending.drain(..).map(TupleEntry::Other));
+                key = None;
+            }
+            (node, Some(_)) => pending.push(node),
+            (node, None) => entries.push(TupleEntry::Other(node)),
+        }
+    }
+    if let Some(k) = key {
+        entries.push(TupleEntry::KeyValue(k, None));
+    }
+    entries.extend(pending.drain(..).map(TupleEntry::Other));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(source: &str) -> String {
+        let settings = FormatterSettings::default();
+        let formatted = format_source(source, &settings).expect("unable to format source");
+        // Formatting should be idempotent
+        assert_eq!(
+            format_source(&formatted, &settings).expect("unable to format source"),
+            formatted
+        );
+        formatted
+    }
+
+    #[test]
+    fn test_format_atoms_and_lists() {
+        assert_eq!(format("(+   1 u2)"), "(+ 1 u2)\n");
+        assert_eq!(
+            format("(define-constant owner    'ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.foo)"),
+            "(define-constant owner 'ST1PQHQKV0RJXZFY1DGX8MNSNYVE3VGZJSRTPGZGM.foo)\n"
+        );
+        assert_eq!(
+            format("(contract-call? .token  transfer  u1 tx-sender)"),
+            "(contract-call? .token transfer u1 tx-sender)\n"
+        );
+        assert_eq!(
+            format("(define-data-var name (string-ascii 10)  \"hello\")\n\n\n\n(define-map m uint u\"world\")"),
+            "(define-data-var name (string-ascii 10) \"hello\")\n\n(define-map m uint u\"world\")\n"
+        );
+    }
+
+    #[test]
+    fn test_format_function_definitions() {
+        let source = "(define-public (increment (step uint)) (let ((new-val (+ step (var-get counter)))) (var-set counter new-val) (ok new-val)))";
+        assert_eq!(
+            format(source),
+            r#"(define-public (increment (step uint))
+  (let ((new-val (+ step (var-get counter))))
+    (var-set counter new-val)
+    (ok new-val)))
+"#
+        );
+    }
+
+    #[test]
+    fn test_format_long_forms() {
+        let source = "(define-read-only (get-balance (account principal)) (match (map-get? balances { account: account, token-id: u1 }) balance (ok (get amount balance)) (err ERR_UNKNOWN_ACCOUNT)))";
+        assert_eq!(
+            format(source),
+            r#"(define-read-only (get-balance (account principal))
+  (match (map-get? balances { account: account, token-id: u1 })
+    balance (ok (get amount balance))
+    (err ERR_UNKNOWN_ACCOUNT)))
+"#
+        );
+
+        let source = "(define-private (foo) (let ((first-value (some-very-long-function-name u1 u2 u3)) (second-value (another-long-function-name first-value))) (ok second-value)))";
+        assert_eq!(
+            format(source),
+            r#"(define-private (foo)
+  (let ((first-value (some-very-long-function-name u1 u2 u3))
+        (second-value (another-long-function-name first-value)))
+    (ok second-value)))
+"#
+        );
+
+        let source = "(define-public (foo) (ok { first-key: (some-long-function-name u1), second-key: (another-long-function-name u2) }))";
+        assert_eq!(
+            format(source),
+            r#"(define-public (foo)
+  (ok {
+        first-key: (some-long-function-name u1),
+        second-key: (another-long-function-name u2)
+      }))
+"#
+        );
+    }
+
+    #[test]
+    fn test_format_comments() {
+        let source = ";; counter\n;;; header\n(define-data-var counter uint u1) ;; trailing\n\n(define-public (increment)\n  ;; increment the counter\n  (begin (var-set counter (+ (var-get counter) u1)) ;; update\n (ok true)))";
+        assert_eq!(
+            format(source),
+            r#";; counter
+;;; header
+(define-data-var counter uint u1) ;; trailing
+
+(define-public (increment)
+  ;; increment the counter
+  (begin
+    (var-set counter (+ (var-get counter) u1)) ;; update
+    (ok true)))
+"#
+        );
+
+        let source = "(define-public (foo)\n  (ok true) ;; done\n)";
+        assert_eq!(
+            format(source),
+            "(define-public (foo)\n  (ok true) ;; done\n)\n"
+        );
+    }
+
+    #[test]
+    fn test_format_invalid_source() {
+        let settings = FormatterSettings::default();
+        assert!(format_source("(define-data-var counter uint u1", &settings).is_err());
+    }
+}
@@ -6,7 +6,7 @@ use crate::analysis::ast_dependency_detector::{ASTDependencyDetector, Dependency
 use crate::analysis::coverage::TestCoverageReport;
 use crate::analysis::{self, AnalysisPass as REPLAnalysisPass};
 use crate::repl::datastore::BurnDatastore;
-use crate::repl::datastore::Datastore;
+use crate::repl::datastore::{Datastore, DatastoreSnapshot};
 use crate::repl::Settings;
 use crate::utils;
 use clarity::consts::CHAIN_ID_TESTNET;
@@ -25,7 +25,7 @@ use clarity::vm::costs::cost_functions::ClarityCostFunction;
 use clarity::vm::costs::{runtime_cost, ExecutionCost, LimitedCostTracker};
 use clarity::vm::database::ClarityDatabase;
 use clarity::vm::diagnostic::{Diagnostic, Level};
-use clarity::vm::errors::Error;
+use clarity::vm::errors::{Error, RuntimeErrorType};
 use clarity::vm::representations::SymbolicExpressionType::{Atom, List};
 use clarity::vm::representations::{Span, SymbolicExpression};
 use clarity::vm::types::{
@@ -54,9 +54,38 @@ pub struct ClarityInterpreter {
     tx_sender: StandardPrincipalData,
     accounts: BTreeSet<String>,
     tokens: BTreeMap<String, BTreeMap<String, u128>>,
+    // Assets maps at the end of the blocks in which they changed, to restore
+    // them when rewinding the chain
+    assets_history: BTreeMap<u32, AssetsMaps>,
+    // Active epoch, and Clarity version of the contracts deployed next
+    epoch: StacksEpochId,
+    clarity_version: ClarityVersion,
+    // Analysis passes of the diagnostics of the last run of each contract
+    diagnostic_passes: HashMap<QualifiedContractIdentifier, Vec<Option<&'static str>>>,
     repl_settings: Settings,
 }
 
+// Diagnostic along with the name of the analysis pass which reported it, if any
+type TaggedDiagnostic = (Option<&'static str>, Diagnostic);
+
+type AssetsMaps = (BTreeSet<String>, BTreeMap<String, BTreeMap<String, u128>>);
+
+/// State of the chain and of the assets maps of an interpreter, see
+/// `ClarityInterpreter::snapshot`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InterpreterSnapshot {
+    datastore: DatastoreSnapshot,
+    tx_sender: String,
+    accounts: BTreeSet<String>,
+    tokens: BTreeMap<String, BTreeMap<String, u128>>,
+    #[serde(default)]
+    assets_history: BTreeMap<u32, AssetsMaps>,
+    #[serde(default)]
+    epoch: Option<StacksEpochId>,
+    #[serde(default)]
+    clarity_version: Option<ClarityVersion>,
+}
+
 #[derive(Debug)]
 pub struct Txid(pub [u8; 32]);
 
@@ -123,6 +152,10 @@ impl ClarityInterpreter {
             tx_sender,
             accounts,
             tokens,
+            assets_history: BTreeMap::new(),
+            epoch: repl_settings.epoch,
+            clarity_version: repl_settings.clarity_version,
+            diagnostic_passes: HashMap::new(),
             repl_settings,
             burn_datastore: BurnDatastore::new(constants),
         }
@@ -135,83 +168,81 @@ impl ClarityInterpreter {
         cost_track: bool,
         eval_hooks: Option<Vec<&mut dyn EvalHook>>,
     ) -> Result<ExecutionResult, Vec<Diagnostic>> {
-        let (mut ast, mut diagnostics, success) =
+        let (ast, diagnostics, success) =
             self.build_ast(contract_identifier.clone(), snippet.clone());
-        let (annotations, mut annotation_diagnostics) = self.collect_annotations(&ast, &snippet);
-        diagnostics.append(&mut annotation_diagnostics);
-
-        let (analysis, mut analysis_diagnostics) =
-            match self.run_analysis(contract_identifier.clone(), &mut ast, &annotations) {
-                Ok((analysis, diagnostics)) => (analysis, diagnostics),
-                Err((_, Some(diagnostic), _)) => {
-                    diagnostics.push(diagnostic);
-                    return Err(diagnostics);
-                }
-                Err(_) => return Err(diagnostics),
-            };
-        diagnostics.append(&mut analysis_diagnostics);
-
-        // If the parser or analysis failed, return the diagnostics to the caller, else execute.
-        if !success {
-            return Err(diagnostics);
-        }
-
-        let mut result = match self.execute(
-            contract_identifier,
-            &mut ast,
+        self.analyse_and_execute(
+            ast,
+            diagnostics,
+            success,
             snippet,
-            analysis,
+            contract_identifier,
             cost_track,
             eval_hooks,
-        ) {
-            Ok(result) => result,
-            Err((_, Some(diagnostic), _)) => {
-                diagnostics.push(diagnostic);
-                return Err(diagnostics);
-            }
-            Err((e, _, _)) => {
-                diagnostics.push(Diagnostic {
-                    level: Level::Error,
-                    message: format!("Runtime Error: {}", e),
-                    spans: vec![],
-                    suggestion: None,
-                });
-                return Err(diagnostics);
-            }
-        };
-
-        result.diagnostics = diagnostics;
-
-        // todo: instead of just returning the value, we should be returning:
-        // - value
-        // - execution cost
-        // - events emitted
-        Ok(result)
+        )
     }
 
     pub fn run_ast<'a, 'hooks>(
         &'a mut self,
+        ast: ContractAST,
+        snippet: String,
+        contract_identifier: QualifiedContractIdentifier,
+        cost_track: bool,
+        eval_hooks: Option<Vec<&mut dyn EvalHook>>,
+    ) -> Result<ExecutionResult, Vec<Diagnostic>> {
+        self.analyse_and_execute(
+            ast,
+            vec![],
+            true,
+            snippet,
+            contract_identifier,
+            cost_track,
+            eval_hooks,
+        )
+    }
+
+    // Analyse and execute a parsed contract, `diagnostics` being the ones of
+    // the parser. The analysis passes of the diagnostics returned are recorded,
+    // see `get_diagnostic_passes`.
+    fn analyse_and_execute<'hooks>(
+        &mut self,
         mut ast: ContractAST,
+        diagnostics: Vec<Diagnostic>,
+        success: bool,
         snippet: String,
         contract_identifier: QualifiedContractIdentifier,
         cost_track: bool,
         eval_hooks: Option<Vec<&mut dyn EvalHook>>,
     ) -> Result<ExecutionResult, Vec<Diagnostic>> {
-        let (annotations, mut diagnostics) = self.collect_annotations(&ast, &snippet);
+        let mut diagnostics: Vec<TaggedDiagnostic> =
+            diagnostics.into_iter().map(|d| (None, d)).collect();
+        let (annotations, annotation_diagnostics) = self.collect_annotations(&ast, &snippet);
+        diagnostics.extend(annotation_diagnostics.into_iter().map(|d| (None, d)));
+        if let Err(fork_diagnostics) = self.fork_dependencies(&contract_identifier, &ast) {
+            diagnostics.extend(fork_diagnostics.into_iter().map(|d| (None, d)));
+            return Err(self.record_diagnostic_passes(&contract_identifier, diagnostics));
+        }
 
         let (analysis, mut analysis_diagnostics) =
-            match self.run_analysis(contract_identifier.clone(), &mut ast, &annotations) {
+            match self.run_tagged_analysis(contract_identifier.clone(), &mut ast, &annotations) {
                 Ok((analysis, diagnostics)) => (analysis, diagnostics),
                 Err((_, Some(diagnostic), _)) => {
                     diagnostics.push(diagnostic);
-                    return Err(diagnostics);
+                    return Err(self.record_diagnostic_passes(&contract_identifier, diagnostics));
+                }
+                Err(_) => {
+                    return Err(self.record_diagnostic_passes(&contract_identifier, diagnostics))
                 }
-                Err(_) => return Err(diagnostics),
             };
         diagnostics.append(&mut analysis_diagnostics);
 
+        // If the parser or analysis failed, return the diagnostics to the caller, else execute.
+        // Warnings denied by the analysis rules are reported as errors, and fail the analysis.
+        if !success || has_errors(&diagnostics) {
+            return Err(self.record_diagnostic_passes(&contract_identifier, diagnostics));
+        }
+
         let mut result = match self.execute(
-            contract_identifier,
+            contract_identifier.clone(),
             &mut ast,
             snippet,
             analysis,
@@ -220,21 +251,24 @@ impl ClarityInterpreter {
         ) {
             Ok(result) => result,
             Err((_, Some(diagnostic), _)) => {
-                diagnostics.push(diagnostic);
-                return Err(diagnostics);
+                diagnostics.push((None, diagnostic));
+                return Err(self.record_diagnostic_passes(&contract_identifier, diagnostics));
             }
             Err((e, _, _)) => {
-                diagnostics.push(Diagnostic {
-                    level: Level::Error,
-                    message: format!("Runtime Error: {}", e),
-                    spans: vec![],
-                    suggestion: None,
-                });
-                return Err(diagnostics);
+                diagnostics.push((
+                    None,
+                    Diagnostic {
+                        level: Level::Error,
+                        message: format!("Runtime Error: {}", e),
+                        spans: vec![],
+                        suggestion: None,
+                    },
+                ));
+                return Err(self.record_diagnostic_passes(&contract_identifier, diagnostics));
             }
         };
 
-        result.diagnostics = diagnostics;
+        result.diagnostics = self.record_diagnostic_passes(&contract_identifier, diagnostics);
 
         // todo: instead of just returning the value, we should be returning:
         // - value
@@ -243,6 +277,27 @@ impl ClarityInterpreter {
         Ok(result)
     }
 
+    fn record_diagnostic_passes(
+        &mut self,
+        contract_identifier: &QualifiedContractIdentifier,
+        diagnostics: Vec<TaggedDiagnostic>,
+    ) -> Vec<Diagnostic> {
+        let (passes, diagnostics) = diagnostics.into_iter().unzip();
+        self.diagnostic_passes
+            .insert(contract_identifier.clone(), passes);
+        diagnostics
+    }
+
+    /// Names of the analysis passes which reported the diagnostics returned by
+    /// the last run of a contract, in the same order. `None` for the diagnostics
+    /// reported by the parser, the Clarity analysis or the execution.
+    pub fn get_diagnostic_passes(
+        &self,
+        contract_identifier: &QualifiedContractIdentifier,
+    ) -> Option<&Vec<Option<&'static str>>> {
+        self.diagnostic_passes.get(contract_identifier)
+    }
+
     pub fn detect_dependencies(
         &mut self,
         contract_id: String,
@@ -277,6 +332,49 @@ impl ClarityInterpreter {
         Ok(dependencies)
     }
 
+    /// In fork mode, deploy the contracts called by `ast` which are missing
+    /// from the datastore, using their source on the remote chain.
+    fn fork_dependencies(
+        &mut self,
+        contract_identifier: &QualifiedContractIdentifier,
+        ast: &ContractAST,
+    ) -> Result<(), Vec<Diagnostic>> {
+        if self.datastore.get_remote().is_none() {
+            return Ok(());
+        }
+        let mut contract_map = HashMap::new();
+        contract_map.insert(contract_identifier.clone(), ast.clone());
+        let unresolved =
+            match ASTDependencyDetector::detect_dependencies(&contract_map, &BTreeMap::new()) {
+                Ok(_) => return Ok(()),
+                Err((_, unresolved)) => unresolved,
+            };
+        let missing: BTreeSet<QualifiedContractIdentifier> = unresolved
+            .into_iter()
+            .filter(|dependency| !self.datastore.has_contract(dependency))
+            .collect();
+        for dependency in missing {
+            let source = match self.datastore.get_remote_contract_source(&dependency) {
+                Some(source) => source,
+                None => continue,
+            };
+            let tx_sender = self.tx_sender.clone();
+            self.tx_sender = dependency.issuer.clone();
+            let result = self.run(source, dependency.clone(), false, None);
+            self.tx_sender = tx_sender;
+            if let Err(mut diagnostics) = result {
+                diagnostics.push(Diagnostic {
+                    level: Level::Error,
+                    message: format!("unable to fork {}", dependency),
+                    spans: vec![],
+                    suggestion: None,
+                });
+                return Err(diagnostics);
+            }
+        }
+        Ok(())
+    }
+
     pub fn build_ast(
         &self,
         contract_identifier: QualifiedContractIdentifier,
@@ -286,8 +384,8 @@ impl ClarityInterpreter {
             &contract_identifier,
             &snippet,
             &mut (),
-            self.repl_settings.clarity_version,
-            self.repl_settings.epoch,
+            self.clarity_version,
+            self.epoch,
         )
     }
 
@@ -343,6 +441,25 @@ impl ClarityInterpreter {
         annotations: &Vec<Annotation>,
     ) -> Result<(ContractAnalysis, Vec<Diagnostic>), (String, Option<Diagnostic>, Option<Error>)>
     {
+        match self.run_tagged_analysis(contract_identifier, contract_ast, annotations) {
+            Ok((analysis, diagnostics)) => {
+                Ok((analysis, diagnostics.into_iter().map(|(_, d)| d).collect()))
+            }
+            Err((e, diagnostic, error)) => Err((e, diagnostic.map(|(_, d)| d), error)),
+        }
+    }
+
+    // Run the Clarity analysis and the analyses of the REPL, each diagnostic
+    // being tagged with the name of the pass which reported it
+    fn run_tagged_analysis(
+        &mut self,
+        contract_identifier: QualifiedContractIdentifier,
+        contract_ast: &mut ContractAST,
+        annotations: &Vec<Annotation>,
+    ) -> Result<
+        (ContractAnalysis, Vec<TaggedDiagnostic>),
+        (String, Option<TaggedDiagnostic>, Option<Error>),
+    > {
         let mut analysis_db = AnalysisDatabase::new(&mut self.datastore);
 
         // Run standard clarity analyses
@@ -352,26 +469,58 @@ impl ClarityInterpreter {
             &mut analysis_db,
             false,
             LimitedCostTracker::new_free(),
-            self.repl_settings.clarity_version,
+            self.clarity_version,
         ) {
             Ok(res) => res,
             Err((error, cost_tracker)) => {
-                return Err(("Analysis".to_string(), Some(error.diagnostic), None));
+                return Err(("Analysis".to_string(), Some((None, error.diagnostic)), None));
             }
         };
 
         // Run REPL-only analyses
-        match analysis::run_analysis(
+        let result = analysis::run_analysis(
             &mut contract_analysis,
             &mut analysis_db,
             annotations,
             &self.repl_settings.analysis,
-        ) {
-            Ok(diagnostics) => Ok((contract_analysis, diagnostics)),
-            Err(mut diagnostics) => {
-                // The last diagnostic should be the error
-                let error = diagnostics.pop().unwrap();
-                Err(("Analysis".to_string(), Some(error), None))
+        );
+        let (success, analysis_diagnostics) = match result {
+            Ok(diagnostics) => (true, diagnostics),
+            Err(diagnostics) => (false, diagnostics),
+        };
+        let mut diagnostics: Vec<TaggedDiagnostic> = analysis_diagnostics
+            .into_iter()
+            .map(|(name, diagnostic)| (Some(name), diagnostic))
+            .collect();
+        if success {
+            Ok((contract_analysis, diagnostics))
+        } else {
+            // The last diagnostic should be the error
+            let error = diagnostics.pop().unwrap();
+            Err(("Analysis".to_string(), Some(error), None))
+        }
+    }
+
+    /// Store the analysis of a contract without deploying it, so that the contracts
+    /// depending on it can be analysed.
+    pub fn insert_contract_analysis(
+        &mut self,
+        contract_identifier: &QualifiedContractIdentifier,
+        contract_analysis: &ContractAnalysis,
+    ) -> Result<(), String> {
+        let mut analysis_db = AnalysisDatabase::new(&mut self.datastore);
+        analysis_db.begin();
+        match analysis_db.insert_contract(contract_identifier, contract_analysis) {
+            Ok(_) => {
+                analysis_db.commit();
+                Ok(())
+            }
+            Err(e) => {
+                analysis_db.roll_back();
+                Err(format!(
+                    "unable to insert analysis of {}: {:?}",
+                    contract_identifier, e
+                ))
             }
         }
     }
@@ -386,10 +535,8 @@ impl ClarityInterpreter {
         mainnet: bool,
     ) {
         {
-            let mut contract_context = ContractContext::new(
-                contract_identifier.clone(),
-                self.repl_settings.clarity_version,
-            );
+            let mut contract_context =
+                ContractContext::new(contract_identifier.clone(), self.clarity_version);
 
             let conn = ClarityDatabase::new(
                 &mut self.datastore,
@@ -403,7 +550,7 @@ impl ClarityInterpreter {
                 clarity::consts::CHAIN_ID_TESTNET,
                 conn,
                 cost_tracker,
-                self.repl_settings.epoch,
+                self.epoch,
             );
             global_context.begin();
 
@@ -448,10 +595,8 @@ impl ClarityInterpreter {
         let mut serialized_events = vec![];
         let mut accounts_to_debit = vec![];
         let mut accounts_to_credit = vec![];
-        let mut contract_context = ContractContext::new(
-            contract_identifier.clone(),
-            self.repl_settings.clarity_version,
-        );
+        let mut contract_context =
+            ContractContext::new(contract_identifier.clone(), self.clarity_version);
 
         let (eval_result, eval_hooks) = {
             let mut conn = ClarityDatabase::new(
@@ -461,7 +606,7 @@ impl ClarityInterpreter {
             );
             let tx_sender: PrincipalData = self.tx_sender.clone().into();
             conn.begin();
-            conn.set_clarity_epoch_version(self.repl_settings.epoch);
+            conn.set_clarity_epoch_version(self.epoch);
             conn.commit();
             let cost_tracker = if cost_track {
                 LimitedCostTracker::new(
@@ -469,19 +614,14 @@ impl ClarityInterpreter {
                     CHAIN_ID_TESTNET,
                     BLOCK_LIMIT_MAINNET.clone(),
                     &mut conn,
-                    self.repl_settings.epoch,
+                    self.epoch,
                 )
                 .expect("failed to initialize cost tracker")
             } else {
                 LimitedCostTracker::new_free()
             };
-            let mut global_context = GlobalContext::new(
-                false,
-                CHAIN_ID_TESTNET,
-                conn,
-                cost_tracker,
-                self.repl_settings.epoch,
-            );
+            let mut global_context =
+                GlobalContext::new(false, CHAIN_ID_TESTNET, conn, cost_tracker, self.epoch);
 
             if let Some(mut in_hooks) = eval_hooks {
                 let mut hooks: Vec<&mut dyn EvalHook> = Vec::new();
@@ -757,6 +897,16 @@ impl ClarityInterpreter {
             let _ = analysis_db
                 .execute(|db| db.insert_contract(&contract_identifier, &contract_analysis))
                 .expect("Unable to save data");
+
+            // A contract deployed on the remote chain keeps the data it has there,
+            // the lookup being cached for the dependencies forked beforehand
+            if self
+                .datastore
+                .get_remote_contract_source(&contract_identifier)
+                .is_some()
+            {
+                self.datastore.fork_contract(&contract_identifier);
+            }
         }
 
         Ok(execution_result)
@@ -779,7 +929,7 @@ impl ClarityInterpreter {
                 CHAIN_ID_TESTNET,
                 conn,
                 LimitedCostTracker::new_free(),
-                self.repl_settings.epoch,
+                self.epoch,
             );
             global_context.begin();
             let mut cur_balance = global_context.database.get_stx_balance_snapshot(&recipient);
@@ -797,6 +947,81 @@ impl ClarityInterpreter {
         Ok(format!("→ {}: {} µSTX", recipient, final_balance))
     }
 
+    pub fn get_data_var(
+        &mut self,
+        contract_identifier: &QualifiedContractIdentifier,
+        var_name: &str,
+    ) -> Result<Value, String> {
+        self.read_database(|db| {
+            db.lookup_variable_unknown_descriptor(contract_identifier, var_name)
+        })
+    }
+
+    pub fn get_map_entry(
+        &mut self,
+        contract_identifier: &QualifiedContractIdentifier,
+        map_name: &str,
+        key: &Value,
+    ) -> Result<Value, String> {
+        self.read_database(|db| {
+            db.fetch_entry_unknown_descriptor(contract_identifier, map_name, key)
+        })
+    }
+
+    /// Entries of a map at the current chain tip, ordered by serialized key.
+    pub fn get_map_entries(
+        &mut self,
+        contract_identifier: &QualifiedContractIdentifier,
+        map_name: &str,
+    ) -> Result<Vec<(Value, Value)>, String> {
+        let mut entries = vec![];
+        for key in self.datastore.get_map_keys(contract_identifier, map_name) {
+            let key = Value::try_deserialize_hex_untyped(&key)
+                .map_err(|e| format!("unable to deserialize key {}: {}", key, e))?;
+            // The deleted entries are kept in the store, as `none`
+            if let Some(value) = self
+                .get_map_entry(contract_identifier, map_name, &key)?
+                .expect_optional()
+            {
+                entries.push((key, value));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Owner of a non-fungible token, as returned by `nft-get-owner?`.
+    pub fn get_nft_owner(
+        &mut self,
+        contract_identifier: &QualifiedContractIdentifier,
+        asset_name: &str,
+        asset: &Value,
+    ) -> Result<Value, String> {
+        let key_type = types::TypeSignature::type_of(asset);
+        self.read_database(|db| {
+            match db.get_nft_owner(contract_identifier, asset_name, asset, &key_type) {
+                Ok(owner) => Ok(Value::some(Value::Principal(owner))?),
+                Err(Error::Runtime(RuntimeErrorType::NoSuchToken, _)) => Ok(Value::none()),
+                Err(e) => Err(e),
+            }
+        })
+    }
+
+    // Read from the database at the current chain tip, without writing to it
+    fn read_database<F>(&mut self, read: F) -> Result<Value, String>
+    where
+        F: FnOnce(&mut ClarityDatabase) -> Result<Value, Error>,
+    {
+        let mut db = ClarityDatabase::new(
+            &mut self.datastore,
+            &self.burn_datastore,
+            &self.burn_datastore,
+        );
+        db.begin();
+        let result = read(&mut db);
+        db.roll_back();
+        result.map_err(|e| e.to_string())
+    }
+
     pub fn set_tx_sender(&mut self, tx_sender: StandardPrincipalData) {
         self.tx_sender = tx_sender;
     }
@@ -805,15 +1030,109 @@ impl ClarityInterpreter {
         self.tx_sender.clone()
     }
 
+    pub fn get_epoch(&self) -> StacksEpochId {
+        self.epoch
+    }
+
+    /// Execute the next transactions in `epoch`, the contracts being deployed
+    /// with the default Clarity version of this epoch.
+    pub fn set_epoch(&mut self, epoch: StacksEpochId) {
+        self.epoch = epoch;
+        self.clarity_version = ClarityVersion::default_for_epoch(epoch);
+    }
+
+    pub fn get_clarity_version(&self) -> ClarityVersion {
+        self.clarity_version
+    }
+
+    pub fn set_clarity_version(&mut self, clarity_version: ClarityVersion) {
+        self.clarity_version = clarity_version;
+    }
+
     pub fn advance_chain_tip(&mut self, count: u32) -> u32 {
+        let height = self.datastore.get_open_chain_tip_height();
+        let changed = match self.assets_history.values().next_back() {
+            Some((accounts, tokens)) => *accounts != self.accounts || *tokens != self.tokens,
+            None => !self.tokens.is_empty(),
+        };
+        if changed {
+            self.assets_history
+                .insert(height, (self.accounts.clone(), self.tokens.clone()));
+        }
         self.burn_datastore.advance_chain_tip(count);
-        self.datastore.advance_chain_tip(count)
+        let new_height = self.datastore.advance_chain_tip(count);
+
+        // Simulate the 2.05 -> 2.1 transition when the chain reaches its height
+        if let Some(start_height) = self.repl_settings.epoch_21_start_height {
+            if height < start_height
+                && new_height >= start_height
+                && self.epoch < StacksEpochId::Epoch21
+            {
+                self.set_epoch(StacksEpochId::Epoch21);
+            }
+        }
+        new_height
+    }
+
+    /// Reset the chain to the end of the block at `height`, the transactions
+    /// submitted next being added to this block.
+    pub fn rewind(&mut self, height: u32) -> Result<u32, String> {
+        if height == self.datastore.get_open_chain_tip_height() {
+            return Ok(height);
+        }
+        self.datastore.rewind(height)?;
+        self.burn_datastore.set_chain_height(height);
+        let (accounts, tokens) = match self.assets_history.range(..=height).next_back() {
+            Some((_, (accounts, tokens))) => (accounts.clone(), tokens.clone()),
+            None => (BTreeSet::new(), BTreeMap::new()),
+        };
+        self.accounts = accounts;
+        self.tokens = tokens;
+        // The assets maps of the block at `height` are recorded again when it is closed
+        self.assets_history.retain(|block, _| *block < height);
+        Ok(height)
     }
 
     pub fn get_block_height(&mut self) -> u32 {
         self.datastore.get_current_block_height()
     }
 
+    pub fn snapshot(&self) -> InterpreterSnapshot {
+        InterpreterSnapshot {
+            datastore: self.datastore.snapshot(),
+            tx_sender: self.tx_sender.to_address(),
+            accounts: self.accounts.clone(),
+            tokens: self.tokens.clone(),
+            assets_history: self.assets_history.clone(),
+            epoch: Some(self.epoch),
+            clarity_version: Some(self.clarity_version),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &InterpreterSnapshot) -> Result<(), String> {
+        let tx_sender = PrincipalData::parse_standard_principal(&snapshot.tx_sender)
+            .map_err(|e| format!("unable to parse the tx-sender: {}", e))?;
+        let remote = self.datastore.get_remote();
+        self.datastore = Datastore::restore(&snapshot.datastore)?;
+        if let Some(remote) = remote {
+            self.datastore.set_remote(remote);
+        }
+        // The burn chain advances along with the stacks chain
+        let height = self.datastore.get_open_chain_tip_height();
+        self.burn_datastore.set_chain_height(height);
+        self.tx_sender = tx_sender;
+        self.accounts = snapshot.accounts.clone();
+        self.tokens = snapshot.tokens.clone();
+        self.assets_history = snapshot.assets_history.clone();
+        if let Some(epoch) = snapshot.epoch {
+            self.epoch = epoch;
+        }
+        if let Some(clarity_version) = snapshot.clarity_version {
+            self.clarity_version = clarity_version;
+        }
+        Ok(())
+    }
+
     fn credit_token(&mut self, account: String, token: String, value: u128) {
         self.accounts.insert(account.clone());
         match self.tokens.entry(token) {
@@ -873,6 +1192,12 @@ impl ClarityInterpreter {
     }
 }
 
+fn has_errors(diagnostics: &[TaggedDiagnostic]) -> bool {
+    diagnostics
+        .iter()
+        .any(|(_, diagnostic)| matches!(diagnostic.level, Level::Error))
+}
+
 pub fn serialize_event(event: &StacksTransactionEvent) -> serde_json::Value {
     match event {
         StacksTransactionEvent::SmartContractEvent(event_data) => json!({
@@ -1,9 +1,11 @@
 use super::boot::{STACKS_BOOT_CODE_MAINNET, STACKS_BOOT_CODE_TESTNET};
 use super::diagnostic::output_diagnostic;
+use super::interpreter::InterpreterSnapshot;
 use super::ClarityInterpreter;
 use crate::analysis::ast_dependency_detector::{ASTDependencyDetector, Dependency};
 use crate::analysis::coverage::{self, TestCoverageReport};
 use crate::repl::settings::InitialContract;
+use crate::utils::value_to_string;
 use ansi_term::{Colour, Style};
 use clarity::codec::StacksMessageCodec;
 use clarity::types::chainstate::StacksAddress;
@@ -62,6 +64,15 @@ pub struct CostsReport {
     pub cost_result: CostSynthesis,
 }
 
+/// State of the chain of a session, which can be saved to a file and restored
+/// instead of replaying the deployment plan. The ASTs of the contracts are not
+/// included, they are rebuilt from the sources of the contracts when restored.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    interpreter: InterpreterSnapshot,
+    contracts: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
 #[derive(Clone, Debug)]
 pub struct Session {
     session_id: u32,
@@ -77,6 +88,9 @@ pub struct Session {
     pub initial_contracts_analysis: Vec<(ContractAnalysis, String, String)>,
     pub show_costs: bool,
     pub executed: Vec<String>,
+    // Branches of the chain created with `new_branch`, other than the current one
+    branches: BTreeMap<String, SessionSnapshot>,
+    branch: String,
 }
 
 impl Session {
@@ -104,6 +118,8 @@ impl Session {
             show_costs: false,
             settings,
             executed: Vec::new(),
+            branches: BTreeMap::new(),
+            branch: "main".to_string(),
         }
     }
 
@@ -398,9 +414,17 @@ impl Session {
             cmd if cmd.starts_with("::get_costs") => self.get_costs(&mut output, cmd),
             cmd if cmd.starts_with("::get_contracts") => self.get_contracts(&mut output),
             cmd if cmd.starts_with("::get_block_height") => self.get_block_height(&mut output),
+            cmd if cmd.starts_with("::get_data_var") => self.get_data_var(&mut output, cmd),
+            cmd if cmd.starts_with("::get_map_entry") => self.get_map_entry(&mut output, cmd),
+            cmd if cmd.starts_with("::list_map_entries") => self.list_map_entries(&mut output, cmd),
+            cmd if cmd.starts_with("::get_nft_owner") => self.get_nft_owner(&mut output, cmd),
             cmd if cmd.starts_with("::advance_chain_tip") => {
                 self.parse_and_advance_chain_tip(&mut output, cmd)
             }
+            cmd if cmd.starts_with("::rewind") => self.parse_and_rewind(&mut output, cmd),
+            cmd if cmd.starts_with("::set_epoch") => self.parse_and_set_epoch(&mut output, cmd),
+            cmd if cmd.starts_with("::branch") => self.parse_and_branch(&mut output, cmd),
+            cmd if cmd.starts_with("::switch") => self.parse_and_switch(&mut output, cmd),
             cmd if cmd.starts_with("::toggle_costs") => self.toggle_costs(&mut output),
             cmd if cmd.starts_with("::encode") => self.encode(&mut output, cmd),
             cmd if cmd.starts_with("::decode") => self.decode(&mut output, cmd),
@@ -412,6 +436,10 @@ impl Session {
             cmd if cmd.starts_with("::reload") => self.reload(&mut output),
             #[cfg(feature = "cli")]
             cmd if cmd.starts_with("::read") => self.read(&mut output, cmd),
+            #[cfg(feature = "cli")]
+            cmd if cmd.starts_with("::save_state") => self.parse_and_save_state(&mut output, cmd),
+            #[cfg(feature = "cli")]
+            cmd if cmd.starts_with("::load_state") => self.parse_and_load_state(&mut output, cmd),
 
             snippet => self.run_snippet(&mut output, self.show_costs, snippet),
         }
@@ -662,6 +690,80 @@ impl Session {
         self.run_snippet(output, self.show_costs, &snippet.to_string());
     }
 
+    pub fn snapshot(&self) -> SessionSnapshot {
+        SessionSnapshot {
+            interpreter: self.interpreter.snapshot(),
+            contracts: self.contracts.clone(),
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &SessionSnapshot) -> Result<(), String> {
+        self.interpreter.restore(&snapshot.interpreter)?;
+        self.contracts = snapshot.contracts.clone();
+        self.rebuild_asts();
+        Ok(())
+    }
+
+    // Build the ASTs of the contracts deployed on the chain from their sources
+    fn rebuild_asts(&mut self) {
+        self.asts.clear();
+        for contract in self.contracts.keys() {
+            let contract_id = match QualifiedContractIdentifier::parse(contract) {
+                Ok(contract_id) => contract_id,
+                Err(_) => continue,
+            };
+            let source = match self.interpreter.datastore.get_contract_src(&contract_id) {
+                Some(source) => source,
+                None => continue,
+            };
+            let (ast, _, _) = self.interpreter.build_ast(contract_id.clone(), source);
+            self.asts.insert(contract_id, ast);
+        }
+    }
+
+    #[cfg(feature = "cli")]
+    pub fn save_state(&self, path: &str) -> Result<(), String> {
+        let bytes = serde_json::to_vec(&self.snapshot())
+            .map_err(|e| format!("unable to serialize the state: {}", e))?;
+        fs::write(path, bytes).map_err(|e| format!("unable to write {}: {}", path, e))
+    }
+
+    #[cfg(feature = "cli")]
+    pub fn load_state(&mut self, path: &str) -> Result<(), String> {
+        let bytes = fs::read(path).map_err(|e| format!("unable to read {}: {}", path, e))?;
+        let snapshot: SessionSnapshot = serde_json::from_slice(&bytes)
+            .map_err(|e| format!("unable to parse the state in {}: {}", path, e))?;
+        self.restore(&snapshot)
+    }
+
+    #[cfg(feature = "cli")]
+    fn parse_and_save_state(&mut self, output: &mut Vec<String>, cmd: &str) {
+        let path = match cmd.split_once(" ") {
+            Some((_, path)) => path.trim(),
+            _ => return output.push(red!("Usage: ::save_state <filename>")),
+        };
+        match self.save_state(path) {
+            Ok(()) => output.push(green!(format!("State saved to {}", path))),
+            Err(e) => output.push(red!(e)),
+        }
+    }
+
+    #[cfg(feature = "cli")]
+    fn parse_and_load_state(&mut self, output: &mut Vec<String>, cmd: &str) {
+        let path = match cmd.split_once(" ") {
+            Some((_, path)) => path.trim(),
+            _ => return output.push(red!("Usage: ::load_state <filename>")),
+        };
+        match self.load_state(path) {
+            Ok(()) => output.push(green!(format!(
+                "State loaded from {}, current height: {}",
+                path,
+                self.interpreter.get_block_height()
+            ))),
+            Err(e) => output.push(red!(e)),
+        }
+    }
+
     pub fn formatted_interpretation_ast<'hooks>(
         &mut self,
         snippet: String,
@@ -1000,6 +1102,42 @@ impl Session {
             "{}",
             help_colour.paint("::advance_chain_tip <count>\t\tSimulate mining of <count> blocks")
         ));
+        output.push(format!(
+            "{}",
+            help_colour.paint("::rewind <height>\t\t\tReset the chain to the block at <height>")
+        ));
+        output.push(format!(
+            "{}",
+            help_colour.paint("::branch <name>\t\t\t\tStart a new branch of the chain")
+        ));
+        output.push(format!(
+            "{}",
+            help_colour.paint("::switch <name>\t\t\t\tSwitch to another branch of the chain")
+        ));
+        output.push(format!(
+            "{}",
+            help_colour.paint("::set_epoch <epoch>\t\t\tSet the epoch (2.0, 2.05 or 2.1)")
+        ));
+        output.push(format!(
+            "{}",
+            help_colour
+                .paint("::get_data_var <contract> <var>\t\tGet the value of a data variable")
+        ));
+        output.push(format!(
+            "{}",
+            help_colour
+                .paint("::get_map_entry <contract> <map> <key>\tGet the entry of a map for <key>")
+        ));
+        output.push(format!(
+            "{}",
+            help_colour.paint("::list_map_entries <contract> <map>\tList the entries of a map")
+        ));
+        output.push(format!(
+            "{}",
+            help_colour.paint(
+                "::get_nft_owner <contract> <asset> <id>\tGet the owner of a non-fungible token"
+            )
+        ));
         output.push(format!(
             "{}",
             help_colour.paint("::toggle_costs\t\t\t\tDisplay cost analysis after every expression")
@@ -1021,6 +1159,15 @@ impl Session {
             "{}",
             help_colour.paint("::read <filename>\t\t\tRead expressions from a file")
         ));
+        output.push(format!(
+            "{}",
+            help_colour.paint("::save_state <filename>\t\tSave the state of the chain to a file")
+        ));
+        output.push(format!(
+            "{}",
+            help_colour
+                .paint("::load_state <filename>\t\tRestore the state of the chain from a file")
+        ));
     }
 
     fn parse_and_advance_chain_tip(&mut self, output: &mut Vec<String>, command: &str) {
@@ -1050,6 +1197,129 @@ impl Session {
         self.interpreter.advance_chain_tip(count)
     }
 
+    fn parse_and_rewind(&mut self, output: &mut Vec<String>, command: &str) {
+        let args: Vec<_> = command.split(' ').collect();
+
+        if args.len() != 2 {
+            output.push(red!("Usage: ::rewind <height>"));
+            return;
+        }
+
+        let height = match args[1].parse::<u32>() {
+            Ok(height) => height,
+            _ => {
+                output.push(red!("Unable to parse height"));
+                return;
+            }
+        };
+
+        match self.rewind(height) {
+            Ok(height) => output.push(green!(format!("Chain rewound to height {}", height))),
+            Err(e) => output.push(red!(e)),
+        }
+    }
+
+    /// Reset the chain to the end of the block at `height`, discarding the
+    /// blocks after it and the contracts deployed in them.
+    pub fn rewind(&mut self, height: u32) -> Result<u32, String> {
+        self.interpreter.rewind(height)?;
+        self.discard_missing_contracts();
+        Ok(height)
+    }
+
+    fn parse_and_branch(&mut self, output: &mut Vec<String>, command: &str) {
+        let args: Vec<_> = command.split(' ').collect();
+
+        if args.len() != 2 {
+            output.push(red!("Usage: ::branch <name>"));
+            return;
+        }
+
+        match self.new_branch(args[1]) {
+            Ok(()) => output.push(green!(format!(
+                "Branch {} created at height {}",
+                args[1],
+                self.interpreter.get_block_height()
+            ))),
+            Err(e) => output.push(red!(e)),
+        }
+    }
+
+    /// Start the branch `name` of the chain from the current state. The
+    /// current branch is kept, and can be restored with `switch`.
+    pub fn new_branch(&mut self, name: &str) -> Result<(), String> {
+        if name == self.branch || self.branches.contains_key(name) {
+            return Err(format!("branch {} already exists", name));
+        }
+        self.branches.insert(self.branch.clone(), self.snapshot());
+        self.branch = name.to_string();
+        Ok(())
+    }
+
+    fn parse_and_switch(&mut self, output: &mut Vec<String>, command: &str) {
+        let args: Vec<_> = command.split(' ').collect();
+
+        if args.len() != 2 {
+            output.push(red!("Usage: ::switch <name>"));
+            return;
+        }
+
+        match self.switch(args[1]) {
+            Ok(()) => output.push(green!(format!(
+                "Switched to branch {}, current height: {}",
+                args[1],
+                self.interpreter.get_block_height()
+            ))),
+            Err(e) => output.push(red!(e)),
+        }
+    }
+
+    /// Switch to the branch `name` of the chain, keeping the current one.
+    pub fn switch(&mut self, name: &str) -> Result<(), String> {
+        if name == self.branch {
+            return Ok(());
+        }
+        let snapshot = match self.branches.get(name) {
+            Some(snapshot) => snapshot.clone(),
+            None => return Err(format!("unknown branch {}", name)),
+        };
+        // The contracts of the branch, and their ASTs, are restored along with its chain
+        let current = self.snapshot();
+        self.restore(&snapshot)?;
+        self.branches.remove(name);
+        self.branches.insert(self.branch.clone(), current);
+        self.branch = name.to_string();
+        Ok(())
+    }
+
+    fn parse_and_set_epoch(&mut self, output: &mut Vec<String>, command: &str) {
+        let args: Vec<_> = command.split(' ').collect();
+
+        if args.len() != 2 {
+            output.push(red!("Usage: ::set_epoch <epoch>"));
+            return;
+        }
+
+        match parse_epoch(args[1]) {
+            Ok(epoch) => {
+                self.interpreter.set_epoch(epoch);
+                output.push(green!(format!("Epoch updated to {}", args[1])));
+            }
+            Err(e) => output.push(red!(e)),
+        }
+    }
+
+    // Forget the contracts which are not deployed on the current chain
+    fn discard_missing_contracts(&mut self) {
+        let datastore = &self.interpreter.datastore;
+        self.contracts.retain(|contract, _| {
+            QualifiedContractIdentifier::parse(contract)
+                .map_or(false, |contract| datastore.has_contract(&contract))
+        });
+        self.asts
+            .retain(|contract, _| datastore.has_contract(contract));
+    }
+
     fn parse_and_set_tx_sender(&mut self, output: &mut Vec<String>, command: &str) {
         let args: Vec<_> = command.split(' ').collect();
 
@@ -1161,6 +1431,101 @@ impl Session {
         output.push(green!(format!("{}", value)));
     }
 
+    // Parse a contract identifier, in which `.contract` refers to a contract
+    // deployed by the tx-sender
+    fn parse_contract_identifier(
+        &self,
+        contract: &str,
+    ) -> Result<QualifiedContractIdentifier, String> {
+        let contract_id = match contract.strip_prefix('.') {
+            Some(name) => format!("{}.{}", self.get_tx_sender(), name),
+            None => contract.to_string(),
+        };
+        QualifiedContractIdentifier::parse(&contract_id)
+            .map_err(|_| format!("Unable to parse the contract identifier {}", contract))
+    }
+
+    // Evaluate the expression `snippet` to a value
+    fn evaluate_value(&mut self, snippet: &str) -> Result<Value, String> {
+        match self.interpret(snippet.to_string(), None, None, false, None, None) {
+            Ok(result) => match result.result {
+                EvaluationResult::Snippet(snippet_result) => Ok(snippet_result.result),
+                EvaluationResult::Contract(_) => Err(format!("{} is not a value", snippet)),
+            },
+            Err(_) => Err(format!("Unable to evaluate {}", snippet)),
+        }
+    }
+
+    pub fn get_data_var(&mut self, output: &mut Vec<String>, cmd: &str) {
+        let args: Vec<_> = cmd.split_whitespace().collect();
+        if args.len() != 3 {
+            return output.push(red!("Usage: ::get_data_var <contract> <var>"));
+        }
+        let value = self
+            .parse_contract_identifier(args[1])
+            .and_then(|contract_id| self.interpreter.get_data_var(&contract_id, args[2]));
+        match value {
+            Ok(value) => output.push(green!(value_to_string(&value))),
+            Err(e) => output.push(red!(e)),
+        }
+    }
+
+    pub fn get_map_entry(&mut self, output: &mut Vec<String>, cmd: &str) {
+        let args: Vec<_> = cmd.splitn(4, ' ').collect();
+        if args.len() != 4 {
+            return output.push(red!("Usage: ::get_map_entry <contract> <map> <key>"));
+        }
+        let value = self
+            .parse_contract_identifier(args[1])
+            .and_then(|contract_id| {
+                let key = self.evaluate_value(args[3])?;
+                self.interpreter.get_map_entry(&contract_id, args[2], &key)
+            });
+        match value {
+            Ok(value) => output.push(green!(value_to_string(&value))),
+            Err(e) => output.push(red!(e)),
+        }
+    }
+
+    pub fn list_map_entries(&mut self, output: &mut Vec<String>, cmd: &str) {
+        let args: Vec<_> = cmd.split_whitespace().collect();
+        if args.len() != 3 {
+            return output.push(red!("Usage: ::list_map_entries <contract> <map>"));
+        }
+        let entries = self
+            .parse_contract_identifier(args[1])
+            .and_then(|contract_id| self.interpreter.get_map_entries(&contract_id, args[2]));
+        match entries {
+            Ok(entries) => {
+                for (key, value) in entries.iter() {
+                    output.push(green!(format!(
+                        "{} → {}",
+                        value_to_string(key),
+                        value_to_string(value)
+                    )));
+                }
+            }
+            Err(e) => output.push(red!(e)),
+        }
+    }
+
+    pub fn get_nft_owner(&mut self, output: &mut Vec<String>, cmd: &str) {
+        let args: Vec<_> = cmd.splitn(4, ' ').collect();
+        if args.len() != 4 {
+            return output.push(red!("Usage: ::get_nft_owner <contract> <asset> <id>"));
+        }
+        let owner = self
+            .parse_contract_identifier(args[1])
+            .and_then(|contract_id| {
+                let id = self.evaluate_value(args[3])?;
+                self.interpreter.get_nft_owner(&contract_id, args[2], &id)
+            });
+        match owner {
+            Ok(owner) => output.push(green!(value_to_string(&owner))),
+            Err(e) => output.push(red!(e)),
+        }
+    }
+
     pub fn get_costs(&mut self, output: &mut Vec<String>, cmd: &str) {
         let snippet = cmd.to_string().split_off("::get_costs ".len());
         self.run_snippet(output, true, &snippet.to_string());
@@ -1376,6 +1741,18 @@ fn decode_hex(byteString: &str) -> Result<Vec<u8>, DecodeHexError> {
     }
 }
 
+fn parse_epoch(epoch: &str) -> Result<StacksEpochId, String> {
+    match epoch {
+        "2.0" => Ok(StacksEpochId::Epoch20),
+        "2.05" => Ok(StacksEpochId::Epoch2_05),
+        "2.1" => Ok(StacksEpochId::Epoch21),
+        _ => Err(format!(
+            "unsupported epoch {}, expected 2.0, 2.05 or 2.1",
+            epoch
+        )),
+    }
+}
+
 fn build_api_reference() -> HashMap<String, String> {
     let mut api_reference = HashMap::new();
     for func in NativeFunctions::ALL.iter() {
@@ -1423,6 +1800,11 @@ fn build_api_reference() -> HashMap<String, String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::repl::datastore::{RemoteAccount, RemoteDatastore};
+    use clarity::vm::database::ClaritySerializable;
+    use clarity::vm::types::{PrincipalData, QualifiedContractIdentifier, Value};
+    use clarity::vm::ClarityVersion;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn encode_simple() {
@@ -1567,4 +1949,422 @@ mod tests {
         );
         assert_eq!(session.handle_command("(at-block (unwrap-panic (get-block-info? id-header-hash u10000)) (contract-call? .contract-2 get-x))")[0], green!("u1"));
     }
+
+    #[test]
+    fn restore_snapshot() {
+        let mut session = Session::new(SessionSettings::default());
+        session.handle_command(
+            "
+            (define-data-var x uint u0)
+
+            (define-read-only (get-x)
+                (var-get x))
+
+            (define-public (incr)
+                (begin
+                    (var-set x (+ (var-get x) u1))
+                    (ok (var-get x))))",
+        );
+        session.handle_command("(contract-call? .contract-0 incr)");
+        session.advance_chain_tip(10);
+
+        let snapshot = serde_json::to_string(&session.snapshot()).unwrap();
+
+        session.handle_command("(contract-call? .contract-0 incr)");
+        session.advance_chain_tip(10);
+        assert_eq!(
+            session.handle_command("(contract-call? .contract-0 get-x)")[0],
+            green!("u2")
+        );
+
+        // A new session, e.g. loading the state from a file, gets the ASTs of the contracts back
+        let snapshot: SessionSnapshot = serde_json::from_str(&snapshot).unwrap();
+        let mut session = Session::new(SessionSettings::default());
+        session.restore(&snapshot).unwrap();
+        let contract_id = QualifiedContractIdentifier::parse(&format!(
+            "{}.contract-0",
+            StacksAddress::burn_address(false)
+        ))
+        .unwrap();
+        assert_eq!(session.asts.len(), 1);
+        assert!(session.asts.contains_key(&contract_id));
+        assert_eq!(
+            session.handle_command("(contract-call? .contract-0 get-x)")[0],
+            green!("u1")
+        );
+        assert_eq!(session.interpreter.get_block_height(), 10);
+        assert_eq!(session.handle_command("(at-block (unwrap-panic (get-block-info? id-header-hash u0)) (contract-call? .contract-0 get-x))")[0], green!("u0"));
+    }
+
+    #[test]
+    fn rewind_and_switch_branches() {
+        let mut session = Session::new(SessionSettings::default());
+        session.handle_command(
+            "
+            (define-data-var x uint u0)
+
+            (define-read-only (get-x)
+                (var-get x))
+
+            (define-public (incr)
+                (begin
+                    (var-set x (+ (var-get x) u1))
+                    (ok (var-get x))))",
+        );
+        session.advance_chain_tip(5);
+        session.handle_command("(contract-call? .contract-0 incr)");
+        session.handle_command("::mint_stx ST1SJ3DTE5DN7X54YDH5D64R3BCB6A2AG2ZQ8YPD5 1000");
+        session.advance_chain_tip(5);
+        session.handle_command("(define-read-only (get-y) u1)");
+        assert_eq!(session.contracts.len(), 2);
+
+        assert_eq!(
+            session.handle_command("::rewind 5")[0],
+            green!("Chain rewound to height 5")
+        );
+        assert_eq!(session.interpreter.get_block_height(), 5);
+        assert_eq!(session.contracts.len(), 1);
+        assert_eq!(
+            session.handle_command("(contract-call? .contract-0 get-x)")[0],
+            green!("u1")
+        );
+        assert_eq!(session.interpreter.get_accounts().len(), 1);
+
+        session.handle_command("::rewind 0");
+        assert_eq!(
+            session.handle_command("(contract-call? .contract-0 get-x)")[0],
+            green!("u0")
+        );
+        assert!(session.interpreter.get_accounts().is_empty());
+        assert_eq!(
+            session.handle_command("::rewind 10")[0],
+            red!("unable to rewind to height 10, the chain tip is at height 0")
+        );
+
+        // Each branch keeps its own chain from the common ancestor
+        assert_eq!(
+            session.handle_command("::branch reorg")[0],
+            green!("Branch reorg created at height 0")
+        );
+        session.handle_command("(contract-call? .contract-0 incr)");
+        session.advance_chain_tip(3);
+        assert_eq!(
+            session.handle_command("::switch main")[0],
+            green!("Switched to branch main, current height: 0")
+        );
+        assert_eq!(
+            session.handle_command("(contract-call? .contract-0 get-x)")[0],
+            green!("u0")
+        );
+        session.handle_command("::switch reorg");
+        assert_eq!(session.interpreter.get_block_height(), 3);
+        assert_eq!(
+            session.handle_command("(contract-call? .contract-0 get-x)")[0],
+            green!("u1")
+        );
+        assert_eq!(
+            session.handle_command("::branch main")[0],
+            red!("branch main already exists")
+        );
+        assert_eq!(
+            session.handle_command("::switch other")[0],
+            red!("unknown branch other")
+        );
+    }
+
+    #[test]
+    fn switch_branches_with_contracts() {
+        let mut session = Session::new(SessionSettings::default());
+        session.handle_command("::branch a");
+        session.advance_chain_tip(1);
+        session.handle_command("(define-read-only (get-x) u1)");
+        let contract_id = QualifiedContractIdentifier::parse(&format!(
+            "{}.contract-0",
+            StacksAddress::burn_address(false)
+        ))
+        .unwrap();
+        assert!(session.asts.contains_key(&contract_id));
+
+        // The contract deployed on branch a is not deployed on branch b
+        session.handle_command("::branch b");
+        session.handle_command("::rewind 0");
+        assert!(session.contracts.is_empty());
+        assert!(session.asts.is_empty());
+
+        // Switching back to branch a gets it back
+        assert_eq!(
+            session.handle_command("::switch a")[0],
+            green!("Switched to branch a, current height: 1")
+        );
+        assert_eq!(session.contracts.len(), 1);
+        assert!(session.asts.contains_key(&contract_id));
+        assert_eq!(
+            session.handle_command("(contract-call? .contract-0 get-x)")[0],
+            green!("u1")
+        );
+
+        session.handle_command("::switch b");
+        assert!(session.contracts.is_empty());
+        assert!(session.asts.is_empty());
+    }
+
+    #[test]
+    fn switch_epoch() {
+        let mut session = Session::new(SessionSettings::default());
+        assert_eq!(
+            session.handle_command("::set_epoch 2.05")[0],
+            green!("Epoch updated to 2.05")
+        );
+        let memo = "
+            (define-public (send (recipient principal))
+                (stx-transfer-memo? u1 tx-sender recipient 0x01))";
+
+        // stx-transfer-memo? is not available in Clarity 1
+        session.handle_command(memo);
+        assert!(session.contracts.is_empty());
+        session.handle_command(
+            "
+            (define-data-var x uint u0)
+
+            (define-public (incr)
+                (begin
+                    (var-set x (+ (var-get x) u1))
+                    (ok (var-get x))))",
+        );
+        session.advance_chain_tip(10);
+
+        session.handle_command("::set_epoch 2.1");
+        assert_eq!(session.interpreter.get_epoch(), StacksEpochId::Epoch21);
+        assert_eq!(
+            session.interpreter.get_clarity_version(),
+            ClarityVersion::Clarity2
+        );
+        assert_eq!(
+            session.handle_command("(contract-call? .contract-0 incr)")[0],
+            green!("(ok u1)")
+        );
+        session.handle_command(memo);
+        assert_eq!(session.contracts.len(), 2);
+
+        assert_eq!(
+            session.handle_command("::set_epoch 3.0")[0],
+            red!("unsupported epoch 3.0, expected 2.0, 2.05 or 2.1")
+        );
+    }
+
+    #[test]
+    fn diagnostic_passes() {
+        let mut settings = SessionSettings::default();
+        settings
+            .repl_settings
+            .analysis
+            .set_passes(vec![crate::analysis::Pass::UnusedChecker]);
+        let mut session = Session::new(settings);
+        let result = session
+            .interpret(
+                ";; #[bogus]
+(define-constant ERR (err u1))
+(define-read-only (get-one) u1)"
+                    .to_string(),
+                Some("contract".to_string()),
+                None,
+                false,
+                None,
+                None,
+            )
+            .unwrap();
+        let contract_id = match result.result {
+            EvaluationResult::Contract(ref contract_result) => {
+                QualifiedContractIdentifier::parse(&contract_result.contract.contract_identifier)
+                    .unwrap()
+            }
+            _ => panic!("expected a contract result"),
+        };
+
+        // The annotation is reported by the interpreter, the constant by the pass
+        let passes = session
+            .interpreter
+            .get_diagnostic_passes(&contract_id)
+            .unwrap();
+        assert_eq!(result.diagnostics.len(), 2);
+        assert_eq!(passes, &vec![None, Some("unused_checker")]);
+    }
+
+    #[test]
+    fn switch_epoch_at_height() {
+        let mut settings = SessionSettings::default();
+        settings.repl_settings.epoch = StacksEpochId::Epoch2_05;
+        settings.repl_settings.epoch_21_start_height = Some(5);
+        let mut session = Session::new(settings);
+
+        session.advance_chain_tip(4);
+        assert_eq!(session.interpreter.get_epoch(), StacksEpochId::Epoch2_05);
+        session.advance_chain_tip(3);
+        assert_eq!(session.interpreter.get_epoch(), StacksEpochId::Epoch21);
+        assert_eq!(
+            session.interpreter.get_clarity_version(),
+            ClarityVersion::Clarity2
+        );
+
+        // The transition only happens once
+        session.handle_command("::set_epoch 2.05");
+        session.advance_chain_tip(1);
+        assert_eq!(session.interpreter.get_epoch(), StacksEpochId::Epoch2_05);
+    }
+
+    #[test]
+    fn inspect_contract_storage() {
+        let mut session = Session::new(SessionSettings::default());
+        session.handle_command(
+            "
+            (define-data-var count uint u3)
+            (define-map owners uint { owner: principal, amount: uint })
+            (define-non-fungible-token ticket uint)
+
+            (map-set owners u1 { owner: tx-sender, amount: u10 })
+            (map-set owners u2 { owner: tx-sender, amount: u20 })
+            (map-set owners u3 { owner: tx-sender, amount: u30 })
+            (map-delete owners u2)
+            (nft-mint? ticket u1 tx-sender)",
+        );
+        let tx_sender = Value::Principal(
+            PrincipalData::parse_standard_principal(&session.get_tx_sender())
+                .unwrap()
+                .into(),
+        );
+
+        assert_eq!(
+            session.handle_command("::get_data_var .contract-0 count")[0],
+            green!("u3")
+        );
+        assert_eq!(
+            session.handle_command("::get_map_entry .contract-0 owners u1")[0],
+            green!(format!(
+                "(some {{amount: u10, owner: {}}})",
+                value_to_string(&tx_sender)
+            ))
+        );
+        assert_eq!(
+            session.handle_command("::get_map_entry .contract-0 owners u2")[0],
+            green!("none")
+        );
+
+        let entries = session.handle_command("::list_map_entries .contract-0 owners");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[1],
+            green!(format!(
+                "u3 → {{amount: u30, owner: {}}}",
+                value_to_string(&tx_sender)
+            ))
+        );
+
+        assert_eq!(
+            session.handle_command("::get_nft_owner .contract-0 ticket u1")[0],
+            green!(format!("(some {})", value_to_string(&tx_sender)))
+        );
+        assert_eq!(
+            session.handle_command("::get_nft_owner .contract-0 ticket u2")[0],
+            green!("none")
+        );
+        assert_eq!(
+            session.handle_command("::get_data_var .contract-0")[0],
+            red!("Usage: ::get_data_var <contract> <var>")
+        );
+    }
+
+    #[derive(Debug)]
+    struct RemoteCounter {
+        contract: QualifiedContractIdentifier,
+        source_lookups: Mutex<u32>,
+    }
+
+    impl RemoteDatastore for RemoteCounter {
+        fn get_data_var(
+            &self,
+            contract: &QualifiedContractIdentifier,
+            var_name: &str,
+        ) -> Option<String> {
+            match (contract == &self.contract, var_name) {
+                (true, "count") => Some(Value::UInt(41).serialize()),
+                _ => None,
+            }
+        }
+
+        fn get_map_entry(
+            &self,
+            contract: &QualifiedContractIdentifier,
+            _map_name: &str,
+            key: &str,
+        ) -> Option<String> {
+            if contract == &self.contract && key == Value::UInt(1).serialize() {
+                let owner = Value::Principal(contract.issuer.clone().into());
+                Some(Value::some(owner).unwrap().serialize())
+            } else {
+                None
+            }
+        }
+
+        fn get_account(&self, _principal: &PrincipalData) -> Option<RemoteAccount> {
+            None
+        }
+
+        fn get_contract_source(&self, contract: &QualifiedContractIdentifier) -> Option<String> {
+            *self.source_lookups.lock().unwrap() += 1;
+            if contract != &self.contract {
+                return None;
+            }
+            Some(
+                "
+                (define-data-var count uint u0)
+                (define-map owners uint principal)
+
+                (define-read-only (get-count)
+                    (var-get count))
+
+                (define-read-only (get-owner (id uint))
+                    (map-get? owners id))
+
+                (define-public (incr)
+                    (begin
+                        (var-set count (+ (var-get count) u1))
+                        (ok (var-get count))))"
+                    .to_string(),
+            )
+        }
+    }
+
+    #[test]
+    fn fork_remote_contract() {
+        let contract = "SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7.counter";
+        let remote = Arc::new(RemoteCounter {
+            contract: QualifiedContractIdentifier::parse(contract).unwrap(),
+            source_lookups: Mutex::new(0),
+        });
+        let mut session = Session::new(SessionSettings::default());
+        session.interpreter.datastore.set_remote(remote.clone());
+
+        let get_count = format!("(contract-call? '{} get-count)", contract);
+        assert_eq!(session.handle_command(&get_count)[0], green!("u41"));
+        // The source fetched to fork the contract is reused when saving it
+        assert_eq!(*remote.source_lookups.lock().unwrap(), 1);
+        assert_eq!(
+            session.handle_command(&format!(
+                "(is-some (contract-call? '{} get-owner u1))",
+                contract
+            ))[0],
+            green!("true")
+        );
+        assert_eq!(
+            session.handle_command(&format!(
+                "(is-some (contract-call? '{} get-owner u2))",
+                contract
+            ))[0],
+            green!("false")
+        );
+
+        // Local writes shadow the remote data
+        session.handle_command(&format!("(contract-call? '{} incr)", contract));
+        assert_eq!(session.handle_command(&get_count)[0], green!("u42"));
+    }
 }
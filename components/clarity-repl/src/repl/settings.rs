@@ -66,6 +66,22 @@ pub struct Settings {
     pub costs_version: u32,
     pub clarity_version: ClarityVersion,
     pub epoch: StacksEpochId,
+    /// Height from which the session switches to epoch 2.1, when the chain
+    /// is advanced past it
+    pub epoch_21_start_height: Option<u32>,
+    pub fork: Option<ForkSettings>,
+}
+
+/// Remote Stacks node from which the data of the contracts deployed on its
+/// chain is read, see `Datastore::set_remote`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ForkSettings {
+    pub node_url: String,
+    /// Index block hash of the block at which the data is read, the tip of
+    /// the node when the session starts by default
+    pub block: Option<String>,
+    /// Directory in which the values fetched from the node are cached
+    pub cache_dir: Option<String>,
 }
 
 impl Default for Settings {
@@ -75,6 +91,8 @@ impl Default for Settings {
             costs_version: DEFAULT_COSTS_VERSION,
             clarity_version: ClarityVersion::latest(),
             epoch: StacksEpochId::Epoch2_05, // TODO(brice): Once 2.1 is live, use `::latest()`
+            epoch_21_start_height: None,
+            fork: None,
         }
     }
 }
@@ -85,6 +103,8 @@ pub struct SettingsFile {
     pub costs_version: Option<u32>,
     pub clarity_version: Option<ClarityVersion>,
     pub epoch: Option<StacksEpochId>,
+    pub epoch_21_start_height: Option<u32>,
+    pub fork: Option<ForkSettings>,
 }
 
 impl From<SettingsFile> for Settings {
@@ -99,6 +119,8 @@ impl From<SettingsFile> for Settings {
             costs_version: file.costs_version.unwrap_or(DEFAULT_COSTS_VERSION),
             clarity_version: file.clarity_version.unwrap_or(ClarityVersion::latest()),
             epoch: file.epoch.unwrap_or(StacksEpochId::latest()),
+            epoch_21_start_height: file.epoch_21_start_height,
+            fork: file.fork,
         }
     }
 }
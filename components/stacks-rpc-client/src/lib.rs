@@ -8,6 +8,8 @@ extern crate serde_derive;
 #[macro_use]
 extern crate serde_json;
 
+pub mod node_datastore;
 pub mod rpc_client;
 
+pub use node_datastore::StacksNodeDatastore;
 pub use rpc_client::{PoxInfo, StacksRpc};
@@ -0,0 +1,287 @@
+use crate::rpc_client::{RpcError, StacksRpc};
+use clarity_repl::clarity::types::chainstate::{BlockHeaderHash, ConsensusHash, StacksBlockId};
+use clarity_repl::clarity::util::hash::Sha512Trunc256Sum;
+use clarity_repl::clarity::vm::types::{PrincipalData, QualifiedContractIdentifier};
+use clarity_repl::repl::datastore::{RemoteAccount, RemoteDatastore};
+use clarity_repl::repl::settings::ForkSettings;
+use std::fs;
+use std::path::PathBuf;
+
+/// Reads the state of a chain from a Stacks node, pinned to a block, for the
+/// sessions in fork mode. The values fetched are cached on disk when a cache
+/// directory is set.
+#[derive(Debug)]
+pub struct StacksNodeDatastore {
+    rpc: StacksRpc,
+    cache_dir: Option<PathBuf>,
+}
+
+impl StacksNodeDatastore {
+    pub fn new(settings: &ForkSettings) -> Result<StacksNodeDatastore, String> {
+        let rpc = StacksRpc::new(&settings.node_url);
+        let tip = match settings.block {
+            Some(ref block) => block.trim_start_matches("0x").to_string(),
+            None => get_index_block_hash(&rpc)?,
+        };
+        let cache_dir = settings
+            .cache_dir
+            .as_ref()
+            .map(|cache_dir| PathBuf::from(cache_dir).join(&tip));
+        Ok(StacksNodeDatastore {
+            rpc: rpc.pinned_at(&tip),
+            cache_dir,
+        })
+    }
+
+    // Read the result of `request` from the cache, or fetch it. The block being
+    // pinned, the missing values are cached as well.
+    fn cached<F>(&self, request: &str, fetch: F) -> Option<String>
+    where
+        F: FnOnce(&StacksRpc) -> Result<String, RpcError>,
+    {
+        let path = self.cache_dir.as_ref().map(|cache_dir| {
+            let hash = Sha512Trunc256Sum::from_data(request.as_bytes());
+            cache_dir.join(format!("{}.json", hash.to_hex()))
+        });
+        if let Some(ref path) = path {
+            if let Ok(Ok(value)) =
+                fs::read_to_string(path).map(|cached| serde_json::from_str(&cached))
+            {
+                return value;
+            }
+        }
+
+        let value = match fetch(&self.rpc) {
+            Ok(value) => Some(value),
+            Err(RpcError::NotFound) => None,
+            Err(RpcError::Generic) => {
+                eprintln!("unable to fetch {} from {}", request, self.rpc.url);
+                return None;
+            }
+        };
+        if let Some(path) = path {
+            let cached = serde_json::to_string(&value).unwrap();
+            let _ = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| fs::write(&path, cached));
+        }
+        value
+    }
+}
+
+// The index block hash of the tip of the node
+fn get_index_block_hash(rpc: &StacksRpc) -> Result<String, String> {
+    let info = rpc
+        .get_info()
+        .map_err(|e| format!("unable to get the tip of {}: {:?}", rpc.url, e))?;
+    let consensus_hash =
+        ConsensusHash::from_hex(info.stacks_tip_consensus_hash.trim_start_matches("0x"))
+            .map_err(|e| format!("invalid consensus hash: {:?}", e))?;
+    let block_hash = BlockHeaderHash::from_hex(info.stacks_tip.trim_start_matches("0x"))
+        .map_err(|e| format!("invalid block hash: {:?}", e))?;
+    Ok(StacksBlockId::new(&consensus_hash, &block_hash).to_hex())
+}
+
+impl RemoteDatastore for StacksNodeDatastore {
+    fn get_data_var(
+        &self,
+        contract: &QualifiedContractIdentifier,
+        var_name: &str,
+    ) -> Option<String> {
+        let request = format!("data_var/{}/{}", contract, var_name);
+        self.cached(&request, |rpc| {
+            rpc.get_data_var(
+                &contract.issuer.to_address(),
+                contract.name.as_str(),
+                var_name,
+            )
+        })
+        .map(|value| value.trim_start_matches("0x").to_string())
+    }
+
+    fn get_map_entry(
+        &self,
+        contract: &QualifiedContractIdentifier,
+        map_name: &str,
+        key: &str,
+    ) -> Option<String> {
+        let request = format!("map_entry/{}/{}/{}", contract, map_name, key);
+        self.cached(&request, |rpc| {
+            rpc.get_map_entry(
+                &contract.issuer.to_address(),
+                contract.name.as_str(),
+                map_name,
+                &format!("0x{}", key),
+            )
+        })
+        .map(|value| value.trim_start_matches("0x").to_string())
+    }
+
+    fn get_account(&self, principal: &PrincipalData) -> Option<RemoteAccount> {
+        let request = format!("accounts/{}", principal);
+        let account = self.cached(&request, |rpc| {
+            let balance = rpc.get_account(&principal.to_string())?;
+            Ok(format!("{}:{}", balance.balance, balance.nonce))
+        })?;
+        let (balance, nonce) = account.split_once(':')?;
+        Some(RemoteAccount {
+            balance: u128::from_str_radix(balance.trim_start_matches("0x"), 16).ok()?,
+            nonce: nonce.parse().ok()?,
+        })
+    }
+
+    fn get_contract_source(&self, contract: &QualifiedContractIdentifier) -> Option<String> {
+        let request = format!("contracts/source/{}", contract);
+        self.cached(&request, |rpc| {
+            rpc.get_contract_source(&contract.issuer.to_address(), contract.name.as_str())
+                .map(|contract| contract.source)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    const TIP: &str = "75ccaa2b8e4b9b4a7c2b5e6b1d2f3a4b5c6d7e8f90a1b2c3d4e5f60718293a4b";
+
+    // Serve the responses of a Stacks node on a local port, recording the
+    // requests received.
+    fn mock_node(requests: Arc<Mutex<Vec<String>>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut content_length = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = header.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let path = request_line.split_whitespace().nth(1).unwrap().to_string();
+                requests.lock().unwrap().push(path.clone());
+
+                let (status, body) = match path.split('?').next().unwrap() {
+                    "/v2/data_var/SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7/counter/count" => {
+                        ("200 OK", r#"{"data":"0x0100000000000000000000000000000029"}"#.to_string())
+                    }
+                    "/v2/map_entry/SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7/counter/owners" => {
+                        assert_eq!(
+                            String::from_utf8(body).unwrap(),
+                            r#""0x0100000000000000000000000000000001""#
+                        );
+                        ("200 OK", r#"{"data":"0x0a0516a0b1c2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0"}"#.to_string())
+                    }
+                    "/v2/accounts/SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7" => (
+                        "200 OK",
+                        r#"{"balance":"0x000000000000000000000000000003e8","locked":"0x00000000000000000000000000000000","unlock_height":0,"nonce":7}"#.to_string(),
+                    ),
+                    "/v2/contracts/source/SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7/counter" => (
+                        "200 OK",
+                        r#"{"source":"(define-data-var count uint u0)","publish_height":1}"#.to_string(),
+                    ),
+                    _ => ("404 Not Found", "".to_string()),
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        url
+    }
+
+    fn new_datastore(url: &str, cache_dir: Option<PathBuf>) -> StacksNodeDatastore {
+        StacksNodeDatastore::new(&ForkSettings {
+            node_url: url.to_string(),
+            block: Some(TIP.to_string()),
+            cache_dir: cache_dir.map(|dir| dir.to_str().unwrap().to_string()),
+        })
+        .unwrap()
+    }
+
+    fn counter() -> QualifiedContractIdentifier {
+        QualifiedContractIdentifier::parse("SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7.counter")
+            .unwrap()
+    }
+
+    #[test]
+    fn read_pinned_state() {
+        let requests = Arc::new(Mutex::new(vec![]));
+        let url = mock_node(requests.clone());
+        let datastore = new_datastore(&url, None);
+
+        assert_eq!(
+            datastore.get_data_var(&counter(), "count"),
+            Some("0100000000000000000000000000000029".to_string())
+        );
+        assert_eq!(
+            datastore.get_map_entry(&counter(), "owners", "0100000000000000000000000000000001"),
+            Some("0a0516a0b1c2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0".to_string())
+        );
+        assert_eq!(datastore.get_data_var(&counter(), "total"), None);
+
+        let account = datastore
+            .get_account(
+                &PrincipalData::parse("SP2J6ZY48GV1EZ5V2V5RB9MP66SW86PYKKNRV9EJ7").unwrap(),
+            )
+            .unwrap();
+        assert_eq!(account.balance, 1000);
+        assert_eq!(account.nonce, 7);
+
+        assert_eq!(
+            datastore.get_contract_source(&counter()),
+            Some("(define-data-var count uint u0)".to_string())
+        );
+
+        let requests = requests.lock().unwrap();
+        assert!(requests
+            .iter()
+            .all(|path| path.contains(&format!("tip={}", TIP))));
+    }
+
+    #[test]
+    fn cache_fetched_values() {
+        let cache_dir =
+            std::env::temp_dir().join(format!("stacks-node-datastore-{}", std::process::id()));
+        let requests = Arc::new(Mutex::new(vec![]));
+        let url = mock_node(requests.clone());
+
+        let datastore = new_datastore(&url, Some(cache_dir.clone()));
+        datastore.get_data_var(&counter(), "count");
+        datastore.get_data_var(&counter(), "total");
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        // A new datastore reads the values, including the missing one, from the cache
+        let datastore = new_datastore(&url, Some(cache_dir.clone()));
+        assert_eq!(
+            datastore.get_data_var(&counter(), "count"),
+            Some("0100000000000000000000000000000029".to_string())
+        );
+        assert_eq!(datastore.get_data_var(&counter(), "total"), None);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        let _ = fs::remove_dir_all(cache_dir);
+    }
+}
@@ -3,6 +3,7 @@ use clarity_repl::clarity::util::hash::{bytes_to_hex, hex_bytes};
 use clarity_repl::clarity::vm::types::Value;
 
 use reqwest::blocking::Client;
+use reqwest::StatusCode;
 use std::io::Cursor;
 
 use clarinet_utils::transactions::StacksTransaction;
@@ -10,11 +11,16 @@ use clarinet_utils::transactions::StacksTransaction;
 #[derive(Debug)]
 pub enum RpcError {
     Generic,
+    NotFound,
 }
 
+#[derive(Debug)]
 pub struct StacksRpc {
     pub url: String,
     pub client: Client,
+    /// Index block hash of the block at which the contracts and accounts are
+    /// read, the tip of the node if unset
+    pub tip: Option<String>,
 }
 
 pub struct PostTransactionResult {
@@ -78,10 +84,17 @@ pub struct PoxCycle {
 pub struct Balance {
     pub balance: String,
     pub nonce: u64,
+    #[serde(default)]
     pub balance_proof: String,
+    #[serde(default)]
     pub nonce_proof: String,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct ClarityData {
+    pub data: String,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Contract {
     pub source: String,
@@ -93,6 +106,19 @@ impl StacksRpc {
         Self {
             url: url.into(),
             client: Client::builder().build().unwrap(),
+            tip: None,
+        }
+    }
+
+    pub fn pinned_at(mut self, tip: &str) -> Self {
+        self.tip = Some(tip.to_string());
+        self
+    }
+
+    fn tip_query(&self) -> String {
+        match self.tip {
+            Some(ref tip) => format!("?proof=0&tip={}", tip),
+            None => "?proof=0".to_string(),
         }
     }
 
@@ -166,13 +192,17 @@ impl StacksRpc {
         contract_name: &str,
     ) -> Result<Contract, RpcError> {
         let request_url = format!(
-            "{}/v2/contracts/source/{}/{}",
-            self.url, principal, contract_name
+            "{}/v2/contracts/source/{}/{}{}",
+            self.url,
+            principal,
+            contract_name,
+            self.tip_query()
         );
 
         let res = self.client.get(&request_url).send();
 
         match res {
+            Ok(response) if response.status() == StatusCode::NOT_FOUND => Err(RpcError::NotFound),
             Ok(response) => match response.json() {
                 Ok(value) => Ok(value),
                 _ => Err(RpcError::Generic),
@@ -181,6 +211,78 @@ impl StacksRpc {
         }
     }
 
+    pub fn get_account(&self, principal: &str) -> Result<Balance, RpcError> {
+        let request_url = format!("{}/v2/accounts/{}{}", self.url, principal, self.tip_query());
+
+        let res = self.client.get(&request_url).send();
+
+        match res {
+            Ok(response) if response.status() == StatusCode::NOT_FOUND => Err(RpcError::NotFound),
+            Ok(response) => match response.json() {
+                Ok(value) => Ok(value),
+                _ => Err(RpcError::Generic),
+            },
+            _ => Err(RpcError::Generic),
+        }
+    }
+
+    /// Get the serialized value of a data variable, in hex.
+    pub fn get_data_var(
+        &self,
+        principal: &str,
+        contract_name: &str,
+        var_name: &str,
+    ) -> Result<String, RpcError> {
+        let request_url = format!(
+            "{}/v2/data_var/{}/{}/{}{}",
+            self.url,
+            principal,
+            contract_name,
+            var_name,
+            self.tip_query()
+        );
+
+        let res = self.client.get(&request_url).send();
+
+        match res {
+            Ok(response) if response.status() == StatusCode::NOT_FOUND => Err(RpcError::NotFound),
+            Ok(response) => match response.json::<ClarityData>() {
+                Ok(value) => Ok(value.data),
+                _ => Err(RpcError::Generic),
+            },
+            _ => Err(RpcError::Generic),
+        }
+    }
+
+    /// Get the serialized entry of a map for the serialized `key`, in hex.
+    pub fn get_map_entry(
+        &self,
+        principal: &str,
+        contract_name: &str,
+        map_name: &str,
+        key: &str,
+    ) -> Result<String, RpcError> {
+        let request_url = format!(
+            "{}/v2/map_entry/{}/{}/{}{}",
+            self.url,
+            principal,
+            contract_name,
+            map_name,
+            self.tip_query()
+        );
+
+        let res = self.client.post(&request_url).json(&key).send();
+
+        match res {
+            Ok(response) if response.status() == StatusCode::NOT_FOUND => Err(RpcError::NotFound),
+            Ok(response) => match response.json::<ClarityData>() {
+                Ok(value) => Ok(value.data),
+                _ => Err(RpcError::Generic),
+            },
+            _ => Err(RpcError::Generic),
+        }
+    }
+
     pub fn call_read_only_fn(
         &self,
         contract_addr: &str,